@@ -0,0 +1,104 @@
+//! Quick scan throughput on a synthetic tree for different walker thread counts.
+//!
+//! Run with `cargo bench --bench quick_scan_walker`. The tree size can be
+//! changed with `WALKER_BENCH_DIRS` (directories, default 2000); every
+//! directory holds 25 small files.
+
+use disk_dominator::app_config::ScannerConfig;
+use disk_dominator::{DiskAnalyzer, DuplicateStrategy, ScanConfig, ScanType, WebSocketManager};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const FILES_PER_DIR: usize = 25;
+const RUNS: usize = 3;
+
+fn scan_config() -> ScanConfig {
+    ScanConfig {
+        exclude_patterns: vec![],
+        include_hidden: false,
+        follow_symlinks: false,
+        max_depth: None,
+        min_file_size: None,
+        max_file_size: None,
+        calculate_hashes: false,
+        quick_hash_threshold: 1024 * 1024,
+        duplicate_strategy: DuplicateStrategy::NameAndSize,
+        large_file_threshold: 100 * 1024 * 1024,
+        same_filesystem: false,
+        use_ignore_files: false,
+    }
+}
+
+/// Spread `dirs` directories over a tree ten wide and three levels deep
+fn build_tree(root: &Path, dirs: usize) {
+    for i in 0..dirs {
+        let dir = root
+            .join(format!("a{}", i % 10))
+            .join(format!("b{}", (i / 10) % 10))
+            .join(format!("c{}", i / 100));
+        std::fs::create_dir_all(&dir).unwrap();
+        for f in 0..FILES_PER_DIR {
+            std::fs::write(dir.join(format!("file{}.dat", f)), [0u8; 64]).unwrap();
+        }
+    }
+}
+
+async fn scan(root: &Path, max_threads: usize) -> (Duration, u64) {
+    let analyzer = DiskAnalyzer::new(Arc::new(WebSocketManager::new()))
+        .with_scanner_config(ScannerConfig { max_threads });
+    let session_id = analyzer
+        .create_scan_session(
+            root.to_string_lossy().to_string(),
+            ScanType::Quick,
+            scan_config(),
+        )
+        .await
+        .unwrap();
+
+    let started = Instant::now();
+    analyzer.start_scan_session(&session_id).await.unwrap();
+    let elapsed = started.elapsed();
+
+    let files = analyzer
+        .get_scan_session(&session_id)
+        .await
+        .and_then(|s| s.results)
+        .map(|r| r.total_files)
+        .unwrap_or_default();
+    (elapsed, files)
+}
+
+fn main() {
+    let dirs = std::env::var("WALKER_BENCH_DIRS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2000);
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    build_tree(temp_dir.path(), dirs);
+    println!(
+        "Synthetic tree: {} directories, {} files",
+        dirs,
+        dirs * FILES_PER_DIR
+    );
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut baseline = None;
+    for threads in [1, 2, 4, 8] {
+        let best = (0..RUNS)
+            .map(|_| runtime.block_on(scan(temp_dir.path(), threads)))
+            .map(|(elapsed, files)| {
+                assert_eq!(files as usize, dirs * FILES_PER_DIR);
+                elapsed
+            })
+            .min()
+            .unwrap();
+        let baseline = *baseline.get_or_insert(best);
+        println!(
+            "{} thread(s): {:>8.1} ms  ({:.2}x)",
+            threads,
+            best.as_secs_f64() * 1000.0,
+            baseline.as_secs_f64() / best.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use crate::hashing::HashAlgorithm;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Name of the user configuration file inside the data directory
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Settings read from `config.toml` (see `docs/config.example.toml`).
+///
+/// Only the sections the backend uses are parsed; anything else in the file
+/// is ignored and missing keys keep their defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AppConfig {
+    #[serde(default)]
+    pub scanner: ScannerConfig,
+    #[serde(default)]
+    pub performance: PerformanceConfig,
+    #[serde(default)]
+    pub watcher: WatcherConfig,
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+    #[serde(default)]
+    pub throttle: ThrottleConfig,
+    #[serde(default)]
+    pub hash_cache: HashCacheConfig,
+    #[serde(default)]
+    pub hashing: HashingConfig,
+}
+
+/// `[scanner]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ScannerConfig {
+    pub max_threads: usize, // Worker threads of the quick scan walker
+}
+
+impl Default for ScannerConfig {
+    fn default() -> Self {
+        Self { max_threads: 4 }
+    }
+}
+
+/// `[performance]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PerformanceConfig {
+    pub max_memory_mb: u64, // Upper bound for the file table of one scan
+}
+
+impl Default for PerformanceConfig {
+    fn default() -> Self {
+        Self { max_memory_mb: 500 }
+    }
+}
+
+/// `[throttle]` section, the starting point for the limits changed from the UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThrottleConfig {
+    pub max_bytes_per_sec: u64, // File contents read for hashing; 0 is unlimited
+    pub max_files_per_sec: u64, // Entries walked plus files hashed; 0 is unlimited
+    pub idle_mode: bool,        // Back off while other programs use the disks, at idle I/O priority
+    pub busy_bytes_per_sec: u64, // Disk traffic of other programs that counts as busy
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes_per_sec: 0,
+            max_files_per_sec: 0,
+            idle_mode: false,
+            busy_bytes_per_sec: 10 * 1024 * 1024,
+        }
+    }
+}
+
+/// `[hash_cache]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HashCacheConfig {
+    pub max_entries: usize, // Files whose hashes are kept; 0 turns the cache off
+}
+
+impl Default for HashCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 1_000_000,
+        }
+    }
+}
+
+/// `[hashing]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HashingConfig {
+    pub algorithm: HashAlgorithm, // Full hashes that confirm duplicates
+    pub prefilter_algorithm: HashAlgorithm, // Hashes of the first and last 64 KB
+}
+
+impl Default for HashingConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: HashAlgorithm::Blake3,
+            prefilter_algorithm: HashAlgorithm::Xxh3,
+        }
+    }
+}
+
+/// `[watcher]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WatcherConfig {
+    pub enabled: bool,           // Watch every scanned root for changes
+    pub coalesce_ms: u64,        // How long to gather changes before applying them
+    pub max_watches: usize,      // Directories watched per root before falling back to polling
+    pub poll_interval_secs: u64, // Time between incremental rescans when polling
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            coalesce_ms: 1000,
+            max_watches: 65536,
+            poll_interval_secs: 300,
+        }
+    }
+}
+
+/// `[scheduler]` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    pub enabled: bool,              // Run scheduled jobs while the app is open
+    pub max_concurrent_jobs: usize, // Jobs allowed to run at the same time
+    pub check_interval_secs: u64,   // Time between checks for due jobs
+    pub history_limit: usize,       // Runs kept in the history of each job
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_concurrent_jobs: 1,
+            check_interval_secs: 30,
+            history_limit: 20,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Load `config.toml` from the app data directory, falling back to defaults
+    pub fn load() -> Self {
+        match crate::app_state::get_data_directory() {
+            Ok(dir) => Self::load_from(&dir.join(CONFIG_FILE)),
+            Err(e) => {
+                tracing::warn!(
+                    "Could not locate data directory, using default config: {}",
+                    e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// Load a config file; a missing or invalid file gives the defaults
+    pub fn load_from(path: &Path) -> Self {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                tracing::warn!(
+                    "Could not read {}, using default config: {}",
+                    path.display(),
+                    e
+                );
+                return Self::default();
+            }
+        };
+
+        toml::from_str(&contents).unwrap_or_else(|e| {
+            tracing::warn!(
+                "Invalid config in {}, using defaults: {}",
+                path.display(),
+                e
+            );
+            Self::default()
+        })
+    }
+}
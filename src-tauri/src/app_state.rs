@@ -1,4 +1,5 @@
 // Temporarily simplified AppState until external modules are available
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -12,18 +13,126 @@ use tokio::sync::RwLock;
 /// Simplified storage struct for basic functionality
 #[derive(Default)]
 pub struct SimpleStorage {
-    // Basic in-memory storage for file scan results
-    pub scan_results: std::collections::HashMap<String, Vec<crate::file_system::FileInfo>>,
+    // Persistent index of file scan results, keyed by scan root
+    pub index: crate::scan_index::ScanIndex,
+    // Checkpoints of scan sessions that have not finished yet
+    pub checkpoints: crate::disk_analyzer::checkpoint::CheckpointStore,
+    // Saved scan profiles of the user
+    pub profiles: crate::scan_profiles::ProfileStore,
+    // Recurring jobs and their run history
+    pub schedules: crate::scheduler::JobStore,
+}
+
+impl SimpleStorage {
+    /// Open the storage kept in the app data directory, falling back to memory only
+    pub fn open() -> Self {
+        let data_dir = match get_data_directory() {
+            Ok(dir) => dir,
+            Err(e) => {
+                tracing::warn!(
+                    "Could not locate data directory, results won't persist: {}",
+                    e
+                );
+                return Self::default();
+            }
+        };
+
+        let index =
+            crate::scan_index::ScanIndex::open(data_dir.join("scan_index")).unwrap_or_else(|e| {
+                tracing::warn!("Could not open scan index, results won't persist: {}", e);
+                Default::default()
+            });
+
+        let checkpoints =
+            crate::disk_analyzer::checkpoint::CheckpointStore::open(data_dir.join("checkpoints"))
+                .unwrap_or_else(|e| {
+                    tracing::warn!(
+                        "Could not open checkpoint store, scans won't be resumable: {}",
+                        e
+                    );
+                    Default::default()
+                });
+
+        let profiles = crate::scan_profiles::ProfileStore::open(
+            data_dir.join(crate::scan_profiles::PROFILES_FILE),
+        )
+        .unwrap_or_else(|e| {
+            tracing::warn!(
+                "Could not open scan profiles, only presets are available: {}",
+                e
+            );
+            Default::default()
+        });
+
+        let schedules =
+            crate::scheduler::JobStore::open(data_dir.join(crate::scheduler::SCHEDULES_FILE))
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Could not open scheduled jobs, none will run: {}", e);
+                    Default::default()
+                });
+
+        Self {
+            index,
+            checkpoints,
+            profiles,
+            schedules,
+        }
+    }
+}
+
+/// Open the hash cache kept in the app data directory, falling back to memory only
+fn open_hash_cache(config: &crate::app_config::HashCacheConfig) -> crate::hash_cache::HashCache {
+    let opened = get_data_directory()
+        .map_err(anyhow::Error::from)
+        .and_then(|dir| {
+            crate::hash_cache::HashCache::open(
+                dir.join(crate::hash_cache::HASH_CACHE_FILE),
+                config.max_entries,
+            )
+        });
+    opened.unwrap_or_else(|e| {
+        tracing::warn!("Could not open hash cache, hashes won't persist: {}", e);
+        crate::hash_cache::HashCache::new(config.max_entries)
+    })
+}
+
+/// Directory where DiskDominator keeps its persistent data
+pub fn get_data_directory() -> std::io::Result<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        let app_data = std::env::var("LOCALAPPDATA")
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::NotFound, e))?;
+        Ok(PathBuf::from(app_data).join("DiskDominator"))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let home = std::env::var("HOME")
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::NotFound, e))?;
+        Ok(PathBuf::from(home)
+            .join(".local")
+            .join("share")
+            .join("diskdominator"))
+    }
 }
 
 /// Central application state - simplified version
 #[derive(Clone)]
 pub struct AppState {
+    pub config: crate::app_config::AppConfig,
     pub storage: Arc<RwLock<SimpleStorage>>,
     pub current_analyzer: Arc<RwLock<Option<crate::disk_analyzer::DiskAnalyzer>>>,
     pub websocket_manager: Arc<crate::websocket::WebSocketManager>,
     pub activity_log:
         Arc<RwLock<std::collections::HashMap<String, crate::commands::home_commands::Activity>>>,
+    /// Keeps indexed roots up to date
+    pub watcher: crate::watcher::WatchManager,
+    /// Runs scheduled jobs
+    pub scheduler: crate::scheduler::Scheduler,
+    /// Scan I/O limits, adjustable live
+    pub throttle: Arc<crate::disk_analyzer::throttle::Throttle>,
+    /// Content hashes reused across scans
+    pub hash_cache: Arc<crate::hash_cache::HashCache>,
     // Commented out until modules are available:
     // pub auth: Arc<RwLock<AuthModule>>,
     // pub i18n: Arc<RwLock<I18nModule>>,
@@ -40,19 +149,54 @@ impl Default for AppState {
 impl AppState {
     pub fn new() -> Self {
         let websocket_manager = Arc::new(crate::websocket::WebSocketManager::new());
-        
+        let config = crate::app_config::AppConfig::load();
+        let storage = Arc::new(RwLock::new(SimpleStorage::open()));
 
         // Don't initialize sample activities here - Tokio runtime isn't ready yet
         // This will be done when the app starts properly
 
         Self {
-            storage: Arc::new(RwLock::new(SimpleStorage::default())),
+            watcher: crate::watcher::WatchManager::new(
+                config.watcher.clone(),
+                storage.clone(),
+                websocket_manager.clone(),
+            ),
+            scheduler: crate::scheduler::Scheduler::new(config.scheduler.clone()),
+            throttle: Arc::new(crate::disk_analyzer::throttle::Throttle::new(
+                config.throttle.clone(),
+            )),
+            hash_cache: Arc::new(open_hash_cache(&config.hash_cache)),
+            config,
+            storage,
             current_analyzer: Arc::new(RwLock::new(None)),
             websocket_manager,
             activity_log: Arc::new(RwLock::new(std::collections::HashMap::new())),
         }
     }
 
+    /// Analyzer shared by all scan sessions, created on first use
+    pub async fn analyzer(&self) -> crate::disk_analyzer::DiskAnalyzer {
+        if let Some(analyzer) = self.current_analyzer.read().await.as_ref() {
+            return analyzer.clone();
+        }
+
+        self.current_analyzer
+            .write()
+            .await
+            .get_or_insert_with(|| {
+                crate::disk_analyzer::DiskAnalyzer::with_storage(
+                    self.websocket_manager.clone(),
+                    self.storage.clone(),
+                )
+                .with_scanner_config(self.config.scanner.clone())
+                .with_performance_config(self.config.performance.clone())
+                .with_hashing_config(self.config.hashing.clone())
+                .with_throttle(self.throttle.clone())
+                .with_hash_cache(self.hash_cache.clone())
+            })
+            .clone()
+    }
+
     /// Add activity to the log
     pub async fn add_activity(&self, activity: crate::commands::home_commands::Activity) {
         let mut log = self.activity_log.write().await;
@@ -0,0 +1,97 @@
+use crate::app_state::AppState;
+use crate::directory_tree::{self, DirectoryNode, TreeOptions, TreemapRect};
+use crate::file_system::SizeBasis;
+use crate::scan_index::IndexedRoot;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::State;
+
+fn tree_options(
+    depth: Option<usize>,
+    min_fraction: Option<f64>,
+    size_basis: Option<SizeBasis>,
+) -> TreeOptions {
+    let defaults = TreeOptions::default();
+    TreeOptions {
+        depth: depth.unwrap_or(defaults.depth),
+        min_fraction: min_fraction.unwrap_or(defaults.min_fraction),
+        size_basis: size_basis.unwrap_or_default(),
+        ..defaults
+    }
+}
+
+fn build_tree(
+    root: &IndexedRoot,
+    path: &str,
+    options: &TreeOptions,
+) -> Result<DirectoryNode, String> {
+    let sizes = root.subtree_sizes(options.size_basis);
+    directory_tree::build_tree(&root.files, sizes, Path::new(path), options)
+        .ok_or_else(|| format!("Path not found in scan results: {}", path))
+}
+
+/// Size tree of a scanned root, built from its stored scan results
+#[tauri::command]
+pub async fn get_directory_tree(
+    root: String,
+    depth: Option<usize>,
+    min_fraction: Option<f64>,
+    size_basis: Option<SizeBasis>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<DirectoryNode, String> {
+    let storage = state.storage.read().await;
+    let indexed = storage
+        .index
+        .get(&root)
+        .ok_or_else(|| format!("No scan results for {}", root))?;
+    build_tree(
+        indexed,
+        &root,
+        &tree_options(depth, min_fraction, size_basis),
+    )
+}
+
+/// Size tree of any directory inside a scanned root, for drilling down
+#[tauri::command]
+pub async fn get_directory_subtree(
+    path: String,
+    depth: Option<usize>,
+    min_fraction: Option<f64>,
+    size_basis: Option<SizeBasis>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<DirectoryNode, String> {
+    let storage = state.storage.read().await;
+    let indexed = storage
+        .index
+        .root_containing(&path)
+        .ok_or_else(|| format!("No scan results cover {}", path))?;
+    build_tree(
+        indexed,
+        &path,
+        &tree_options(depth, min_fraction, size_basis),
+    )
+}
+
+/// Squarified treemap of a directory inside a scanned root, laid out in a `width` by `height` area
+#[tauri::command]
+pub async fn get_treemap_layout(
+    path: String,
+    width: f64,
+    height: f64,
+    depth: Option<usize>,
+    min_fraction: Option<f64>,
+    size_basis: Option<SizeBasis>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<TreemapRect>, String> {
+    let storage = state.storage.read().await;
+    let indexed = storage
+        .index
+        .root_containing(&path)
+        .ok_or_else(|| format!("No scan results cover {}", path))?;
+    let tree = build_tree(
+        indexed,
+        &path,
+        &tree_options(depth, min_fraction, size_basis),
+    )?;
+    Ok(directory_tree::layout_treemap(&tree, width, height))
+}
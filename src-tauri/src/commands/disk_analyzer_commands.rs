@@ -3,11 +3,14 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tauri::State;
 
+use crate::app_config::ThrottleConfig;
 use crate::app_state::AppState;
 use crate::commands::home_commands::{log_activity, ActivityMetadata, ActivityType};
-use crate::disk_analyzer::{
-    DiskAnalyzer, DualScanProgress, DuplicateStrategy, ScanConfig, ScanType,
-};
+use crate::disk_analyzer::checkpoint::ScanCheckpoint;
+use crate::disk_analyzer::scan_errors::ScanErrorReport;
+use crate::disk_analyzer::throttle::ThrottleStatus;
+use crate::disk_analyzer::{DualScanProgress, ScanConfig, ScanType};
+use crate::scan_profiles;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -15,6 +18,7 @@ pub enum DiskScanType {
     Quick,
     Deep,
     Custom,
+    Incremental,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -24,6 +28,14 @@ pub struct ScanRequest {
     pub exclude_patterns: Option<Vec<String>>,
     pub include_hidden: Option<bool>,
     pub calculate_hashes: Option<bool>,
+    pub follow_symlinks: Option<bool>,
+    pub same_filesystem: Option<bool>, // Don't cross mount points
+    pub max_depth: Option<usize>,
+    pub min_file_size: Option<u64>,
+    pub max_file_size: Option<u64>,
+    pub use_ignore_files: Option<bool>, // Honor .gitignore / .ddignore files
+    #[serde(default)]
+    pub profile_id: Option<String>, // Saved profile supplying the settings not given here
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -58,57 +70,53 @@ pub struct SessionControl {
 }
 
 /// Start a disk scan (new implementation)
+///
+/// Settings come from the profile named by `profile_id`, or the platform
+/// defaults without one; options set in the request override either.
 #[tauri::command]
 pub async fn scan_disk_new(
     scan_request: ScanRequest,
     state: State<'_, Arc<AppState>>,
 ) -> Result<ScanResponse, String> {
-    let app_state = state.inner();
-
-    // Check if analyzer exists, create if not
-    let analyzer = {
-        let current_analyzer = app_state.current_analyzer.read().await;
-        if let Some(analyzer) = current_analyzer.as_ref() {
-            analyzer.clone()
-        } else {
-            drop(current_analyzer);
-            let mut current_analyzer = app_state.current_analyzer.write().await;
-            let analyzer = DiskAnalyzer::new(app_state.websocket_manager.clone());
-            *current_analyzer = Some(analyzer.clone());
-            analyzer
-        }
-    };
-
     // Convert scan type
     let scan_type = match scan_request.scan_type {
         DiskScanType::Quick => ScanType::Quick,
         DiskScanType::Deep => ScanType::Deep,
         DiskScanType::Custom => ScanType::Custom,
+        DiskScanType::Incremental => ScanType::Incremental,
     };
 
     // Create scan config
+    let base = match &scan_request.profile_id {
+        Some(id) => {
+            state
+                .storage
+                .read()
+                .await
+                .profiles
+                .get(id)
+                .ok_or_else(|| format!("Scan profile not found: {}", id))?
+                .config
+        }
+        None => scan_profiles::default_config(),
+    };
     let config = ScanConfig {
-        exclude_patterns: scan_request.exclude_patterns.unwrap_or_else(|| {
-            vec![
-                "System Volume Information".to_string(),
-                "$Recycle.Bin".to_string(),
-                "Windows".to_string(),
-                "Program Files".to_string(),
-                "ProgramData".to_string(),
-                "AppData".to_string(),
-                "node_modules".to_string(),
-                ".git".to_string(),
-            ]
-        }),
-        include_hidden: scan_request.include_hidden.unwrap_or(false),
-        follow_symlinks: false,
-        max_depth: None,
-        min_file_size: None,
-        max_file_size: None,
-        calculate_hashes: scan_request.calculate_hashes.unwrap_or(true),
-        quick_hash_threshold: 1024 * 1024, // 1MB
-        duplicate_strategy: DuplicateStrategy::SmartDetection,
-        large_file_threshold: 100 * 1024 * 1024, // 100MB
+        exclude_patterns: scan_request
+            .exclude_patterns
+            .unwrap_or(base.exclude_patterns),
+        include_hidden: scan_request.include_hidden.unwrap_or(base.include_hidden),
+        follow_symlinks: scan_request.follow_symlinks.unwrap_or(base.follow_symlinks),
+        max_depth: scan_request.max_depth.or(base.max_depth),
+        min_file_size: scan_request.min_file_size.or(base.min_file_size),
+        max_file_size: scan_request.max_file_size.or(base.max_file_size),
+        calculate_hashes: scan_request
+            .calculate_hashes
+            .unwrap_or(base.calculate_hashes),
+        same_filesystem: scan_request.same_filesystem.unwrap_or(base.same_filesystem),
+        use_ignore_files: scan_request
+            .use_ignore_files
+            .unwrap_or(base.use_ignore_files),
+        ..base
     };
 
     // Get disk path from disk_id
@@ -118,23 +126,43 @@ pub async fn scan_disk_new(
         scan_request.disk_id.clone()
     };
 
+    let session_id = start_scan(state.inner(), disk_path, scan_type, config).await?;
+
+    Ok(ScanResponse {
+        session_id,
+        message: format!("Escaneo iniciado en disco {}", scan_request.disk_id),
+        success: true,
+    })
+}
+
+/// Create a scan session and run it in the background, returning its id
+pub async fn start_scan(
+    app_state: &Arc<AppState>,
+    disk_path: String,
+    scan_type: ScanType,
+    config: ScanConfig,
+) -> Result<String, String> {
+    let analyzer = app_state.analyzer().await;
+
     // Create scan session
     let session_id = analyzer
-        .create_scan_session(disk_path, scan_type, config)
+        .create_scan_session(disk_path.clone(), scan_type, config)
         .await
         .map_err(|e| e.to_string())?;
 
-    // Start the scan
-    analyzer
-        .start_scan_session(&session_id)
-        .await
-        .map_err(|e| e.to_string())?;
+    // Run the scan in the background so several disks can be scanned at once
+    let scan_session_id = session_id.clone();
+    tokio::spawn(async move {
+        if let Err(e) = analyzer.start_scan_session(&scan_session_id).await {
+            tracing::error!("Scan session {} failed: {}", scan_session_id, e);
+        }
+    });
 
     // Log activity
     log_activity(
         app_state,
         "Escaneo de disco iniciado".to_string(),
-        format!("Iniciando escaneo de disco {}", scan_request.disk_id),
+        format!("Iniciando escaneo de disco {}", disk_path),
         ActivityType::ScanStarted,
         "running".to_string(),
         Some(ActivityMetadata {
@@ -146,11 +174,52 @@ pub async fn scan_disk_new(
     )
     .await;
 
-    Ok(ScanResponse {
-        session_id,
-        message: format!("Escaneo iniciado en disco {}", scan_request.disk_id),
-        success: true,
-    })
+    Ok(session_id)
+}
+
+/// List scan sessions interrupted by a crash or quit and continue them from their last checkpoint
+#[tauri::command]
+pub async fn resume_interrupted_sessions(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<ScanCheckpoint>, String> {
+    let app_state = state.inner();
+    let analyzer = app_state.analyzer().await;
+
+    let restored = analyzer
+        .restore_interrupted_sessions()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if restored.is_empty() {
+        return Ok(restored);
+    }
+
+    log_activity(
+        app_state,
+        "Escaneos reanudados".to_string(),
+        format!("Reanudando {} escaneo(s) interrumpido(s)", restored.len()),
+        ActivityType::ScanStarted,
+        "running".to_string(),
+        Some(ActivityMetadata {
+            size: None,
+            count: Some(restored.len() as u32),
+            duration: None,
+            error: None,
+        }),
+    )
+    .await;
+
+    // Run the restored sessions one after another in the background
+    let session_ids: Vec<String> = restored.iter().map(|c| c.session_id.clone()).collect();
+    tokio::spawn(async move {
+        for session_id in session_ids {
+            if let Err(e) = analyzer.start_scan_session(&session_id).await {
+                tracing::error!("Failed to resume scan session {}: {}", session_id, e);
+            }
+        }
+    });
+
+    Ok(restored)
 }
 
 /// Get scan progress for a session (new implementation)
@@ -175,6 +244,28 @@ pub async fn get_scan_progress_new(
     Ok(None)
 }
 
+/// Get the errors a scan session ran into, grouped by kind
+#[tauri::command]
+pub async fn get_scan_errors(
+    session_id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ScanErrorReport, String> {
+    let app_state = state.inner();
+
+    let analyzer = {
+        let current_analyzer = app_state.current_analyzer.read().await;
+        current_analyzer.as_ref().cloned()
+    };
+
+    match analyzer {
+        Some(analyzer) => analyzer
+            .get_scan_errors(&session_id)
+            .await
+            .ok_or_else(|| format!("Scan session not found: {}", session_id)),
+        None => Err(format!("Scan session not found: {}", session_id)),
+    }
+}
+
 /// Get all disk statuses
 #[tauri::command]
 pub async fn get_disk_statuses(state: State<'_, Arc<AppState>>) -> Result<Vec<DiskStatus>, String> {
@@ -216,55 +307,68 @@ pub async fn get_disk_statuses(state: State<'_, Arc<AppState>>) -> Result<Vec<Di
         // Check if there's an active scan for this disk
         if let Some(ref analyzer) = analyzer {
             let sessions = analyzer.get_active_sessions().await;
-            for session in sessions.values() {
-                if session.disk_path.starts_with(&format!("{}:", disk_id)) {
-                    match session.status {
-                        crate::disk_analyzer::ScanSessionStatus::Running => {
-                            status.status = "scanning".to_string();
-                        }
-                        crate::disk_analyzer::ScanSessionStatus::Paused => {
-                            status.status = "paused".to_string();
-                            status.is_paused = true;
-                        }
-                        crate::disk_analyzer::ScanSessionStatus::Completed => {
-                            status.status = "complete".to_string();
-                            status.can_analyze_duplicates = true;
-                            status.can_organize = true;
-                        }
-                        crate::disk_analyzer::ScanSessionStatus::Error(_) => {
-                            status.status = "error".to_string();
-                        }
-                        _ => {}
+            // Several sessions may target the same disk; report the most recent one
+            let latest = sessions
+                .values()
+                .filter(|s| s.disk_path.starts_with(&format!("{}:", disk_id)))
+                .max_by_key(|s| s.created_at);
+            if let Some(session) = latest {
+                match session.status {
+                    crate::disk_analyzer::ScanSessionStatus::Running => {
+                        status.status = "scanning".to_string();
+                    }
+                    crate::disk_analyzer::ScanSessionStatus::Paused => {
+                        status.status = "paused".to_string();
+                        status.is_paused = true;
                     }
+                    crate::disk_analyzer::ScanSessionStatus::Completed => {
+                        status.status = "complete".to_string();
+                        status.can_analyze_duplicates = true;
+                        status.can_organize = true;
+                    }
+                    crate::disk_analyzer::ScanSessionStatus::Error(_) => {
+                        status.status = "error".to_string();
+                    }
+                    _ => {}
+                }
+
+                // Update progress information
+                status.progress = session.progress.overall_progress as f32;
+                status.quick_scan_progress = Some(
+                    (session.progress.quick_scan.processed_files as f32
+                        / session.progress.quick_scan.total_files.max(1) as f32)
+                        * 100.0,
+                );
+                status.slow_scan_progress = Some(
+                    (session.progress.deep_scan.processed_files as f32
+                        / session.progress.deep_scan.total_files.max(1) as f32)
+                        * 100.0,
+                );
+                status.files_scanned = session.progress.quick_scan.processed_files
+                    + session.progress.deep_scan.processed_files;
+                status.total_files = session.progress.quick_scan.total_files
+                    + session.progress.deep_scan.total_files;
+                status.current_path = Some(session.progress.quick_scan.current_path.clone());
+                if matches!(
+                    session.status,
+                    crate::disk_analyzer::ScanSessionStatus::Running
+                        | crate::disk_analyzer::ScanSessionStatus::Paused
+                ) {
+                    status.estimated_time_remaining = session.progress.remaining_time();
+                }
 
-                    // Update progress information
-                    status.progress = session.progress.overall_progress as f32;
-                    status.quick_scan_progress = Some(
-                        (session.progress.quick_scan.processed_files as f32
-                            / session.progress.quick_scan.total_files.max(1) as f32)
-                            * 100.0,
-                    );
-                    status.slow_scan_progress = Some(
-                        (session.progress.deep_scan.processed_files as f32
-                            / session.progress.deep_scan.total_files.max(1) as f32)
-                            * 100.0,
-                    );
-                    status.files_scanned = session.progress.quick_scan.processed_files
-                        + session.progress.deep_scan.processed_files;
-                    status.total_files = session.progress.quick_scan.total_files
-                        + session.progress.deep_scan.total_files;
-                    status.current_path = Some(session.progress.quick_scan.current_path.clone());
-
-                    match session.scan_type {
-                        crate::disk_analyzer::ScanType::Quick => {
-                            status.scan_type = Some("quick".to_string());
-                        }
-                        crate::disk_analyzer::ScanType::Deep => {
-                            status.scan_type = Some("deep".to_string());
-                        }
-                        crate::disk_analyzer::ScanType::Custom => {
-                            status.scan_type = Some("custom".to_string());
-                        }
+                match session.scan_type {
+                    crate::disk_analyzer::ScanType::Quick => {
+                        status.scan_type = Some("quick".to_string());
+                    }
+                    crate::disk_analyzer::ScanType::Deep => {
+                        status.scan_type = Some("deep".to_string());
+                    }
+                    crate::disk_analyzer::ScanType::Custom => {
+                        status.scan_type = Some("custom".to_string());
+                    }
+                    crate::disk_analyzer::ScanType::Incremental => {
+                        status.scan_type = Some("incremental".to_string());
                     }
                 }
             }
@@ -370,3 +474,19 @@ pub async fn cancel_scan(
 
     Err("No hay analizador activo".to_string())
 }
+
+/// Current scan I/O limits and whether idle mode is holding scans back
+#[tauri::command]
+pub async fn get_scan_throttle(state: State<'_, Arc<AppState>>) -> Result<ThrottleStatus, String> {
+    Ok(state.throttle.status())
+}
+
+/// Change the scan I/O limits; running scans follow them right away
+#[tauri::command]
+pub async fn set_scan_throttle(
+    settings: ThrottleConfig,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ThrottleStatus, String> {
+    state.throttle.set_settings(settings);
+    Ok(state.throttle.status())
+}
@@ -1,10 +1,12 @@
 use crate::app_state::AppState;
-use crate::file_system::FileInfo;
+use crate::file_system::{path_encoding, FileInfo, HardLinkSet};
+use crate::hash_cache::{HashCache, HashCacheStatus, HashCounters, HashKind};
+use crate::hashing::{self, HashAlgorithm};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::sync::Arc;
 use tauri::State;
 use tokio::fs;
 // use std::io; // Not needed for current implementation
@@ -32,6 +34,8 @@ pub struct DuplicateCopy {
     pub is_original: bool,
     pub keep_suggestion: bool,
     pub metadata: Option<FileMetadata>,
+    #[serde(default)]
+    pub hard_linked: bool, // Shares its data with an earlier copy, so deleting it frees nothing
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,7 +66,7 @@ pub struct DuplicateOptions {
     pub detection_method: DetectionMethod,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum DetectionMethod {
     Hash,
@@ -107,19 +111,27 @@ pub struct FailedDelete {
     pub error: String,
 }
 
+/// A file to delete from a duplicate group, with the copy kept in its place
+#[derive(Debug, Clone)]
+pub struct DeleteRequest {
+    pub id: String,
+    pub file: Option<FileInfo>, // None when the id is not in the scan results
+    pub kept_id: Option<String>,
+    pub kept: Option<FileInfo>,
+    pub group: Option<String>, // Hash of the stored duplicate group holding both files
+}
+
 /// Find duplicate files based on detection method
 #[tauri::command]
 pub async fn find_duplicates_advanced(
     options: DuplicateOptions,
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
 ) -> Result<Vec<DuplicateGroup>, String> {
     let storage = state.storage.read().await;
-    let mut all_files = Vec::new();
 
-    // Collect files from all scanned directories
-    for (_, files) in storage.scan_results.iter() {
-        all_files.extend(files.clone());
-    }
+    // Collect files from all indexed scan roots
+    let mut all_files: Vec<FileInfo> = storage.index.files().collect();
+    drop(storage);
 
     // Filter by disk if specified
     if let Some(ref disks) = options.disks {
@@ -142,7 +154,9 @@ pub async fn find_duplicates_advanced(
 
     // Group files based on detection method
     let groups = match options.detection_method {
-        DetectionMethod::Hash => group_by_hash(all_files).await?,
+        DetectionMethod::Hash => {
+            group_by_hash(all_files, &state.hash_cache, state.config.hashing.algorithm).await?
+        }
         DetectionMethod::Name => group_by_name(all_files),
         DetectionMethod::Size => group_by_size(all_files),
         DetectionMethod::NameAndSize => group_by_name_and_size(all_files),
@@ -154,11 +168,27 @@ pub async fn find_duplicates_advanced(
     Ok(duplicate_groups)
 }
 
+/// Size of the hash cache and how often it spared rehashing a file
+#[tauri::command]
+pub async fn get_hash_cache_status(
+    state: State<'_, Arc<AppState>>,
+) -> Result<HashCacheStatus, String> {
+    Ok(state.hash_cache.status())
+}
+
+/// Forget every cached hash, so the next scans hash all files again
+#[tauri::command]
+pub async fn clear_hash_cache(state: State<'_, Arc<AppState>>) -> Result<HashCacheStatus, String> {
+    state.hash_cache.clear();
+    state.hash_cache.persist().await;
+    Ok(state.hash_cache.status())
+}
+
 /// Get duplicate groups with advanced filtering
 #[tauri::command]
 pub async fn get_duplicate_groups(
     options: DuplicateOptions,
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
 ) -> Result<HashMap<String, serde_json::Value>, String> {
     let groups = find_duplicates_advanced(options, state).await?;
 
@@ -167,7 +197,7 @@ pub async fn get_duplicate_groups(
     for group in &groups {
         // Only count recoverable space (excluding the original)
         for (idx, copy) in group.copies.iter().enumerate() {
-            if idx > 0 {
+            if idx > 0 && !copy.hard_linked {
                 // Skip the first copy (original)
                 let disk_id = copy.disk.clone();
                 *by_disk.entry(disk_id).or_insert(0) += copy.size;
@@ -196,46 +226,115 @@ pub async fn get_duplicate_groups(
     Ok(result)
 }
 
-/// Delete duplicates in batch with safety checks
+/// Delete duplicates in batch with safety checks.
+///
+/// `keep` maps each file to the copy kept in its place, which every file
+/// needs. How the two were found to match is read from the duplicate groups
+/// stored by the scans, never taken from the caller.
 #[tauri::command]
 pub async fn delete_duplicates_batch(
     file_ids: Vec<String>,
     move_to_trash: bool,
-    _state: State<'_, AppState>,
+    keep: Option<HashMap<String, String>>,
+    state: State<'_, Arc<AppState>>,
 ) -> Result<DeleteBatchResult, String> {
+    let keep = keep.unwrap_or_default();
+
+    // Map IDs to the files found by the scan, and files to their stored group
+    let storage = state.storage.read().await;
+    let groups: HashMap<&str, &str> = storage
+        .index
+        .roots()
+        .flat_map(|root| root.duplicate_groups.iter())
+        .flat_map(|group| {
+            group
+                .files
+                .iter()
+                .map(|file| (file.path.as_str(), group.hash.as_str()))
+        })
+        .collect();
+    let requests: Vec<_> = file_ids
+        .into_iter()
+        .map(|id| {
+            let kept_id = keep.get(&id).cloned();
+            let file = storage.index.file_by_id(&id);
+            let kept = kept_id.as_ref().and_then(|k| storage.index.file_by_id(k));
+            let group = match (&file, &kept) {
+                (Some(file), Some(kept)) => groups
+                    .get(file.path.as_str())
+                    .filter(|&group| groups.get(kept.path.as_str()) == Some(group))
+                    .map(|group| group.to_string()),
+                _ => None,
+            };
+            DeleteRequest {
+                file,
+                kept,
+                kept_id,
+                group,
+                id,
+            }
+        })
+        .collect();
+    drop(storage);
+
+    Ok(delete_duplicates(requests, move_to_trash).await)
+}
+
+/// Delete the files of a batch, verifying each against its kept copy. The
+/// byte comparison is skipped only for groups found by a full hash, while
+/// both files are unchanged since the scan.
+pub async fn delete_duplicates(
+    requests: Vec<DeleteRequest>,
+    move_to_trash: bool,
+) -> DeleteBatchResult {
     let mut deleted = Vec::new();
     let mut failed = Vec::new();
     let mut space_saved = 0u64;
+    let deleting: HashSet<String> = requests.iter().map(|r| r.id.clone()).collect();
 
-    for file_id in file_ids {
-        // For now, we'll use the file_id as the path
-        // In a real implementation, you'd look up the actual file path from the database
-        match delete_file(&file_id, move_to_trash).await {
+    for request in requests {
+        let result = match (&request.file, &request.kept_id, &request.kept) {
+            (None, _, _) => Err("File not found in scan results".to_string()),
+            (Some(_), Some(kept_id), _) if deleting.contains(kept_id) => {
+                Err("Kept copy is deleted in the same batch".to_string())
+            }
+            (Some(_), Some(_), None) => Err("Kept copy not found in scan results".to_string()),
+            (Some(file), Some(_), Some(kept)) => {
+                let hashed = request
+                    .group
+                    .as_deref()
+                    .and_then(HashAlgorithm::of)
+                    .is_some();
+                crate::file_system::delete_indexed_duplicate(file, kept, hashed, move_to_trash)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+            (Some(_), None, _) => Err("No kept copy to compare with".to_string()),
+        };
+        match result {
             Ok(size) => {
-                deleted.push(file_id);
+                deleted.push(request.id);
                 space_saved += size;
             }
-            Err(e) => {
-                failed.push(FailedDelete {
-                    id: file_id,
-                    error: e.to_string(),
-                });
-            }
+            Err(error) => failed.push(FailedDelete {
+                id: request.id,
+                error,
+            }),
         }
     }
 
-    Ok(DeleteBatchResult {
+    DeleteBatchResult {
         deleted,
         failed,
         space_saved,
-    })
+    }
 }
 
 /// Apply smart selection strategy to duplicate groups
 #[tauri::command]
 pub async fn smart_select_duplicates(
     strategy: SmartSelectionStrategy,
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
 ) -> Result<Vec<SelectionResult>, String> {
     let mut results = Vec::new();
 
@@ -267,7 +366,7 @@ pub async fn smart_select_duplicates(
 pub async fn preview_duplicate(
     file_path: String,
 ) -> Result<HashMap<String, serde_json::Value>, String> {
-    let metadata = fs::metadata(&file_path)
+    let metadata = fs::metadata(path_encoding::decode_path(&file_path))
         .await
         .map_err(|e| format!("Failed to get file metadata: {}", e))?;
 
@@ -318,12 +417,26 @@ pub async fn preview_duplicate(
 
 // Helper functions
 
-async fn group_by_hash(files: Vec<FileInfo>) -> Result<HashMap<String, Vec<FileInfo>>, String> {
+async fn group_by_hash(
+    files: Vec<FileInfo>,
+    cache: &Arc<HashCache>,
+    algorithm: HashAlgorithm,
+) -> Result<HashMap<String, Vec<FileInfo>>, String> {
     let mut groups: HashMap<String, Vec<FileInfo>> = HashMap::new();
+    let counters = HashCounters::default();
 
     for file in files {
         if !file.is_directory {
-            match calculate_file_hash(&file.path).await {
+            let hash = cache
+                .hash_async(
+                    &path_encoding::decode_path(&file.path),
+                    HashKind::Full,
+                    algorithm,
+                    &counters,
+                    || hashing::hash_file(path_encoding::decode_path(&file.path), algorithm),
+                )
+                .await;
+            match hash {
                 Ok(hash) => {
                     groups.entry(hash).or_default().push(file);
                 }
@@ -334,6 +447,14 @@ async fn group_by_hash(files: Vec<FileInfo>) -> Result<HashMap<String, Vec<FileI
         }
     }
 
+    cache.persist().await;
+    let stats = counters.snapshot();
+    tracing::debug!(
+        "Hash cache answered {} of {} lookups",
+        stats.hits,
+        stats.hits + stats.misses
+    );
+
     // Only keep groups with duplicates
     groups.retain(|_, files| files.len() > 1);
 
@@ -384,23 +505,6 @@ fn group_by_name_and_size(files: Vec<FileInfo>) -> HashMap<String, Vec<FileInfo>
     groups
 }
 
-async fn calculate_file_hash(path: &str) -> Result<String> {
-    let mut file = fs::File::open(path).await?;
-    let mut hasher = Sha256::new();
-    let mut buffer = vec![0; 8192];
-
-    loop {
-        use tokio::io::AsyncReadExt;
-        let n = file.read(&mut buffer).await?;
-        if n == 0 {
-            break;
-        }
-        hasher.update(&buffer[..n]);
-    }
-
-    Ok(format!("{:x}", hasher.finalize()))
-}
-
 async fn convert_to_duplicate_groups(
     groups: HashMap<String, Vec<FileInfo>>,
 ) -> Vec<DuplicateGroup> {
@@ -410,13 +514,17 @@ async fn convert_to_duplicate_groups(
     for (key, files) in groups {
         if files.len() > 1 {
             let first_file = &files[0];
-            let total_size: u64 = files.iter().map(|f| f.size).sum();
-            let recoverable_size = total_size - first_file.size;
+            let mut hard_links = HardLinkSet::default();
+            let mut total_size = 0;
 
             let mut copies = Vec::new();
             for (idx, file) in files.iter().enumerate() {
+                let hard_linked = !hard_links.first_sighting(file.hard_link_key());
+                if !hard_linked {
+                    total_size += file.size;
+                }
                 copies.push(DuplicateCopy {
-                    id: format!("{}_{}", id_counter, idx),
+                    id: file.file_id(),
                     path: file.path.clone(),
                     disk: get_disk_from_path(&file.path).unwrap_or_else(|| "Unknown".to_string()),
                     size: file.size,
@@ -426,8 +534,10 @@ async fn convert_to_duplicate_groups(
                     is_original: idx == 0,
                     keep_suggestion: idx == 0,
                     metadata: None,
+                    hard_linked,
                 });
             }
+            let recoverable_size = total_size - first_file.size;
 
             duplicate_groups.push(DuplicateGroup {
                 id: id_counter.to_string(),
@@ -481,22 +591,6 @@ fn get_file_type(name: &str) -> String {
     }
 }
 
-async fn delete_file(path: &str, move_to_trash: bool) -> Result<u64> {
-    let metadata = fs::metadata(path).await?;
-    let size = metadata.len();
-
-    if move_to_trash {
-        // In a real implementation, you would move to trash
-        // For now, we'll just rename the file
-        let trash_path = format!("{}.trash", path);
-        fs::rename(path, trash_path).await?;
-    } else {
-        fs::remove_file(path).await?;
-    }
-
-    Ok(size)
-}
-
 fn apply_selection_strategy(
     group: &DuplicateGroup,
     strategy: &SelectionStrategy,
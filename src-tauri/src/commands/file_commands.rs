@@ -1,8 +1,9 @@
 use crate::app_state::AppState;
 use crate::disk_analyzer::{DiskAnalyzer, DuplicateGroup, ScanConfig, ScanSession, ScanType};
-use crate::file_system::{DiskInfo, FileInfo};
+use crate::file_system::{DiskInfo, FileInfo, SizeBasis};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use tauri::State;
 // use ai_module::OrganizeRules as AiOrganizeRules;
 // use ai_module::FileOperation as AiFileOperation;
@@ -11,6 +12,20 @@ use tauri::State;
 pub struct ScanOptions {
     pub scan_type: ScanType,
     pub exclude_patterns: Vec<String>,
+    #[serde(default)]
+    pub include_hidden: bool,
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    #[serde(default)]
+    pub same_filesystem: bool,
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    #[serde(default)]
+    pub min_file_size: Option<u64>,
+    #[serde(default)]
+    pub max_file_size: Option<u64>,
+    #[serde(default)]
+    pub use_ignore_files: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,7 +46,7 @@ pub struct FileOperation {
 /// Get current scan progress
 #[tauri::command]
 pub async fn get_scan_progress(
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
 ) -> Result<crate::disk_analyzer::ScanProgress, String> {
     if let Some(analyzer) = &*state.current_analyzer.read().await {
         Ok(analyzer.get_progress().await)
@@ -52,14 +67,13 @@ pub async fn create_scan_session(
     disk_path: String,
     scan_type: String,
     exclude_patterns: Vec<String>,
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
 ) -> Result<String, String> {
-    let analyzer = DiskAnalyzer::new(state.websocket_manager.clone());
-
     let scan_type_enum = match scan_type.as_str() {
         "quick" => ScanType::Quick,
         "deep" => ScanType::Deep,
         "custom" => ScanType::Custom,
+        "incremental" => ScanType::Incremental,
         _ => return Err("Invalid scan type".to_string()),
     };
 
@@ -74,34 +88,32 @@ pub async fn create_scan_session(
         quick_hash_threshold: 1024 * 1024, // 1MB
         duplicate_strategy: crate::disk_analyzer::DuplicateStrategy::SmartDetection,
         large_file_threshold: 100 * 1024 * 1024, // 100MB
+        same_filesystem: false,
+        use_ignore_files: false,
     };
 
-    // Store analyzer for session management
-    {
-        let mut current = state.current_analyzer.write().await;
-        *current = Some(analyzer);
-    }
-
-    let session_id = {
-        let guard = state.current_analyzer.read().await;
-        let analyzer = guard.as_ref().ok_or("Analyzer not initialized")?;
-        analyzer
-            .create_scan_session(disk_path, scan_type_enum, config)
-            .await
-            .map_err(|e| e.to_string())?
-    };
-
-    Ok(session_id)
+    // Sessions share one analyzer so they can run side by side
+    state
+        .analyzer()
+        .await
+        .create_scan_session(disk_path, scan_type_enum, config)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 /// Start a scan session
 #[tauri::command]
 pub async fn start_scan_session(
     session_id: String,
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
 ) -> Result<(), String> {
-    let guard = state.current_analyzer.read().await;
-    let analyzer = guard.as_ref().ok_or("Analyzer not initialized")?;
+    // Don't hold the lock while the scan runs
+    let analyzer = state
+        .current_analyzer
+        .read()
+        .await
+        .clone()
+        .ok_or("Analyzer not initialized")?;
     analyzer
         .start_scan_session(&session_id)
         .await
@@ -112,7 +124,7 @@ pub async fn start_scan_session(
 #[tauri::command]
 pub async fn pause_scan_session(
     session_id: String,
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
 ) -> Result<(), String> {
     let guard = state.current_analyzer.read().await;
     let analyzer = guard.as_ref().ok_or("Analyzer not initialized")?;
@@ -126,7 +138,7 @@ pub async fn pause_scan_session(
 #[tauri::command]
 pub async fn resume_scan_session(
     session_id: String,
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
 ) -> Result<(), String> {
     let guard = state.current_analyzer.read().await;
     let analyzer = guard.as_ref().ok_or("Analyzer not initialized")?;
@@ -140,7 +152,7 @@ pub async fn resume_scan_session(
 #[tauri::command]
 pub async fn cancel_scan_session(
     session_id: String,
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
 ) -> Result<(), String> {
     let guard = state.current_analyzer.read().await;
     let analyzer = guard.as_ref().ok_or("Analyzer not initialized")?;
@@ -154,7 +166,7 @@ pub async fn cancel_scan_session(
 #[tauri::command]
 pub async fn get_scan_session(
     session_id: String,
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
 ) -> Result<Option<ScanSession>, String> {
     let guard = state.current_analyzer.read().await;
     let analyzer = guard.as_ref().ok_or("Analyzer not initialized")?;
@@ -164,7 +176,7 @@ pub async fn get_scan_session(
 /// Get all active scan sessions
 #[tauri::command]
 pub async fn get_active_scan_sessions(
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
 ) -> Result<std::collections::HashMap<String, ScanSession>, String> {
     let guard = state.current_analyzer.read().await;
     let analyzer = guard.as_ref().ok_or("Analyzer not initialized")?;
@@ -176,39 +188,15 @@ pub async fn get_active_scan_sessions(
 pub async fn scan_disk(
     path: String,
     options: ScanOptions,
-    state: State<'_, AppState>,
+    state: State<'_, Arc<AppState>>,
 ) -> Result<Vec<FileInfo>, String> {
-    let analyzer = DiskAnalyzer::new(state.websocket_manager.clone());
-
-    // Store analyzer for progress tracking
-    {
-        let mut current = state.current_analyzer.write().await;
-        *current = Some(analyzer);
-    }
-
-    // Perform the scan
-    let files = {
-        let guard = state.current_analyzer.read().await;
-        let analyzer = guard.as_ref().ok_or("Analyzer not initialized")?;
-        analyzer
-            .scan_directory(&path, &options)
-            .await
-            .map_err(|e| e.to_string())?
-    };
-
-    // Store results in app state for later use
-    {
-        let mut storage = state.storage.write().await;
-        storage.scan_results.insert(path.clone(), files.clone());
-    }
-
-    // Clear current analyzer
-    {
-        let mut current = state.current_analyzer.write().await;
-        *current = None;
-    }
-
-    Ok(files)
+    // Results are published to the scan index when the session completes
+    state
+        .analyzer()
+        .await
+        .scan_directory(&path, &options)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 /// Get disk information
@@ -223,38 +211,37 @@ pub async fn get_disk_info() -> Result<Vec<DiskInfo>, String> {
 #[tauri::command]
 pub async fn get_large_files(
     min_size_mb: u64,
-    state: State<'_, AppState>,
+    size_basis: Option<SizeBasis>,
+    state: State<'_, Arc<AppState>>,
 ) -> Result<Vec<FileInfo>, String> {
     let storage = state.storage.read().await;
     let min_size_bytes = min_size_mb * 1024 * 1024;
+    let basis = size_basis.unwrap_or_default();
 
     // Filter files from scan results that are larger than threshold
-    let mut large_files = Vec::new();
-    for files in storage.scan_results.values() {
-        for file in files {
-            if file.size >= min_size_bytes {
-                large_files.push(file.clone());
-            }
-        }
-    }
+    let mut large_files: Vec<FileInfo> = storage
+        .index
+        .files()
+        .filter(|file| file.size_on(basis) >= min_size_bytes)
+        .collect();
 
     // Sort by size descending
-    large_files.sort_by(|a, b| b.size.cmp(&a.size));
+    large_files.sort_by_key(|file| std::cmp::Reverse(file.size_on(basis)));
 
     Ok(large_files)
 }
 
 /// Find duplicate files
 #[tauri::command]
-pub async fn find_duplicates(state: State<'_, AppState>) -> Result<Vec<DuplicateGroup>, String> {
+pub async fn find_duplicates(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<DuplicateGroup>, String> {
     let analyzer = DiskAnalyzer::new(state.websocket_manager.clone());
 
     // Get all files from stored scan results
     let storage = state.storage.read().await;
-    let mut all_files = Vec::new();
-    for files in storage.scan_results.values() {
-        all_files.extend(files.clone());
-    }
+    let all_files: Vec<FileInfo> = storage.index.files().collect();
+    drop(storage);
 
     if all_files.is_empty() {
         return Ok(Vec::new());
@@ -272,7 +259,7 @@ pub async fn organize_files(
     _source_path: String,
     _target_path: String,
     _rules: OrganizeRules,
-    _state: State<'_, AppState>,
+    _state: State<'_, Arc<AppState>>,
 ) -> Result<Vec<FileOperation>, String> {
     // Temporarily return empty suggestions until AI module is available
     Ok(Vec::new())
@@ -315,7 +302,7 @@ pub async fn get_file_metadata(path: String) -> Result<FileInfo, String> {
 #[tauri::command]
 pub async fn perform_file_operation(
     operation: FileOperation,
-    _state: State<'_, AppState>,
+    _state: State<'_, Arc<AppState>>,
 ) -> Result<bool, String> {
     match operation.operation.as_str() {
         "move" => {
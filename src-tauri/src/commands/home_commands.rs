@@ -18,6 +18,7 @@ pub struct DiskSummary {
     pub percentage: f32,
     pub file_system: Option<String>,
     pub last_scanned: Option<DateTime<Utc>>,
+    pub indexed_size: Option<u64>, // Size of the scanned files on this disk, on the requested basis
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -42,6 +43,7 @@ pub enum ActivityType {
     FilesMoved,
     DiskOrganized,
     ErrorOccurred,
+    ScheduledJobRun,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -103,8 +105,11 @@ pub async fn log_activity(
 
 #[tauri::command]
 pub async fn get_system_overview(
+    size_basis: Option<file_system::SizeBasis>,
     state: State<'_, Arc<AppState>>,
 ) -> Result<SystemOverview, String> {
+    let basis = size_basis.unwrap_or_default();
+
     // Get disk information
     let disks = file_system::get_system_disks()
         .await
@@ -144,54 +149,65 @@ pub async fn get_system_overview(
             free: disk.available_space,
             percentage,
             file_system: Some(disk.file_system.clone()),
-            last_scanned: None, // TODO: Get from scan history
+            last_scanned: None, // Filled from the scan index below
+            indexed_size: None,
         });
     }
 
-    // Get additional statistics from app state
-    let app_state = state.inner();
+    // Get statistics from the persistent scan index
+    let storage = state.storage.read().await;
+
+    for summary in disk_summaries.iter_mut() {
+        summary.last_scanned = storage.index.last_scan_under(&summary.path);
+        if summary.last_scanned.is_some() {
+            let disk = [summary.path.clone()];
+            summary.indexed_size = Some(
+                storage
+                    .index
+                    .roots_under(Some(&disk))
+                    .map(|root| root.files.total_size(basis))
+                    .sum(),
+            );
+        }
+    }
 
-    // Get real statistics from disk analyzer if available
-    let (duplicates_found, space_recoverable, large_files_count, last_full_scan) =
-        if let Some(analyzer) = app_state.current_analyzer.read().await.as_ref() {
-            let stats = analyzer.get_statistics().await;
-            (
-                stats.duplicates_count as u32,
-                stats.duplicate_size,
-                stats.large_files_count as u32,
-                stats.last_scan_time,
-            )
+    let mut duplicates_found = 0u32;
+    let mut space_recoverable = 0u64;
+    let mut large_files_count = 0u32;
+
+    for root in storage.index.roots() {
+        let large_file_threshold = root.record.config.large_file_threshold;
+        large_files_count += root
+            .files
+            .entries()
+            .filter(|f| f.size_on(basis) >= large_file_threshold)
+            .count() as u32;
+
+        if !root.duplicate_groups.is_empty() {
+            for group in &root.duplicate_groups {
+                duplicates_found += group.files.len().saturating_sub(1) as u32;
+                space_recoverable += group.potential_savings;
+            }
         } else {
-            // If no analyzer is active, check stored results in storage
-            let storage = app_state.storage.read().await;
-
-            // Calculate statistics from all stored scan results
-            let mut total_duplicates = 0u32;
-            let mut total_recoverable = 0u64;
-            let mut total_large_files = 0u32;
-
-            for (_, files) in storage.scan_results.iter() {
-                // Count files > 100MB as large files
-                total_large_files +=
-                    files.iter().filter(|f| f.size > 100 * 1024 * 1024).count() as u32;
-
-                // TODO: Add duplicate detection to stored results
-                // For now, estimate duplicates as files with same size
-                let mut size_map = std::collections::HashMap::new();
-                for file in files {
-                    size_map.entry(file.size).or_insert(Vec::new()).push(file);
+            // No duplicate detection ran for this root, estimate duplicates as files with same size
+            let mut size_map = std::collections::HashMap::new();
+            let mut hard_links = file_system::HardLinkSet::default();
+            for file in root.files.entries() {
+                if hard_links.first_sighting(file.hard_link_key()) {
+                    *size_map.entry(file.size()).or_insert(0u64) += 1;
                 }
+            }
 
-                for (size, group) in size_map {
-                    if group.len() > 1 {
-                        total_duplicates += (group.len() - 1) as u32;
-                        total_recoverable += size * (group.len() - 1) as u64;
-                    }
+            for (size, count) in size_map {
+                if count > 1 {
+                    duplicates_found += (count - 1) as u32;
+                    space_recoverable += size * (count - 1);
                 }
             }
+        }
+    }
 
-            (total_duplicates, total_recoverable, total_large_files, None)
-        };
+    let last_full_scan = storage.index.last_scan();
 
     Ok(SystemOverview {
         disks: disk_summaries,
@@ -287,5 +303,5 @@ pub async fn execute_quick_action(
 pub async fn refresh_dashboard(_state: State<'_, Arc<AppState>>) -> Result<SystemOverview, String> {
     // Force refresh all dashboard data
     // This could trigger background updates as well
-    get_system_overview(_state).await
+    get_system_overview(None, _state).await
 }
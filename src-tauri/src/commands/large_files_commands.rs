@@ -1,9 +1,9 @@
 use crate::app_state::AppState;
-use crate::file_system::FileInfo;
+use crate::file_system::{path_encoding, FileInfo, HardLinkSet, SizeBasis};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tauri::State;
 use tokio::fs;
@@ -15,6 +15,7 @@ pub struct LargeFileInfo {
     pub path: String,
     pub name: String,
     pub size: u64,
+    pub allocated_size: u64, // Size on disk; the apparent size when unknown
     pub file_type: String,
     pub extension: String,
     pub created: i64,
@@ -34,15 +35,26 @@ pub struct LargeFileFilter {
     pub extensions: Option<Vec<String>>,
     pub sort_by: Option<String>,
     pub sort_order: Option<String>,
+    pub size_basis: Option<SizeBasis>, // Size used by the size filters and ranking
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpaceAnalysis {
+    pub size_basis: SizeBasis, // Size used by every total below
     pub total_size: u64,
     pub file_count: usize,
     pub by_type: HashMap<String, SpaceByType>,
     pub by_disk: HashMap<String, u64>,
     pub size_distribution: SizeDistribution,
+    pub slack: SlackSpace,
+}
+
+/// Space allocated on disk beyond the contents of the files
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SlackSpace {
+    pub total: u64,
+    pub small_files: u64,        // Slack of files under 1MB
+    pub small_file_count: usize, // Files under 1MB that waste any space
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -138,30 +150,25 @@ pub async fn find_large_files(
     .await;
     let storage = state.storage.read().await;
     let mut large_files = Vec::new();
+    let basis = filter.size_basis.unwrap_or_default();
 
-    // Collect all files from scan results
-    for (scan_path, files) in storage.scan_results.iter() {
-        // Filter by paths if specified
-        if let Some(ref paths) = filter.paths {
-            if !paths.iter().any(|p| scan_path.starts_with(p)) {
-                continue;
-            }
-        }
-
-        for file in files {
+    // Query indexed scan results, filtered by paths if specified
+    for root in storage.index.roots_under(filter.paths.as_deref()) {
+        for file in &root.files {
             if file.is_directory {
                 continue;
             }
 
             // Apply size filters
+            let size = file.size_on(basis);
             if let Some(min_size) = filter.min_size {
-                if file.size < min_size {
+                if size < min_size {
                     continue;
                 }
             }
 
             if let Some(max_size) = filter.max_size {
-                if file.size > max_size {
+                if size > max_size {
                     continue;
                 }
             }
@@ -184,10 +191,11 @@ pub async fn find_large_files(
             }
 
             large_files.push(LargeFileInfo {
-                id: format!("{:x}", md5::compute(&file.path)),
+                id: file.file_id(),
                 path: file.path.clone(),
                 name: file.name.clone(),
                 size: file.size,
+                allocated_size: file.size_on(SizeBasis::Allocated),
                 file_type: file_type.clone(),
                 extension: extension.clone(),
                 created: file.created.timestamp(),
@@ -201,7 +209,7 @@ pub async fn find_large_files(
     }
 
     // Sort results
-    sort_large_files(&mut large_files, &filter.sort_by, &filter.sort_order);
+    sort_large_files(&mut large_files, &filter.sort_by, &filter.sort_order, basis);
 
     // Log scan completion activity
     let total_size: u64 = large_files.iter().map(|f| f.size).sum();
@@ -227,9 +235,11 @@ pub async fn find_large_files(
 #[tauri::command]
 pub async fn get_file_space_analysis(
     paths: Option<Vec<String>>,
+    size_basis: Option<SizeBasis>,
     state: State<'_, Arc<AppState>>,
 ) -> Result<SpaceAnalysis, String> {
     let storage = state.storage.read().await;
+    let basis = size_basis.unwrap_or_default();
     let mut total_size = 0u64;
     let mut file_count = 0usize;
     let mut by_type: HashMap<String, SpaceByType> = HashMap::new();
@@ -242,24 +252,33 @@ pub async fn get_file_space_analysis(
         huge: 0,
         gigantic: 0,
     };
+    let mut slack = SlackSpace::default();
+    let mut hard_links = HardLinkSet::default();
 
-    // Analyze all files
-    for (scan_path, files) in storage.scan_results.iter() {
-        // Filter by paths if specified
-        if let Some(ref filter_paths) = paths {
-            if !filter_paths.iter().any(|p| scan_path.starts_with(p)) {
+    // Analyze all indexed files, filtered by paths if specified
+    for root in storage.index.roots_under(paths.as_deref()) {
+        for file in &root.files {
+            if file.is_directory {
                 continue;
             }
-        }
 
-        for file in files {
-            if file.is_directory {
+            // Hard links to data already counted take no extra space
+            if !hard_links.first_sighting(file.hard_link_key()) {
                 continue;
             }
 
-            total_size += file.size;
+            let size = file.size_on(basis);
+            total_size += size;
             file_count += 1;
 
+            // Space lost to partly filled clusters
+            let file_slack = file.slack();
+            slack.total += file_slack;
+            if file.size < 1_048_576 && file_slack > 0 {
+                slack.small_files += file_slack;
+                slack.small_file_count += 1;
+            }
+
             // Group by file type
             let file_type = get_file_type(&file.name);
             let type_entry = by_type.entry(file_type).or_insert(SpaceByType {
@@ -267,16 +286,16 @@ pub async fn get_file_space_analysis(
                 count: 0,
                 percentage: 0.0,
             });
-            type_entry.size += file.size;
+            type_entry.size += size;
             type_entry.count += 1;
 
             // Group by disk
             if let Some(disk) = get_disk_from_path(&file.path) {
-                *by_disk.entry(disk).or_insert(0) += file.size;
+                *by_disk.entry(disk).or_insert(0) += size;
             }
 
             // Size distribution
-            match file.size {
+            match size {
                 s if s < 1_048_576 => size_distribution.tiny += 1, // < 1MB
                 s if s < 10_485_760 => size_distribution.small += 1, // 1MB - 10MB
                 s if s < 104_857_600 => size_distribution.medium += 1, // 10MB - 100MB
@@ -295,11 +314,13 @@ pub async fn get_file_space_analysis(
     }
 
     Ok(SpaceAnalysis {
+        size_basis: basis,
         total_size,
         file_count,
         by_type,
         by_disk,
         size_distribution,
+        slack,
     })
 }
 
@@ -310,22 +331,23 @@ pub async fn compress_file(
     options: CompressionOptions,
 ) -> Result<CompressionResult, String> {
     let start_time = std::time::Instant::now();
+    let source = path_encoding::decode_path(&file_path);
 
     // Get original file info
-    let metadata = fs::metadata(&file_path)
+    let metadata = fs::metadata(&source)
         .await
         .map_err(|e| format!("Failed to read file metadata: {}", e))?;
     let original_size = metadata.len();
 
     // Generate output path
-    let output_path = generate_compressed_path(&file_path, &options.format);
+    let output_path = generate_compressed_path(&source, &options.format);
 
     // Perform compression based on format
     let compressed_size = match options.format {
-        CompressionFormat::Zip => compress_to_zip(&file_path, &output_path, &options.level).await?,
-        CompressionFormat::Tar => compress_to_tar(&file_path, &output_path).await?,
+        CompressionFormat::Zip => compress_to_zip(&source, &output_path, &options.level).await?,
+        CompressionFormat::Tar => compress_to_tar(&source, &output_path).await?,
         CompressionFormat::TarGz => {
-            compress_to_tar_gz(&file_path, &output_path, &options.level).await?
+            compress_to_tar_gz(&source, &output_path, &options.level).await?
         }
         CompressionFormat::SevenZ => {
             return Err("7z compression not yet implemented".to_string());
@@ -334,7 +356,7 @@ pub async fn compress_file(
 
     // Delete original if requested
     if !options.keep_original {
-        fs::remove_file(&file_path)
+        fs::remove_file(&source)
             .await
             .map_err(|e| format!("Failed to delete original file: {}", e))?;
     }
@@ -350,7 +372,7 @@ pub async fn compress_file(
         original_size,
         compressed_size,
         compression_ratio,
-        output_path,
+        output_path: path_encoding::encode_path(&output_path),
         time_taken,
     })
 }
@@ -358,7 +380,8 @@ pub async fn compress_file(
 /// Generate a preview for a file
 #[tauri::command]
 pub async fn generate_file_preview(file_path: String) -> Result<FilePreview, String> {
-    let metadata = fs::metadata(&file_path)
+    let source = path_encoding::decode_path(&file_path);
+    let metadata = fs::metadata(&source)
         .await
         .map_err(|e| format!("Failed to read file metadata: {}", e))?;
 
@@ -374,7 +397,7 @@ pub async fn generate_file_preview(file_path: String) -> Result<FilePreview, Str
     let (preview_type, content) = match file_type.as_str() {
         "text" | "code" => {
             // Read first 1000 characters for text files
-            let content = read_text_preview(&file_path, 1000).await?;
+            let content = read_text_preview(&source, 1000).await?;
             ("text".to_string(), Some(content))
         }
         "image" => {
@@ -430,23 +453,18 @@ pub async fn delete_large_files_batch(
     let mut failed = Vec::new();
     let mut space_freed = 0u64;
 
-    // Get file info from storage to map IDs to paths
+    // Map IDs to the files found by the scan
     let storage = state.storage.read().await;
-    let mut id_to_file: HashMap<String, FileInfo> = HashMap::new();
-
-    for files in storage.scan_results.values() {
-        for file in files {
-            let file_id = format!("{:x}", md5::compute(&file.path));
-            if file_ids.contains(&file_id) {
-                id_to_file.insert(file_id, file.clone());
-            }
-        }
-    }
+    let id_to_file: HashMap<String, FileInfo> = file_ids
+        .iter()
+        .filter_map(|id| Some((id.clone(), storage.index.file_by_id(id)?)))
+        .collect();
+    drop(storage);
 
     // Delete each file
     for file_id in file_ids {
         if let Some(file_info) = id_to_file.get(&file_id) {
-            match delete_file(&file_info.path, move_to_trash).await {
+            match crate::file_system::delete_indexed_file(file_info, move_to_trash).await {
                 Ok(size) => {
                     deleted.push(file_id);
                     space_freed += size;
@@ -587,15 +605,20 @@ fn sort_large_files(
     files: &mut Vec<LargeFileInfo>,
     sort_by: &Option<String>,
     sort_order: &Option<String>,
+    basis: SizeBasis,
 ) {
     let ascending = sort_order.as_ref().map(|o| o == "asc").unwrap_or(false);
+    let size = |f: &LargeFileInfo| match basis {
+        SizeBasis::Apparent => f.size,
+        SizeBasis::Allocated => f.allocated_size,
+    };
 
     match sort_by.as_ref().map(|s| s.as_str()) {
         Some("size") => {
             if ascending {
-                files.sort_by_key(|f| f.size);
+                files.sort_by_key(size);
             } else {
-                files.sort_by_key(|f| std::cmp::Reverse(f.size));
+                files.sort_by_key(|f| std::cmp::Reverse(size(f)));
             }
         }
         Some("name") => {
@@ -626,23 +649,8 @@ fn sort_large_files(
     }
 }
 
-async fn delete_file(path: &str, move_to_trash: bool) -> Result<u64> {
-    let metadata = fs::metadata(path).await?;
-    let size = metadata.len();
-
-    if move_to_trash {
-        // In a real implementation, you would use a proper trash library
-        // For now, we'll just rename the file
-        let trash_path = format!("{}.trash", path);
-        fs::rename(path, trash_path).await?;
-    } else {
-        fs::remove_file(path).await?;
-    }
-
-    Ok(size)
-}
-
-async fn read_text_preview(path: &str, max_chars: usize) -> Result<String, String> {
+/// Read up to `max_chars` bytes from the start of a file as UTF-8 text
+async fn read_text_preview(path: &Path, max_chars: usize) -> Result<String, String> {
     use tokio::io::AsyncReadExt;
 
     let mut file = fs::File::open(path)
@@ -658,20 +666,20 @@ async fn read_text_preview(path: &str, max_chars: usize) -> Result<String, Strin
     String::from_utf8(buffer[..n].to_vec()).map_err(|_| "File is not valid UTF-8 text".to_string())
 }
 
-fn generate_compressed_path(original_path: &str, format: &CompressionFormat) -> String {
+fn generate_compressed_path(original_path: &Path, format: &CompressionFormat) -> PathBuf {
     let extension = match format {
-        CompressionFormat::Zip => "zip",
-        CompressionFormat::Tar => "tar",
-        CompressionFormat::TarGz => "tar.gz",
-        CompressionFormat::SevenZ => "7z",
+        CompressionFormat::Zip => ".zip",
+        CompressionFormat::Tar => ".tar",
+        CompressionFormat::TarGz => ".tar.gz",
+        CompressionFormat::SevenZ => ".7z",
     };
 
-    format!("{}.{}", original_path, extension)
+    path_encoding::with_suffix(original_path, extension)
 }
 
 async fn compress_to_zip(
-    file_path: &str,
-    output_path: &str,
+    file_path: &Path,
+    output_path: &Path,
     level: &CompressionLevel,
 ) -> Result<u64, String> {
     use std::io::{Read, Write};
@@ -695,7 +703,7 @@ async fn compress_to_zip(
         }
     };
 
-    let file_name = Path::new(file_path)
+    let file_name = file_path
         .file_name()
         .ok_or("Invalid file name")?
         .to_string_lossy();
@@ -720,7 +728,7 @@ async fn compress_to_zip(
     Ok(metadata.len())
 }
 
-async fn compress_to_tar(file_path: &str, output_path: &str) -> Result<u64, String> {
+async fn compress_to_tar(file_path: &Path, output_path: &Path) -> Result<u64, String> {
     use tar::Builder;
 
     let output_file = std::fs::File::create(output_path)
@@ -728,9 +736,7 @@ async fn compress_to_tar(file_path: &str, output_path: &str) -> Result<u64, Stri
 
     let mut tar = Builder::new(output_file);
 
-    let file_name = Path::new(file_path)
-        .file_name()
-        .ok_or("Invalid file name")?;
+    let file_name = file_path.file_name().ok_or("Invalid file name")?;
 
     tar.append_path_with_name(file_path, file_name)
         .map_err(|e| format!("Failed to add file to tar: {}", e))?;
@@ -745,8 +751,8 @@ async fn compress_to_tar(file_path: &str, output_path: &str) -> Result<u64, Stri
 }
 
 async fn compress_to_tar_gz(
-    file_path: &str,
-    output_path: &str,
+    file_path: &Path,
+    output_path: &Path,
     level: &CompressionLevel,
 ) -> Result<u64, String> {
     use flate2::write::GzEncoder;
@@ -765,9 +771,7 @@ async fn compress_to_tar_gz(
     let gz = GzEncoder::new(output_file, compression);
     let mut tar = Builder::new(gz);
 
-    let file_name = Path::new(file_path)
-        .file_name()
-        .ok_or("Invalid file name")?;
+    let file_name = file_path.file_name().ok_or("Invalid file name")?;
 
     tar.append_path_with_name(file_path, file_name)
         .map_err(|e| format!("Failed to add file to tar: {}", e))?;
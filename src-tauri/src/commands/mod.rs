@@ -1,10 +1,14 @@
+pub mod directory_tree_commands;
 pub mod disk_analyzer_commands;
 pub mod duplicate_commands;
 pub mod file_commands;
 pub mod home_commands;
 pub mod large_files_commands;
 pub mod organize_commands;
+pub mod scan_profile_commands;
+pub mod scheduler_commands;
 pub mod user_commands;
+pub mod watcher_commands;
 // Temporarily commented out until external modules are available:
 // pub mod auth_commands;
 // pub mod i18n_commands;
@@ -0,0 +1,75 @@
+use crate::app_state::AppState;
+use crate::commands::disk_analyzer_commands::{start_scan, ScanResponse};
+use crate::scan_profiles::ScanProfile;
+use std::sync::Arc;
+use tauri::State;
+
+/// Built-in presets followed by the user's saved profiles
+#[tauri::command]
+pub async fn list_scan_profiles(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<ScanProfile>, String> {
+    Ok(state.storage.read().await.profiles.list())
+}
+
+/// Create a profile, or update it when its id is already saved
+#[tauri::command]
+pub async fn save_scan_profile(
+    profile: ScanProfile,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ScanProfile, String> {
+    state
+        .storage
+        .write()
+        .await
+        .profiles
+        .save(profile)
+        .map_err(|e| e.to_string())
+}
+
+/// Delete a saved profile, returning whether it existed
+#[tauri::command]
+pub async fn delete_scan_profile(
+    profile_id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<bool, String> {
+    state
+        .storage
+        .write()
+        .await
+        .profiles
+        .delete(&profile_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Scan every root of a profile with its settings, one session per root
+#[tauri::command]
+pub async fn scan_with_profile(
+    profile_id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<ScanResponse>, String> {
+    let profile = state
+        .storage
+        .read()
+        .await
+        .profiles
+        .get(&profile_id)
+        .ok_or_else(|| format!("Scan profile not found: {}", profile_id))?;
+
+    let mut responses = Vec::new();
+    for root in &profile.roots {
+        let session_id = start_scan(
+            state.inner(),
+            root.clone(),
+            profile.scan_type.clone(),
+            profile.config.clone(),
+        )
+        .await?;
+        responses.push(ScanResponse {
+            session_id,
+            message: format!("Escaneo iniciado en {} ({})", root, profile.name),
+            success: true,
+        });
+    }
+    Ok(responses)
+}
@@ -0,0 +1,80 @@
+use crate::app_state::AppState;
+use crate::scheduler::{JobAction, ScheduledJob};
+use chrono::Utc;
+use std::sync::Arc;
+use tauri::State;
+
+/// Every scheduled job with its next run and recent history
+#[tauri::command]
+pub async fn list_scheduled_jobs(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<ScheduledJob>, String> {
+    let mut jobs = state.storage.read().await.schedules.list();
+    for job in &mut jobs {
+        job.running = state.scheduler.is_running(&job.id);
+    }
+    Ok(jobs)
+}
+
+/// Create a job, first due one period from now
+#[tauri::command]
+pub async fn create_scheduled_job(
+    job: ScheduledJob,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ScheduledJob, String> {
+    let mut storage = state.storage.write().await;
+    if let JobAction::ScanProfile { profile_id } = &job.action {
+        if storage.profiles.get(profile_id).is_none() {
+            return Err(format!("Scan profile not found: {}", profile_id));
+        }
+    }
+    storage
+        .schedules
+        .create(job, Utc::now())
+        .map_err(|e| e.to_string())
+}
+
+/// Pause a job, or resume it from now
+#[tauri::command]
+pub async fn pause_scheduled_job(
+    job_id: String,
+    paused: bool,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ScheduledJob, String> {
+    state
+        .storage
+        .write()
+        .await
+        .schedules
+        .set_paused(&job_id, paused, Utc::now())
+        .map_err(|e| e.to_string())
+}
+
+/// Start a job now, outside its schedule; the run shows up in its history
+#[tauri::command]
+pub async fn run_scheduled_job_now(
+    job_id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    state
+        .scheduler
+        .run_now(state.inner(), &job_id)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Delete a job, returning whether it existed
+#[tauri::command]
+pub async fn delete_scheduled_job(
+    job_id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<bool, String> {
+    state
+        .storage
+        .write()
+        .await
+        .schedules
+        .delete(&job_id)
+        .map_err(|e| e.to_string())
+}
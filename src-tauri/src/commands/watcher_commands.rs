@@ -0,0 +1,29 @@
+use crate::app_state::AppState;
+use crate::watcher::WatchStatus;
+use std::sync::Arc;
+use tauri::State;
+
+/// Keep the index of a scanned root up to date with changes on disk
+#[tauri::command]
+pub async fn watch_root(
+    root: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<WatchStatus, String> {
+    state
+        .watcher
+        .watch(&root, state.analyzer().await)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Stop keeping a root up to date, returning whether it was watched
+#[tauri::command]
+pub async fn unwatch_root(root: String, state: State<'_, Arc<AppState>>) -> Result<bool, String> {
+    Ok(state.watcher.unwatch(&root))
+}
+
+/// How every watched root is kept up to date
+#[tauri::command]
+pub async fn get_watch_status(state: State<'_, Arc<AppState>>) -> Result<Vec<WatchStatus>, String> {
+    Ok(state.watcher.status())
+}
@@ -0,0 +1,134 @@
+use crate::file_system::SizeBasis;
+use crate::file_table::{FileRef, FileTable, SubtreeSize};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::path::Path;
+
+mod treemap;
+
+pub use treemap::{layout_treemap, TreemapRect};
+
+pub const DEFAULT_DEPTH: usize = 3;
+pub const DEFAULT_MIN_FRACTION: f64 = 0.001;
+pub const DEFAULT_MAX_CHILDREN: usize = 50;
+
+/// How much of a directory tree to build
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeOptions {
+    pub depth: usize,        // Levels of children below the requested directory
+    pub min_fraction: f64, // Entries smaller than this share of the requested directory go into "other"
+    pub max_children: usize, // Biggest children kept per directory, the rest go into "other"
+    pub size_basis: SizeBasis,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        Self {
+            depth: DEFAULT_DEPTH,
+            min_fraction: DEFAULT_MIN_FRACTION,
+            max_children: DEFAULT_MAX_CHILDREN,
+            size_basis: SizeBasis::default(),
+        }
+    }
+}
+
+/// One entry of a directory size tree, with its recursive size
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryNode {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub file_count: u64,
+    pub is_directory: bool,
+    pub is_other: bool, // Lumps together the children too small to show on their own
+    pub has_more: bool, // Has children below the requested depth, drill down to get them
+    pub children: Vec<DirectoryNode>, // Biggest first
+}
+
+/// Build the size tree of `path` from stored scan results.
+///
+/// Sizes are recursive and count the data of hard-linked files once. Each
+/// directory keeps at most `max_children` children, all at least
+/// `min_fraction` of the size of `path`; the rest are summed into a single
+/// "other" node, so the tree stays small however many files were scanned.
+/// `sizes` are the table's [`FileTable::subtree_sizes`] on the options' size
+/// basis. Returns `None` when `path` is not in the table.
+pub fn build_tree(
+    files: &FileTable,
+    sizes: &[SubtreeSize],
+    path: &Path,
+    options: &TreeOptions,
+) -> Option<DirectoryNode> {
+    let root = files.node(path)?;
+    let root_size = sizes[root.id() as usize].size;
+    let builder = TreeBuilder {
+        sizes,
+        min_size: (root_size as f64 * options.min_fraction.clamp(0.0, 1.0)) as u64,
+        max_children: options.max_children,
+    };
+    Some(builder.node(root, options.depth))
+}
+
+struct TreeBuilder<'a> {
+    sizes: &'a [SubtreeSize],
+    min_size: u64,
+    max_children: usize,
+}
+
+impl TreeBuilder<'_> {
+    fn node(&self, entry: FileRef<'_>, depth: usize) -> DirectoryNode {
+        let total = self.size_of(&entry);
+        let mut node = DirectoryNode {
+            path: entry.path().to_string_lossy().to_string(),
+            name: entry.display_name().into_owned(),
+            size: total.size,
+            file_count: total.files,
+            is_directory: entry.is_directory(),
+            is_other: false,
+            has_more: false,
+            children: Vec::new(),
+        };
+        if !node.is_directory {
+            return node;
+        }
+
+        let mut children: Vec<_> = entry.children().collect();
+        if depth == 0 {
+            node.has_more = !children.is_empty();
+            return node;
+        }
+        children.sort_by_key(|child| Reverse(self.size_of(child).size));
+
+        let mut other = SubtreeSize::default();
+        let mut lumped = 0;
+        for (i, child) in children.into_iter().enumerate() {
+            let size = self.size_of(&child);
+            if i < self.max_children && size.size >= self.min_size {
+                node.children.push(self.node(child, depth - 1));
+            } else {
+                other.size += size.size;
+                other.files += size.files;
+                lumped += 1;
+            }
+        }
+
+        if lumped > 0 {
+            node.children.push(DirectoryNode {
+                path: node.path.clone(),
+                name: format!("{} more", lumped),
+                size: other.size,
+                file_count: other.files,
+                is_directory: false,
+                is_other: true,
+                has_more: false,
+                children: Vec::new(),
+            });
+            node.children.sort_by_key(|child| Reverse(child.size));
+        }
+        node
+    }
+
+    fn size_of(&self, entry: &FileRef<'_>) -> SubtreeSize {
+        self.sizes[entry.id() as usize]
+    }
+}
@@ -0,0 +1,133 @@
+use super::DirectoryNode;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// Rectangle of one node in a treemap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreemapRect {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub depth: usize, // 0 for the root of the layout
+    pub is_directory: bool,
+    pub is_other: bool,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+/// Lay out `root` in a `width` by `height` area with the squarified treemap
+/// algorithm (Bruls, Huizing and van Wijk), which keeps the rectangles close
+/// to square. Returns a rectangle for every node of the tree with a size,
+/// parents before their children; children are nested inside their parent.
+pub fn layout_treemap(root: &DirectoryNode, width: f64, height: f64) -> Vec<TreemapRect> {
+    let mut rects = Vec::new();
+    let area = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: width.max(0.0),
+        height: height.max(0.0),
+    };
+    place(root, area, 0, &mut rects);
+    rects
+}
+
+fn place(node: &DirectoryNode, area: Rect, depth: usize, rects: &mut Vec<TreemapRect>) {
+    rects.push(TreemapRect {
+        path: node.path.clone(),
+        name: node.name.clone(),
+        size: node.size,
+        depth,
+        is_directory: node.is_directory,
+        is_other: node.is_other,
+        x: area.x,
+        y: area.y,
+        width: area.width,
+        height: area.height,
+    });
+
+    let mut children: Vec<_> = node.children.iter().filter(|c| c.size > 0).collect();
+    let total: u64 = children.iter().map(|c| c.size).sum();
+    if total == 0 || area.width <= 0.0 || area.height <= 0.0 {
+        return;
+    }
+    children.sort_by_key(|child| Reverse(child.size));
+
+    let scale = area.width * area.height / total as f64;
+    let areas: Vec<f64> = children.iter().map(|c| c.size as f64 * scale).collect();
+    for (child, rect) in children.into_iter().zip(squarify(&areas, area)) {
+        place(child, rect, depth + 1, rects);
+    }
+}
+
+/// Split `area` into rectangles of the given areas, sorted largest first
+fn squarify(areas: &[f64], mut area: Rect) -> Vec<Rect> {
+    let mut rects = Vec::with_capacity(areas.len());
+    let mut start = 0;
+    while start < areas.len() {
+        // Grow the row along the shorter side while that makes its worst rectangle squarer
+        let side = area.width.min(area.height);
+        let mut end = start + 1;
+        let mut row_area = areas[start];
+        while end < areas.len() {
+            let grown = row_area + areas[end];
+            if worst_ratio(&areas[start..=end], grown, side)
+                > worst_ratio(&areas[start..end], row_area, side)
+            {
+                break;
+            }
+            row_area = grown;
+            end += 1;
+        }
+
+        let thickness = if side > 0.0 { row_area / side } else { 0.0 };
+        let mut offset = 0.0;
+        for &a in &areas[start..end] {
+            let length = if thickness > 0.0 { a / thickness } else { 0.0 };
+            rects.push(if area.width >= area.height {
+                Rect {
+                    x: area.x,
+                    y: area.y + offset,
+                    width: thickness,
+                    height: length,
+                }
+            } else {
+                Rect {
+                    x: area.x + offset,
+                    y: area.y,
+                    width: length,
+                    height: thickness,
+                }
+            });
+            offset += length;
+        }
+
+        if area.width >= area.height {
+            area.x += thickness;
+            area.width = (area.width - thickness).max(0.0);
+        } else {
+            area.y += thickness;
+            area.height = (area.height - thickness).max(0.0);
+        }
+        start = end;
+    }
+    rects
+}
+
+/// Worst aspect ratio of a row of areas, sorted largest first, laid along `side`
+fn worst_ratio(row: &[f64], row_area: f64, side: f64) -> f64 {
+    let largest = row[0];
+    let smallest = row[row.len() - 1];
+    let side = side * side;
+    let row_area = row_area * row_area;
+    (side * largest / row_area).max(row_area / (side * smallest))
+}
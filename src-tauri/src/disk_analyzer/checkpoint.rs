@@ -0,0 +1,481 @@
+use super::{ScanConfig, ScanSession, ScanType};
+use crate::file_system::FileInfo;
+use crate::file_table::{EntryId, FileTable};
+use crate::scan_index::write_synced;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+const MANIFEST_FILE: &str = "checkpoint.json";
+
+/// How often a running scan writes its checkpoint
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Session metadata saved with every checkpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanCheckpoint {
+    pub session_id: String,
+    pub disk_path: String,
+    pub scan_type: ScanType,
+    pub config: ScanConfig,
+    pub created_at: DateTime<Utc>,
+    pub phase: String, // "quick" or "deep"
+    pub saved_at: DateTime<Utc>,
+}
+
+/// An unfinished scan loaded back from its last checkpoint
+#[derive(Debug, Clone)]
+pub struct InterruptedScan {
+    pub checkpoint: ScanCheckpoint,
+    pub directories: HashMap<String, DateTime<Utc>>, // Directories fully walked
+    pub files: FileTable,                            // Files found, with hashes computed so far
+}
+
+/// Contents of `checkpoint.json`
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    #[serde(flatten)]
+    checkpoint: ScanCheckpoint,
+    segments: Vec<u64>, // Generations of the segments to replay, oldest first
+}
+
+/// One line of a segment
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Record<'a> {
+    File(Cow<'a, FileInfo>),
+    Hash {
+        path: Cow<'a, str>,
+        hash: Cow<'a, str>,
+    }, // Hash of a file stored by an earlier segment
+    Directory {
+        path: Cow<'a, str>,
+        modified: DateTime<Utc>,
+    },
+}
+
+/// What one save adds to a checkpoint
+enum Segment<'a> {
+    Full {
+        directories: &'a HashMap<String, DateTime<Utc>>,
+        files: &'a FileTable,
+    },
+    Changes {
+        directories: Vec<(String, DateTime<Utc>)>,
+        files: Vec<FileInfo>,
+        hashes: Vec<(String, String)>,
+    }, // Found since the previous save
+}
+
+impl Segment<'_> {
+    fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+        let mut line = |record: Record| -> Result<()> {
+            serde_json::to_writer(&mut *writer, &record)?;
+            writer.write_all(b"\n")?;
+            Ok(())
+        };
+
+        match self {
+            Segment::Full { directories, files } => {
+                for file in files.iter() {
+                    line(Record::File(Cow::Owned(file)))?;
+                }
+                for (path, modified) in directories.iter() {
+                    line(Record::Directory {
+                        path: path.into(),
+                        modified: *modified,
+                    })?;
+                }
+            }
+            Segment::Changes {
+                directories,
+                files,
+                hashes,
+            } => {
+                for file in files {
+                    line(Record::File(Cow::Borrowed(file)))?;
+                }
+                for (path, hash) in hashes {
+                    line(Record::Hash {
+                        path: path.into(),
+                        hash: hash.into(),
+                    })?;
+                }
+                for (path, modified) in directories {
+                    line(Record::Directory {
+                        path: path.into(),
+                        modified: *modified,
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn segment_file(generation: u64) -> String {
+    format!("segment-{}.jsonl", generation)
+}
+
+/// On-disk store of scan checkpoints, one directory per session.
+///
+/// A checkpoint is a chain of segments, each holding the files, hashes and
+/// directories found since the one before, and a `checkpoint.json` manifest
+/// naming the segments to replay. A save writes and syncs one new segment,
+/// then renames a new manifest into place, so a crash keeps the previous
+/// checkpoint whole. Segments no manifest names any more are then removed.
+#[derive(Debug, Clone, Default)]
+pub struct CheckpointStore {
+    dir: Option<PathBuf>,
+}
+
+impl CheckpointStore {
+    /// Open (or create) the checkpoint store in `dir`
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir: Some(dir) })
+    }
+
+    /// Write a complete checkpoint of a running session, replacing the previous one
+    pub fn save(
+        &self,
+        checkpoint: &ScanCheckpoint,
+        directories: &HashMap<String, DateTime<Utc>>,
+        files: &FileTable,
+    ) -> Result<()> {
+        self.write(checkpoint, &Segment::Full { directories, files }, false)
+    }
+
+    /// Write `segment` and make it the session's checkpoint, after the
+    /// segments of the current one if `append` is set
+    fn write(&self, checkpoint: &ScanCheckpoint, segment: &Segment, append: bool) -> Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        let session_dir = dir.join(&checkpoint.session_id);
+        fs::create_dir_all(&session_dir)?;
+
+        let mut segments = match Self::load_manifest(&session_dir) {
+            Ok(manifest) => manifest.segments,
+            Err(_) => Vec::new(),
+        };
+        let generation = segments.last().map_or(1, |last| last + 1);
+        if !append {
+            segments.clear();
+        }
+        segments.push(generation);
+
+        write_synced(&session_dir.join(segment_file(generation)), |writer| {
+            segment.write_to(writer)
+        })?;
+
+        let manifest = Manifest {
+            checkpoint: checkpoint.clone(),
+            segments,
+        };
+        let staged = session_dir.join(format!("{}.tmp", MANIFEST_FILE));
+        write_synced(&staged, |writer| {
+            Ok(serde_json::to_writer_pretty(writer, &manifest)?)
+        })?;
+        fs::rename(&staged, session_dir.join(MANIFEST_FILE))?;
+
+        // Segments of replaced chains, or written by saves that never finished
+        for entry in fs::read_dir(&session_dir)?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let unused = match name.strip_prefix("segment-") {
+                Some(rest) => rest
+                    .strip_suffix(".jsonl")
+                    .and_then(|g| g.parse().ok())
+                    .is_none_or(|g| !manifest.segments.contains(&g)),
+                None => false,
+            };
+            if unused {
+                if let Err(e) = fs::remove_file(entry.path()) {
+                    tracing::warn!("Failed to remove {}: {}", entry.path().display(), e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Load every checkpoint left behind by an unfinished session
+    pub fn load_all(&self) -> Vec<InterruptedScan> {
+        let Some(dir) = &self.dir else {
+            return Vec::new();
+        };
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!("Could not list checkpoints in {}: {}", dir.display(), e);
+                return Vec::new();
+            }
+        };
+
+        let mut interrupted = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+
+            match Self::load(&path) {
+                Ok(scan) => interrupted.push(scan),
+                Err(e) => {
+                    tracing::warn!("Skipping unreadable checkpoint {}: {}", path.display(), e);
+                }
+            }
+        }
+
+        interrupted.sort_by_key(|scan| scan.checkpoint.created_at);
+        interrupted
+    }
+
+    /// Forget the checkpoint of a session that completed or was cancelled
+    pub fn remove(&self, session_id: &str) -> Result<()> {
+        if let Some(dir) = &self.dir {
+            let session_dir = dir.join(session_id);
+            if session_dir.exists() {
+                fs::remove_dir_all(session_dir)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn load_manifest(session_dir: &Path) -> Result<Manifest> {
+        Ok(serde_json::from_reader(BufReader::new(fs::File::open(
+            session_dir.join(MANIFEST_FILE),
+        )?))?)
+    }
+
+    fn load(session_dir: &Path) -> Result<InterruptedScan> {
+        let manifest = Self::load_manifest(session_dir)?;
+        let mut files = FileTable::new();
+        let mut directories = HashMap::new();
+        let mut hashes = Vec::new();
+        for generation in &manifest.segments {
+            let reader =
+                BufReader::new(fs::File::open(session_dir.join(segment_file(*generation)))?);
+            for line in reader.lines() {
+                let line = line?;
+                if line.is_empty() {
+                    continue;
+                }
+                match serde_json::from_str(&line)? {
+                    Record::File(file) => {
+                        files.push(&file);
+                    }
+                    Record::Hash { path, hash } => hashes.push((path, hash)),
+                    Record::Directory { path, modified } => {
+                        directories.insert(path.into_owned(), modified);
+                    }
+                }
+            }
+        }
+
+        // Applied once every file is in, so lookups build the child index once
+        for (path, hash) in hashes {
+            if let Some(id) = files.find(Path::new(path.as_ref())).map(|f| f.id()) {
+                files.set_hash(id, &hash);
+            }
+        }
+
+        Ok(InterruptedScan {
+            checkpoint: manifest.checkpoint,
+            directories,
+            files,
+        })
+    }
+}
+
+/// What a [`Checkpointer`] hands to a write
+enum Save {
+    Everything(HashMap<String, DateTime<Utc>>, FileTable),
+    Changes(Segment<'static>, bool), // And whether they follow the last checkpoint written
+}
+
+/// Periodically checkpoints one running session.
+///
+/// The scan reports what it finds, and each save writes only what was found
+/// since the previous one. Writes run on a blocking thread and the scan does
+/// not wait for them; while one is running, saves that come due are put off.
+pub struct Checkpointer {
+    store: CheckpointStore,
+    checkpoint: ScanCheckpoint,
+    last_saved: Instant,
+    chained: bool, // A save of this run was handed to a write, so later changes follow it
+    added: Vec<EntryId>, // Files found since the last save
+    hashed: Vec<EntryId>, // Files hashed since the last save
+    walked: Vec<(String, DateTime<Utc>)>, // Directories walked since the last save
+    saves: u64,    // Saves handed to a write so far
+    written: Arc<Mutex<u64>>, // Number of the last save written; held while writing, so writes run one at a time
+    failed: Arc<AtomicBool>,  // A save was lost, so the next one must write everything
+    writing: Option<JoinHandle<()>>,
+}
+
+impl Checkpointer {
+    pub fn new(store: CheckpointStore, session: &ScanSession) -> Self {
+        Self {
+            store,
+            checkpoint: ScanCheckpoint {
+                session_id: session.id.clone(),
+                disk_path: session.disk_path.clone(),
+                scan_type: session.scan_type.clone(),
+                config: session.config.clone(),
+                created_at: session.created_at,
+                phase: "quick".to_string(),
+                saved_at: Utc::now(),
+            },
+            last_saved: Instant::now(),
+            chained: false,
+            added: Vec::new(),
+            hashed: Vec::new(),
+            walked: Vec::new(),
+            saves: 0,
+            written: Arc::new(Mutex::new(0)),
+            failed: Arc::new(AtomicBool::new(false)),
+            writing: None,
+        }
+    }
+
+    /// Note a file added to the table since the last save
+    pub fn file_added(&mut self, id: EntryId) {
+        self.added.push(id);
+    }
+
+    /// Note a file whose hash was set since the last save
+    pub fn file_hashed(&mut self, id: EntryId) {
+        self.hashed.push(id);
+    }
+
+    /// Note a directory walked since the last save
+    pub fn directory_walked(&mut self, path: &str, modified: DateTime<Utc>) {
+        self.walked.push((path.to_string(), modified));
+    }
+
+    /// Save what changed, unless a checkpoint was written less than
+    /// [`CHECKPOINT_INTERVAL`] ago or is still being written
+    pub fn maybe_save(
+        &mut self,
+        phase: &str,
+        directories: &HashMap<String, DateTime<Utc>>,
+        files: &FileTable,
+    ) {
+        let writing = self.writing.as_ref().is_some_and(|w| !w.is_finished());
+        if writing || self.last_saved.elapsed() < CHECKPOINT_INTERVAL {
+            return;
+        }
+        if self.failed.swap(false, Ordering::SeqCst) {
+            self.save(phase, directories, files);
+            return;
+        }
+
+        let segment = Segment::Changes {
+            directories: std::mem::take(&mut self.walked),
+            files: std::mem::take(&mut self.added)
+                .into_iter()
+                .filter_map(|id| files.get(id))
+                .map(|file| file.to_file_info())
+                .collect(),
+            hashes: std::mem::take(&mut self.hashed)
+                .into_iter()
+                .filter_map(|id| files.get(id))
+                .filter_map(|file| {
+                    let hash = file.hash()?.to_string();
+                    Some((file.path().to_string_lossy().to_string(), hash))
+                })
+                .collect(),
+        };
+        // The first save of a run replaces whatever an earlier run left behind
+        let chained = std::mem::replace(&mut self.chained, true);
+        self.spawn_write(phase, Save::Changes(segment, chained));
+    }
+
+    /// Save everything now. The table is copy-on-write, so the snapshot
+    /// handed to the write costs little.
+    pub fn save(
+        &mut self,
+        phase: &str,
+        directories: &HashMap<String, DateTime<Utc>>,
+        files: &FileTable,
+    ) {
+        self.added.clear();
+        self.hashed.clear();
+        self.walked.clear();
+        self.chained = true;
+        self.failed.store(false, Ordering::SeqCst);
+        self.spawn_write(phase, Save::Everything(directories.clone(), files.clone()));
+    }
+
+    /// Write on a blocking thread; failures are logged since the scan itself
+    /// can go on without them
+    fn spawn_write(&mut self, phase: &str, save: Save) {
+        self.checkpoint.phase = phase.to_string();
+        self.checkpoint.saved_at = Utc::now();
+        self.last_saved = Instant::now();
+        self.saves += 1;
+
+        let number = self.saves;
+        let store = self.store.clone();
+        let checkpoint = self.checkpoint.clone();
+        let written = self.written.clone();
+        let failed = self.failed.clone();
+        self.writing = Some(tokio::task::spawn_blocking(move || {
+            // A save overtaken by a newer one is dropped, the newer one holds its changes
+            let mut written = written.lock().unwrap_or_else(|e| e.into_inner());
+            if *written >= number {
+                return;
+            }
+            *written = number;
+
+            let result = match save {
+                Save::Everything(directories, files) => {
+                    store.save(&checkpoint, &directories, &files)
+                }
+                Save::Changes(segment, append) => store.write(&checkpoint, &segment, append),
+            };
+            if let Err(e) = result {
+                failed.store(true, Ordering::SeqCst);
+                tracing::warn!(
+                    "Failed to checkpoint scan session {}: {}",
+                    checkpoint.session_id,
+                    e
+                );
+            }
+        }));
+    }
+
+    /// Drop the checkpoint once the session no longer needs resuming
+    pub async fn discard(&mut self) {
+        let store = self.store.clone();
+        let written = self.written.clone();
+        let session_id = self.checkpoint.session_id.clone();
+        let removed = tokio::task::spawn_blocking(move || {
+            // Waits for a running write; any still queued find themselves overtaken
+            *written.lock().unwrap_or_else(|e| e.into_inner()) = u64::MAX;
+            store.remove(&session_id)
+        })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|removed| removed);
+
+        if let Err(e) = removed {
+            tracing::warn!(
+                "Failed to remove checkpoint of scan session {}: {}",
+                self.checkpoint.session_id,
+                e
+            );
+        }
+    }
+}
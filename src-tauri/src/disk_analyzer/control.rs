@@ -0,0 +1,93 @@
+use super::eta::EtaEstimator;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Pause/cancel handle and counters owned by a single scan session.
+///
+/// Controls form a tree: a child created with [`ScanControl::child`] is
+/// paused or cancelled whenever one of its ancestors is, so cancelling a
+/// session also stops the workers of whatever phase it is running.
+/// Counters and time estimates are not shared with the parent.
+#[derive(Debug, Default)]
+pub struct ScanControl {
+    paused: AtomicBool,
+    cancelled: AtomicBool,
+    files_processed: AtomicU64,
+    bytes_processed: AtomicU64,
+    eta: Mutex<EtaEstimator>,
+    parent: Option<Arc<ScanControl>>,
+}
+
+impl ScanControl {
+    /// Create a control that follows this one's pause and cancel state
+    pub fn child(self: &Arc<Self>) -> Arc<Self> {
+        Arc::new(Self {
+            parent: Some(self.clone()),
+            ..Self::default()
+        })
+    }
+
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst) || self.parent.as_ref().is_some_and(|p| p.is_paused())
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+            || self.parent.as_ref().is_some_and(|p| p.is_cancelled())
+    }
+
+    /// Block a worker thread while paused; cancelling also ends the wait
+    pub fn wait_if_paused_blocking(&self) {
+        while self.is_paused() && !self.is_cancelled() {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    /// Count one processed file of `bytes` bytes, returning the new file count
+    pub fn add_file(&self, bytes: u64) -> u64 {
+        self.bytes_processed.fetch_add(bytes, Ordering::SeqCst);
+        self.files_processed.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    pub fn files_processed(&self) -> u64 {
+        self.files_processed.load(Ordering::SeqCst)
+    }
+
+    pub fn bytes_processed(&self) -> u64 {
+        self.bytes_processed.load(Ordering::SeqCst)
+    }
+
+    /// Set the bytes this control is expected to process, used to estimate the time left
+    pub fn expect_bytes(&self, total: u64) {
+        self.eta.lock().unwrap().set_total(total);
+    }
+
+    pub fn expected_bytes(&self) -> u64 {
+        self.eta.lock().unwrap().total()
+    }
+
+    /// Estimated time until the expected bytes are processed, `None` until the throughput is known
+    pub fn remaining_time(&self) -> Option<Duration> {
+        let done = self.bytes_processed();
+        let mut eta = self.eta.lock().unwrap();
+        if self.is_paused() {
+            eta.pause();
+        } else {
+            eta.record(Instant::now(), done);
+        }
+        eta.remaining(done)
+    }
+}
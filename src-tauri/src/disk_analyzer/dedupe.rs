@@ -0,0 +1,199 @@
+use super::{DuplicateGroup, DuplicateStrategy, ScanConfig};
+use crate::file_table::{EntryId, FileTable};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Stage of the duplicate search; each one only looks at the files the
+/// previous one could not tell apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupeStage {
+    Size,   // Exact size, read from the file table
+    Name,   // Same name as well, for the name based strategies
+    Blocks, // Hash of the first, middle and last 64 KB
+    Full,   // Hash of the whole contents
+}
+
+/// What one stage of the duplicate search did
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DedupeStageReport {
+    pub stage: DedupeStage,
+    pub files_in: u64,
+    pub candidates: u64, // Files still possibly duplicated afterwards
+    pub groups: u64,
+    pub bytes_read: u64,  // File contents read by this stage, without cache hits
+    pub bytes_saved: u64, // Contents of the files it was given that no stage has to read
+}
+
+/// Stages run for a scan, derived from its duplicate strategy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DedupePlan {
+    pub by_name: bool,
+    pub blocks: bool,
+    pub full: bool,
+    pub block_threshold: u64, // Smaller files skip the blocks stage when fully hashed anyway
+}
+
+impl DedupePlan {
+    pub fn for_config(config: &ScanConfig) -> Self {
+        let (by_name, blocks, full) = match config.duplicate_strategy {
+            DuplicateStrategy::HashOnly => (false, true, true),
+            DuplicateStrategy::NameAndSize => (true, false, false),
+            DuplicateStrategy::NameSizePartialHash => (true, true, false),
+            DuplicateStrategy::SmartDetection => (false, true, config.calculate_hashes),
+        };
+        Self {
+            by_name,
+            blocks,
+            full,
+            block_threshold: config.quick_hash_threshold,
+        }
+    }
+
+    /// Whether `size` bytes go through the blocks stage
+    pub fn samples_blocks(&self, size: u64) -> bool {
+        self.blocks && (!self.full || size > self.block_threshold)
+    }
+}
+
+/// Files that may share their contents, with what they were found to share
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidateGroup {
+    pub key: String,
+    pub files: Vec<EntryId>, // In table order
+}
+
+/// Groups of possible duplicates, narrowed down stage by stage.
+///
+/// Every stage splits groups further and drops the files left alone in
+/// theirs; files from different groups are never compared again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Candidates {
+    groups: Vec<CandidateGroup>,
+}
+
+impl Candidates {
+    /// Group the non-empty files of `table` by exact size
+    pub fn by_size(table: &FileTable) -> Self {
+        let mut by_size: HashMap<u64, Vec<EntryId>> = HashMap::new();
+        for file in table.entries() {
+            if !file.is_directory() && file.size() > 0 {
+                by_size.entry(file.size()).or_default().push(file.id());
+            }
+        }
+        let mut groups: Vec<CandidateGroup> = by_size
+            .into_iter()
+            .filter(|(_, files)| files.len() > 1)
+            .map(|(size, files)| CandidateGroup {
+                key: size.to_string(),
+                files,
+            })
+            .collect();
+        groups.sort_by_key(|group| group.files[0]);
+        Self { groups }
+    }
+
+    /// Split every group by `key_of`, appending it to the group key. Files
+    /// without a key, such as those that could not be read, are dropped.
+    pub fn refine(self, key_of: impl FnMut(EntryId) -> Option<String>) -> Self {
+        self.split(key_of, |group, part| format!("{}:{}", group, part))
+    }
+
+    /// Split every group by `key_of`, which becomes the key of the new groups
+    pub fn regroup(self, key_of: impl FnMut(EntryId) -> Option<String>) -> Self {
+        self.split(key_of, |_, part| part.to_string())
+    }
+
+    fn split(
+        self,
+        mut key_of: impl FnMut(EntryId) -> Option<String>,
+        key: impl Fn(&str, &str) -> String,
+    ) -> Self {
+        let mut groups = Vec::new();
+        for group in self.groups {
+            let mut parts: Vec<(String, Vec<EntryId>)> = Vec::new();
+            let mut index: HashMap<String, usize> = HashMap::new();
+            for id in group.files {
+                let Some(part) = key_of(id) else {
+                    continue;
+                };
+                match index.get(&part) {
+                    Some(&i) => parts[i].1.push(id),
+                    None => {
+                        index.insert(part.clone(), parts.len());
+                        parts.push((part, vec![id]));
+                    }
+                }
+            }
+            groups.extend(parts.into_iter().filter(|(_, files)| files.len() > 1).map(
+                |(part, files)| CandidateGroup {
+                    key: key(&group.key, &part),
+                    files,
+                },
+            ));
+        }
+        Self { groups }
+    }
+
+    pub fn groups(&self) -> &[CandidateGroup] {
+        &self.groups
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = EntryId> + '_ {
+        self.groups
+            .iter()
+            .flat_map(|group| group.files.iter().copied())
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.groups.iter().map(|group| group.files.len()).sum()
+    }
+
+    /// Combined size of the candidates in `table`
+    pub fn bytes(&self, table: &FileTable) -> u64 {
+        self.files()
+            .filter_map(|id| table.get(id))
+            .map(|file| file.size())
+            .sum()
+    }
+
+    /// Report of a stage that turned `before` into these candidates.
+    /// `bytes_pending` are the contents later stages will still read.
+    pub fn report(
+        &self,
+        stage: DedupeStage,
+        before: (u64, u64), // Files and bytes given to the stage
+        bytes_read: u64,
+        bytes_pending: u64,
+    ) -> DedupeStageReport {
+        let (files_in, bytes_in) = before;
+        DedupeStageReport {
+            stage,
+            files_in,
+            candidates: self.file_count() as u64,
+            groups: self.groups.len() as u64,
+            bytes_read,
+            bytes_saved: bytes_in.saturating_sub(bytes_read + bytes_pending),
+        }
+    }
+
+    /// The remaining groups as duplicates, largest savings first
+    pub fn into_duplicate_groups(self, table: &FileTable) -> Vec<DuplicateGroup> {
+        let mut groups: Vec<DuplicateGroup> = self
+            .groups
+            .into_iter()
+            .map(|group| {
+                let files = group
+                    .files
+                    .iter()
+                    .filter_map(|&id| table.get(id))
+                    .map(|file| file.to_file_info())
+                    .collect();
+                DuplicateGroup::new(group.key, files)
+            })
+            .collect();
+        groups.sort_by_key(|group| Reverse(group.potential_savings));
+        groups
+    }
+}
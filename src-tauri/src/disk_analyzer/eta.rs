@@ -0,0 +1,61 @@
+use std::time::{Duration, Instant};
+
+/// Weight of the newest throughput sample in the smoothed rate
+const SMOOTHING: f64 = 0.3;
+
+/// Shortest interval a throughput sample is measured over
+const MIN_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Remaining-time estimate for a phase that works through a known number of bytes.
+///
+/// Throughput is sampled at most every [`MIN_SAMPLE_INTERVAL`] and smoothed
+/// with an exponential moving average, so a burst of small files or a slow
+/// directory moves the estimate without making it jump around. Time spent
+/// paused is not sampled.
+#[derive(Debug, Default)]
+pub struct EtaEstimator {
+    total_bytes: u64,
+    last_sample: Option<(Instant, u64)>, // Time and bytes done at the last sample
+    rate: Option<f64>,                   // Smoothed bytes per second
+}
+
+impl EtaEstimator {
+    pub fn set_total(&mut self, total_bytes: u64) {
+        self.total_bytes = total_bytes;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total_bytes
+    }
+
+    /// Record that `done` bytes were processed by `now`
+    pub fn record(&mut self, now: Instant, done: u64) {
+        let Some((then, done_then)) = self.last_sample else {
+            self.last_sample = Some((now, done));
+            return;
+        };
+        let interval = now.saturating_duration_since(then);
+        if interval < MIN_SAMPLE_INTERVAL {
+            return;
+        }
+
+        let sample = done.saturating_sub(done_then) as f64 / interval.as_secs_f64();
+        self.rate = Some(match self.rate {
+            Some(rate) => SMOOTHING * sample + (1.0 - SMOOTHING) * rate,
+            None => sample,
+        });
+        self.last_sample = Some((now, done));
+    }
+
+    /// Forget the last sample so the time until the next one is not measured
+    pub fn pause(&mut self) {
+        self.last_sample = None;
+    }
+
+    /// Time left to process the remaining bytes, `None` until the throughput is known
+    pub fn remaining(&self, done: u64) -> Option<Duration> {
+        let rate = self.rate.filter(|&rate| rate > 0.0)?;
+        let left = self.total_bytes.saturating_sub(done);
+        Duration::try_from_secs_f64(left as f64 / rate).ok()
+    }
+}
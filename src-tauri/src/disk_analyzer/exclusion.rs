@@ -0,0 +1,138 @@
+use super::ScanConfig;
+use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Ignore files honored during the walk when the config asks for them
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ddignore"];
+
+/// Exclude patterns of a scan, matched with gitignore semantics.
+///
+/// A pattern without a slash matches a name at any depth (`node_modules`,
+/// `*.iso`), one with a slash is anchored to the scan root (`/Windows`,
+/// `home/*/cache`), `**` spans directories and a leading `!` re-includes
+/// what an earlier pattern excluded. Absolute paths under the scan root are
+/// anchored to it. Matching ignores case on Windows only.
+#[derive(Debug, Clone)]
+pub struct ExclusionRules {
+    patterns: Gitignore,
+    use_ignore_files: bool,
+}
+
+/// `.gitignore` / `.ddignore` files of the directories above the one being walked
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreFiles(Option<Arc<IgnoreLayer>>);
+
+#[derive(Debug)]
+struct IgnoreLayer {
+    matcher: Gitignore,
+    parent: IgnoreFiles,
+}
+
+impl ExclusionRules {
+    /// Compile the exclude patterns of `config` for a scan of `root`
+    pub fn new(root: &Path, config: &ScanConfig) -> Result<Self> {
+        let mut builder = Self::builder(root);
+        for pattern in &config.exclude_patterns {
+            builder
+                .add_line(None, &Self::anchor_absolute(root, pattern))
+                .with_context(|| format!("Invalid exclude pattern '{}'", pattern))?;
+        }
+
+        Ok(Self {
+            patterns: builder.build()?,
+            use_ignore_files: config.use_ignore_files,
+        })
+    }
+
+    /// Add the ignore files found in `dir` on top of those of its parents
+    pub fn enter(&self, dir: &Path, parent: &IgnoreFiles) -> IgnoreFiles {
+        if !self.use_ignore_files {
+            return parent.clone();
+        }
+
+        let mut builder = Self::builder(dir);
+        let mut found = false;
+        for name in IGNORE_FILES {
+            let file = dir.join(name);
+            if file.is_file() {
+                found = true;
+                if let Some(e) = builder.add(&file) {
+                    tracing::warn!("Problem reading {}: {}", file.display(), e);
+                }
+            }
+        }
+        if !found {
+            return parent.clone();
+        }
+
+        match builder.build() {
+            Ok(matcher) => IgnoreFiles(Some(Arc::new(IgnoreLayer {
+                matcher,
+                parent: parent.clone(),
+            }))),
+            Err(e) => {
+                tracing::warn!("Ignoring ignore files in {}: {}", dir.display(), e);
+                parent.clone()
+            }
+        }
+    }
+
+    /// Whether `path` is excluded, checking the scan patterns first and then
+    /// the ignore files from the closest directory up
+    pub fn is_excluded(&self, path: &Path, is_dir: bool, ignore_files: &IgnoreFiles) -> bool {
+        match self.patterns.matched(path, is_dir) {
+            Match::Ignore(_) => return true,
+            Match::Whitelist(_) => return false,
+            Match::None => {}
+        }
+
+        let mut layer = ignore_files.0.as_deref();
+        while let Some(current) = layer {
+            match current.matcher.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => layer = current.parent.0.as_deref(),
+            }
+        }
+        false
+    }
+
+    /// Like [`ExclusionRules::is_excluded`] for flat file lists, where
+    /// excluded parent directories were not pruned by a walk
+    pub fn is_excluded_with_parents(&self, path: &Path, is_dir: bool) -> bool {
+        path.starts_with(self.patterns.path())
+            && self
+                .patterns
+                .matched_path_or_any_parents(path, is_dir)
+                .is_ignore()
+    }
+
+    fn builder(root: &Path) -> GitignoreBuilder {
+        let mut builder = GitignoreBuilder::new(root);
+        // Only fails for case-insensitive matching without Unicode support
+        let _ = builder.case_insensitive(cfg!(windows));
+        builder
+    }
+
+    /// Rewrite an absolute path under `root` as a pattern anchored to it
+    fn anchor_absolute(root: &Path, pattern: &str) -> String {
+        let (negation, body) = match pattern.strip_prefix('!') {
+            Some(body) => ("!", body),
+            None => ("", pattern),
+        };
+
+        match Path::new(body).strip_prefix(root) {
+            Ok(relative) if Path::new(body).is_absolute() && !relative.as_os_str().is_empty() => {
+                format!(
+                    "{}/{}",
+                    negation,
+                    relative.to_string_lossy().replace('\\', "/")
+                )
+            }
+            _ => pattern.to_string(),
+        }
+    }
+}
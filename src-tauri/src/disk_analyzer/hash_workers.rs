@@ -0,0 +1,44 @@
+use anyhow::Result;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use tokio::sync::mpsc;
+
+/// Outcomes buffered between the hashing workers and the task collecting them
+pub const RESULT_CAPACITY: usize = 100;
+
+/// Hash `items` on `pool` and hand every outcome to the returned receiver.
+///
+/// Workers block while the channel is full, so a consumer that falls behind
+/// slows hashing down rather than losing results: every item `hash` returns
+/// an outcome for is received, hashed or failed. `hash` returns `None` for
+/// items it skips, such as when the scan is cancelled. Once the receiver is
+/// dropped, workers stop taking new items.
+pub fn spawn<T, F>(
+    pool: ThreadPool,
+    items: Vec<T>,
+    capacity: usize,
+    hash: F,
+) -> mpsc::Receiver<(T, Result<String>)>
+where
+    T: Send + 'static,
+    F: Fn(&T) -> Option<Result<String>> + Send + Sync + 'static,
+{
+    let (tx, rx) = mpsc::channel(capacity);
+
+    // The pool runs off the async runtime, where blocking on the channel is allowed
+    tokio::task::spawn_blocking(move || {
+        pool.install(|| {
+            items.into_par_iter().for_each(|item| {
+                if tx.is_closed() {
+                    return;
+                }
+                if let Some(outcome) = hash(&item) {
+                    // Only fails once the receiver is gone
+                    let _ = tx.blocking_send((item, outcome));
+                }
+            })
+        })
+    });
+
+    rx
+}
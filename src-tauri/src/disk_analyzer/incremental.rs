@@ -0,0 +1,123 @@
+use super::ScanChanges;
+use crate::file_system::{path_encoding, FileInfo};
+use crate::file_table::{FileRef, FileTable};
+use crate::hashing::HashAlgorithm;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Snapshot of an earlier walk of a root, used by incremental rescans and to
+/// resume interrupted scans
+#[derive(Debug, Default)]
+pub struct PreviousSnapshot {
+    files: FileTable,
+    subdirs_by_dir: HashMap<PathBuf, Vec<PathBuf>>,
+    directories: HashMap<PathBuf, DateTime<Utc>>,
+    algorithm: HashAlgorithm, // Hashes by other algorithms are not reused
+}
+
+impl PreviousSnapshot {
+    /// Build a snapshot from the files and fully walked directories of an
+    /// earlier walk, for a scan hashing by `algorithm`
+    pub fn new(
+        files: &FileTable,
+        directories: &HashMap<String, DateTime<Utc>>,
+        algorithm: HashAlgorithm,
+    ) -> Self {
+        let mut snapshot = Self {
+            files: files.clone(),
+            algorithm,
+            ..Self::default()
+        };
+
+        for (dir, modified) in directories {
+            let dir = path_encoding::decode_path(dir);
+            if let Some(parent) = dir.parent() {
+                snapshot
+                    .subdirs_by_dir
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .push(dir.clone());
+            }
+            snapshot.directories.insert(dir, *modified);
+        }
+
+        snapshot
+    }
+
+    /// Whether `dir` still has the modification time recorded by the previous scan
+    pub fn is_unchanged(&self, dir: &Path, modified: DateTime<Utc>) -> bool {
+        self.directories.get(dir) == Some(&modified)
+    }
+
+    /// Files that lived directly in `dir` during the previous scan
+    pub fn files_in(&self, dir: &Path) -> impl Iterator<Item = FileInfo> + '_ {
+        self.files
+            .children_of(Path::new(&path_encoding::encode_path(dir)))
+            .filter(|file| !file.is_directory())
+            .map(|file| {
+                let mut info = file.to_file_info();
+                info.hash = info.hash.filter(|hash| self.algorithm.tagged(hash));
+                info
+            })
+    }
+
+    /// Subdirectories of `dir` seen during the previous scan
+    pub fn subdirs_of(&self, dir: &Path) -> &[PathBuf] {
+        self.subdirs_by_dir
+            .get(dir)
+            .map(|dirs| dirs.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Reuse hashes of files that did not change since the previous scan
+    pub fn carry_forward_hashes(&self, files: &mut FileTable) -> usize {
+        let reusable: Vec<_> = files
+            .entries()
+            .filter(|file| file.hash().is_none())
+            .filter_map(|file| {
+                let previous = self.files.find(&file.path())?;
+                let hash = previous.hash().filter(|hash| self.algorithm.tagged(hash))?;
+                Self::same_entry(&previous, &file).then(|| (file.id(), hash))
+            })
+            .collect();
+
+        for (id, hash) in &reusable {
+            files.set_hash(*id, hash);
+        }
+        reusable.len()
+    }
+
+    /// Compare the new file list against the previous scan
+    pub fn diff(&self, files: &FileTable) -> ScanChanges {
+        let mut changes = ScanChanges::default();
+        let mut seen = HashSet::with_capacity(files.len());
+
+        for file in files.entries() {
+            let path = file.path();
+            match self.files.find(&path) {
+                None => changes.added.push(path.to_string_lossy().to_string()),
+                Some(previous) => {
+                    seen.insert(previous.id());
+                    if !Self::same_entry(&previous, &file) {
+                        changes.modified.push(path.to_string_lossy().to_string())
+                    }
+                }
+            }
+        }
+
+        changes.removed = self
+            .files
+            .entries()
+            .filter(|file| !seen.contains(&file.id()))
+            .map(|file| file.path().to_string_lossy().to_string())
+            .collect();
+
+        changes
+    }
+
+    /// Same size, modification time and inode
+    fn same_entry(a: &FileRef, b: &FileRef) -> bool {
+        a.size() == b.size() && a.modified() == b.modified() && a.inode() == b.inode()
+    }
+}
@@ -1,32 +1,61 @@
+use crate::app_config::{HashingConfig, PerformanceConfig, ScannerConfig};
+use crate::app_state::SimpleStorage;
 use crate::commands::file_commands::ScanOptions;
-use crate::file_system::FileInfo;
+use crate::error::DiskDominatorError;
+use crate::file_system::{path_encoding, FileInfo, HardLinkSet, SizeBasis};
+use crate::file_table::{EntryId, FileTable};
+use crate::hash_cache::{HashCache, HashCacheStats, HashCounters, HashKind};
+use crate::hashing::{self, HashAlgorithm};
+use crate::scan_index::ScanRecord;
 use crate::websocket::{ScanProgressMessage, WebSocketManager};
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::fs;
-use tokio::io::{AsyncReadExt, BufReader};
 use tokio::sync::{mpsc, Mutex, RwLock};
 use uuid::Uuid;
 
+pub mod checkpoint;
+pub mod control;
+pub mod dedupe;
+pub mod eta;
+pub mod exclusion;
+pub mod hash_workers;
+mod incremental;
+pub mod scan_errors;
+pub mod throttle;
+mod walker;
+
+use checkpoint::{CheckpointStore, Checkpointer, ScanCheckpoint};
+use control::ScanControl;
+use dedupe::{Candidates, DedupePlan, DedupeStage, DedupeStageReport};
+use exclusion::ExclusionRules;
+use incremental::PreviousSnapshot;
+use scan_errors::{ScanError, ScanErrorReport, ScanErrors};
+use throttle::Throttle;
+use walker::{WalkEntry, Walker};
+
+/// How many files the quick scan collects between memory limit checks
+const MEMORY_CHECK_INTERVAL: usize = 10_000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ScanType {
     Quick,
     Deep,
     Custom,
+    Incremental, // Reuse the previous scan of the same root where nothing changed
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DuplicateStrategy {
-    HashOnly,            // SHA-256 hash comparison (most accurate, slowest)
+    HashOnly,            // Full content hash comparison (most accurate, slowest)
     NameAndSize,         // Name + size comparison (fast, less accurate)
-    NameSizePartialHash, // Name + size + partial hash (balanced)
-    SmartDetection,      // Adaptive based on file size and type
+    NameSizePartialHash, // Name + size + sampled blocks (balanced)
+    SmartDetection,      // Size + sampled blocks, then full hashes if `calculate_hashes`
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,9 +67,13 @@ pub struct ScanConfig {
     pub min_file_size: Option<u64>,
     pub max_file_size: Option<u64>,
     pub calculate_hashes: bool,
-    pub quick_hash_threshold: u64, // Only calculate partial hash for files above this size
+    pub quick_hash_threshold: u64, // Files above this size are sampled before a full hash
     pub duplicate_strategy: DuplicateStrategy,
     pub large_file_threshold: u64, // Files above this size are considered "large"
+    #[serde(default)]
+    pub same_filesystem: bool, // Don't cross into other mounted filesystems
+    #[serde(default)]
+    pub use_ignore_files: bool, // Honor .gitignore / .ddignore files found while walking
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,9 +81,27 @@ pub struct DualScanProgress {
     pub quick_scan: ScanProgress,
     pub deep_scan: ScanProgress,
     pub overall_progress: f64,
-    pub current_phase: String, // "quick", "deep", "completed"
-    pub estimated_total_time: u64,
-    pub elapsed_time: u64,
+    pub current_phase: String,     // "quick", "deep", "completed"
+    pub estimated_total_time: u64, // Seconds, elapsed plus the time left in the current phase; 0 while unknown
+    pub elapsed_time: u64,         // Seconds since the scan started
+    #[serde(default)]
+    pub dedupe_stage: Option<DedupeStage>, // Stage of the duplicate search in the deep phase
+}
+
+impl DualScanProgress {
+    /// Record the time elapsed since `started_at` and the estimated time left in the current phase
+    fn set_timing(&mut self, started_at: Option<DateTime<Utc>>, remaining: Option<Duration>) {
+        self.elapsed_time = started_at
+            .map(|started| (Utc::now() - started).num_seconds().max(0) as u64)
+            .unwrap_or_default();
+        self.estimated_total_time = remaining.map_or(0, |r| self.elapsed_time + r.as_secs());
+    }
+
+    /// Estimated seconds left in the current phase, if known
+    pub fn remaining_time(&self) -> Option<u64> {
+        (self.estimated_total_time > 0)
+            .then(|| self.estimated_total_time.saturating_sub(self.elapsed_time))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +117,10 @@ pub struct ScanSession {
     pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
     pub progress: DualScanProgress,
     pub results: Option<ScanResults>,
+    #[serde(skip)]
+    pub control: Arc<ScanControl>, // Pause/cancel handle and counters of this session
+    #[serde(skip)]
+    pub errors: Arc<std::sync::Mutex<ScanErrors>>, // Paths that could not be scanned
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,12 +135,30 @@ pub enum ScanSessionStatus {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanResults {
-    pub files: Vec<FileInfo>,
+    pub files: FileTable,
     pub duplicate_groups: Vec<DuplicateGroup>,
     pub large_files: Vec<FileInfo>,
     pub total_files: u64,
-    pub total_size: u64,
+    pub total_size: u64, // Apparent size
+    #[serde(default)]
+    pub total_allocated_size: u64, // Size on disk
     pub scan_duration: u64,
+    #[serde(default)]
+    pub directories: HashMap<String, DateTime<Utc>>, // Modification time of every directory walked
+    #[serde(default)]
+    pub changes: Option<ScanChanges>, // Only set by incremental scans
+    #[serde(default)]
+    pub hash_cache: HashCacheStats, // Hashes reused from earlier scans
+    #[serde(default)]
+    pub dedupe: Vec<DedupeStageReport>, // Stages of the duplicate search, in order
+}
+
+/// Files that changed since the previous scan of the same root
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,9 +167,35 @@ pub struct DuplicateGroup {
     pub files: Vec<FileInfo>,
     pub total_size: u64,
     pub potential_savings: u64,
+    #[serde(default)]
+    pub hard_linked: Vec<String>, // Hard links to an earlier member; deleting them frees nothing
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl DuplicateGroup {
+    /// Group `files`, counting the data of hard-linked members once
+    pub fn new(hash: String, files: Vec<FileInfo>) -> Self {
+        let mut hard_links = HardLinkSet::default();
+        let mut total_size = 0;
+        let mut hard_linked = Vec::new();
+        for file in &files {
+            if hard_links.first_sighting(file.hard_link_key()) {
+                total_size += file.size;
+            } else {
+                hard_linked.push(file.path.clone());
+            }
+        }
+
+        Self {
+            potential_savings: total_size - files.first().map_or(0, |f| f.size),
+            hash,
+            files,
+            total_size,
+            hard_linked,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ScanProgress {
     pub total_files: u64,
     pub processed_files: u64,
@@ -107,53 +206,68 @@ pub struct ScanProgress {
 
 #[derive(Clone)]
 pub struct DiskAnalyzer {
-    progress: Arc<Mutex<ScanProgress>>,
     websocket_manager: Arc<WebSocketManager>,
     active_sessions: Arc<RwLock<HashMap<String, ScanSession>>>,
-    is_paused: Arc<AtomicBool>,
-    should_cancel: Arc<AtomicBool>,
-    files_processed: Arc<AtomicU64>,
-    bytes_processed: Arc<AtomicU64>,
-    scan_start_time: Arc<Mutex<Option<Instant>>>,
-}
-
-pub struct AnalyzerStatistics {
-    pub duplicates_count: usize,
-    pub duplicate_size: u64,
-    pub large_files_count: usize,
-    pub last_scan_time: Option<chrono::DateTime<chrono::Utc>>,
+    storage: Option<Arc<RwLock<SimpleStorage>>>,
+    resume_points: Arc<Mutex<HashMap<String, PreviousSnapshot>>>, // Restored sessions not started yet
+    scanner: ScannerConfig,
+    performance: PerformanceConfig,
+    hashing: HashingConfig,
+    throttle: Arc<Throttle>, // Shared by the walker and hashing workers of every session
+    hash_cache: Arc<HashCache>,
 }
 
 impl DiskAnalyzer {
-    pub async fn get_statistics(&self) -> AnalyzerStatistics {
-        // Get current progress stats
-        let progress = self.progress.lock().await;
-
-        // For now, return current scan statistics
-        // TODO: Implement proper result storage
-        AnalyzerStatistics {
-            duplicates_count: 0, // Will be populated when duplicate detection runs
-            duplicate_size: 0,
-            large_files_count: progress.total_files as usize, // Approximate for now
-            last_scan_time: Some(chrono::Utc::now()),         // Current time as we're scanning
-        }
-    }
     pub fn new(websocket_manager: Arc<WebSocketManager>) -> Self {
         Self {
-            progress: Arc::new(Mutex::new(ScanProgress {
-                total_files: 0,
-                processed_files: 0,
-                total_size: 0,
-                current_path: String::new(),
-                errors: Vec::new(),
-            })),
             websocket_manager,
             active_sessions: Arc::new(RwLock::new(HashMap::new())),
-            is_paused: Arc::new(AtomicBool::new(false)),
-            should_cancel: Arc::new(AtomicBool::new(false)),
-            files_processed: Arc::new(AtomicU64::new(0)),
-            bytes_processed: Arc::new(AtomicU64::new(0)),
-            scan_start_time: Arc::new(Mutex::new(None)),
+            storage: None,
+            resume_points: Arc::new(Mutex::new(HashMap::new())),
+            scanner: ScannerConfig::default(),
+            performance: PerformanceConfig::default(),
+            hashing: HashingConfig::default(),
+            throttle: Arc::new(Throttle::default()),
+            hash_cache: Arc::new(HashCache::default()),
+        }
+    }
+
+    /// Use the `[scanner]` settings of the app config
+    pub fn with_scanner_config(self, scanner: ScannerConfig) -> Self {
+        Self { scanner, ..self }
+    }
+
+    /// Use the `[performance]` settings of the app config
+    pub fn with_performance_config(self, performance: PerformanceConfig) -> Self {
+        Self {
+            performance,
+            ..self
+        }
+    }
+
+    /// Use the `[hashing]` settings of the app config
+    pub fn with_hashing_config(self, hashing: HashingConfig) -> Self {
+        Self { hashing, ..self }
+    }
+
+    /// Limit scan I/O with a throttle that can be adjusted while scans run
+    pub fn with_throttle(self, throttle: Arc<Throttle>) -> Self {
+        Self { throttle, ..self }
+    }
+
+    /// Reuse the hashes of files left unchanged since they were last hashed
+    pub fn with_hash_cache(self, hash_cache: Arc<HashCache>) -> Self {
+        Self { hash_cache, ..self }
+    }
+
+    /// Create an analyzer that publishes completed sessions into the shared results store
+    pub fn with_storage(
+        websocket_manager: Arc<WebSocketManager>,
+        storage: Arc<RwLock<SimpleStorage>>,
+    ) -> Self {
+        Self {
+            storage: Some(storage),
+            ..Self::new(websocket_manager)
         }
     }
 
@@ -164,11 +278,77 @@ impl DiskAnalyzer {
         scan_type: ScanType,
         config: ScanConfig,
     ) -> Result<String> {
+        // Reject invalid exclude patterns before anything is registered
+        ExclusionRules::new(Path::new(&disk_path), &config)?;
+
         let session_id = Uuid::new_v4().to_string();
-        let session = ScanSession {
-            id: session_id.clone(),
-            disk_path: disk_path.clone(),
-            scan_type: scan_type.clone(),
+        self.register_session(Self::new_session(
+            session_id.clone(),
+            disk_path,
+            scan_type,
+            config,
+        ))
+        .await?;
+
+        Ok(session_id)
+    }
+
+    /// Register every session left unfinished by a crash or quit, oldest first.
+    ///
+    /// Starting a restored session continues it from its last checkpoint:
+    /// directories walked before the interruption are not listed again as long
+    /// as their modification time is unchanged, and files already hashed keep
+    /// their hash, so both phases pick up roughly where they stopped.
+    pub async fn restore_interrupted_sessions(&self) -> Result<Vec<ScanCheckpoint>> {
+        let mut restored = Vec::new();
+
+        for scan in self.checkpoint_store().await.load_all() {
+            let checkpoint = scan.checkpoint;
+            if self
+                .active_sessions
+                .read()
+                .await
+                .contains_key(&checkpoint.session_id)
+            {
+                continue; // Already restored or still running
+            }
+
+            let mut session = Self::new_session(
+                checkpoint.session_id.clone(),
+                checkpoint.disk_path.clone(),
+                checkpoint.scan_type.clone(),
+                checkpoint.config.clone(),
+            );
+            session.created_at = checkpoint.created_at;
+            self.register_session(session).await?;
+
+            self.resume_points.lock().await.insert(
+                checkpoint.session_id.clone(),
+                PreviousSnapshot::new(&scan.files, &scan.directories, self.hashing.algorithm),
+            );
+
+            tracing::info!(
+                "Restored scan session {} from its {} phase checkpoint ({} files)",
+                checkpoint.session_id,
+                checkpoint.phase,
+                scan.files.len()
+            );
+            restored.push(checkpoint);
+        }
+
+        Ok(restored)
+    }
+
+    fn new_session(
+        session_id: String,
+        disk_path: String,
+        scan_type: ScanType,
+        config: ScanConfig,
+    ) -> ScanSession {
+        ScanSession {
+            id: session_id,
+            disk_path,
+            scan_type,
             config,
             status: ScanSessionStatus::Created,
             created_at: chrono::Utc::now(),
@@ -194,27 +374,43 @@ impl DiskAnalyzer {
                 current_phase: "created".to_string(),
                 estimated_total_time: 0,
                 elapsed_time: 0,
+                dedupe_stage: None,
             },
             results: None,
-        };
-
-        {
-            let mut sessions = self.active_sessions.write().await;
-            sessions.insert(session_id.clone(), session);
+            control: Arc::default(),
+            errors: Arc::default(),
         }
+    }
 
-        // Start WebSocket session
-        let scan_type_str = match scan_type {
+    /// Track a session and announce it over WebSocket
+    async fn register_session(&self, session: ScanSession) -> Result<()> {
+        let disk_path = session.disk_path.clone();
+        let scan_type_str = match session.scan_type {
             ScanType::Quick => "quick",
             ScanType::Deep => "deep",
             ScanType::Custom => "custom",
+            ScanType::Incremental => "incremental",
         };
 
+        {
+            let mut sessions = self.active_sessions.write().await;
+            sessions.insert(session.id.clone(), session);
+        }
+
+        // Start WebSocket session
         self.websocket_manager
             .start_scan_session(disk_path, scan_type_str.to_string())
             .await?;
 
-        Ok(session_id)
+        Ok(())
+    }
+
+    /// Checkpoint store shared through the results storage, if any
+    async fn checkpoint_store(&self) -> CheckpointStore {
+        match &self.storage {
+            Some(storage) => storage.read().await.checkpoints.clone(),
+            None => CheckpointStore::default(),
+        }
     }
 
     /// Start a scan session
@@ -225,35 +421,39 @@ impl DiskAnalyzer {
                 .get_mut(session_id)
                 .ok_or_else(|| anyhow::anyhow!("Session not found"))?;
 
-            session.status = ScanSessionStatus::Running;
+            if session.started_at.is_some() {
+                return Err(anyhow::anyhow!("Session already started"));
+            }
+
+            // A session paused before it started stays paused until resumed
+            session.status = if session.control.is_paused() {
+                ScanSessionStatus::Paused
+            } else {
+                ScanSessionStatus::Running
+            };
             session.started_at = Some(chrono::Utc::now());
             session.clone()
         };
-
-        // Reset atomic counters
-        self.is_paused.store(false, Ordering::SeqCst);
-        self.should_cancel.store(false, Ordering::SeqCst);
-        self.files_processed.store(0, Ordering::SeqCst);
-        self.bytes_processed.store(0, Ordering::SeqCst);
-
-        {
-            let mut start_time = self.scan_start_time.lock().await;
-            *start_time = Some(Instant::now());
-        }
+        let resume_from = self.resume_points.lock().await.remove(session_id);
 
         // Start the dual scanning process
-        self.perform_dual_scan(session_id, &session).await?;
+        let result = self.perform_dual_scan(&session, resume_from).await;
+        if let Err(e) = &result {
+            let mut sessions = self.active_sessions.write().await;
+            if let Some(session) = sessions.get_mut(session_id) {
+                session.status = ScanSessionStatus::Error(e.to_string());
+            }
+        }
 
-        Ok(())
+        result
     }
 
     /// Pause a scan session
     pub async fn pause_scan_session(&self, session_id: &str) -> Result<()> {
-        self.is_paused.store(true, Ordering::SeqCst);
-
         {
             let mut sessions = self.active_sessions.write().await;
             if let Some(session) = sessions.get_mut(session_id) {
+                session.control.pause();
                 session.status = ScanSessionStatus::Paused;
                 session.paused_at = Some(chrono::Utc::now());
             }
@@ -267,11 +467,10 @@ impl DiskAnalyzer {
 
     /// Resume a scan session
     pub async fn resume_scan_session(&self, session_id: &str) -> Result<()> {
-        self.is_paused.store(false, Ordering::SeqCst);
-
         {
             let mut sessions = self.active_sessions.write().await;
             if let Some(session) = sessions.get_mut(session_id) {
+                session.control.resume();
                 session.status = ScanSessionStatus::Running;
                 session.paused_at = None;
             }
@@ -285,11 +484,10 @@ impl DiskAnalyzer {
 
     /// Cancel a scan session
     pub async fn cancel_scan_session(&self, session_id: &str) -> Result<()> {
-        self.should_cancel.store(true, Ordering::SeqCst);
-
         {
             let mut sessions = self.active_sessions.write().await;
             if let Some(session) = sessions.get_mut(session_id) {
+                session.control.cancel();
                 session.status = ScanSessionStatus::Cancelled;
             }
         }
@@ -303,67 +501,169 @@ impl DiskAnalyzer {
         sessions.get(session_id).cloned()
     }
 
+    /// Errors hit so far by a scan session
+    pub async fn get_scan_errors(&self, session_id: &str) -> Option<ScanErrorReport> {
+        let sessions = self.active_sessions.read().await;
+        let session = sessions.get(session_id)?;
+        let report = session.errors.lock().unwrap().report();
+        Some(report)
+    }
+
     /// Get all active sessions
     pub async fn get_active_sessions(&self) -> HashMap<String, ScanSession> {
         self.active_sessions.read().await.clone()
     }
 
     /// Perform dual scanning (quick then deep)
-    async fn perform_dual_scan(&self, session_id: &str, session: &ScanSession) -> Result<()> {
+    async fn perform_dual_scan(
+        &self,
+        session: &ScanSession,
+        resume_from: Option<PreviousSnapshot>,
+    ) -> Result<()> {
+        let session_id = session.id.as_str();
         let mut results = ScanResults {
-            files: Vec::new(),
+            files: FileTable::new(),
             duplicate_groups: Vec::new(),
             large_files: Vec::new(),
             total_files: 0,
             total_size: 0,
+            total_allocated_size: 0,
             scan_duration: 0,
+            directories: HashMap::new(),
+            changes: None,
+            hash_cache: HashCacheStats::default(),
+            dedupe: Vec::new(),
         };
 
         let start_time = Instant::now();
 
+        // Incremental scans start from the last stored snapshot of the same root
+        let previous = match session.scan_type {
+            ScanType::Incremental => self
+                .load_previous_snapshot(&session.disk_path)
+                .await
+                .map(Arc::new),
+            _ => None,
+        };
+        let resume_from = resume_from.map(Arc::new);
+        let mut checkpointer = Checkpointer::new(self.checkpoint_store().await, session);
+
         // Phase 1: Quick scan (metadata only)
+        // A resumed session walks from its checkpoint, which is newer than the index
         self.update_session_phase(session_id, "quick").await?;
-        let quick_files = self
-            .perform_quick_scan(session_id, &session.disk_path, &session.config)
-            .await?;
+        let quick_scan = self
+            .perform_quick_scan(
+                session,
+                resume_from.clone().or_else(|| previous.clone()),
+                &mut checkpointer,
+            )
+            .await;
+        let (mut quick_files, directories) = match quick_scan {
+            Ok(scanned) => scanned,
+            Err(e) => {
+                // Resuming would only run into the same failure
+                checkpointer.discard().await;
+                return Err(e);
+            }
+        };
 
-        if self.should_cancel.load(Ordering::SeqCst) {
+        if session.control.is_cancelled() {
+            checkpointer.discard().await;
             return Ok(());
         }
 
+        if let Some(resume_from) = &resume_from {
+            resume_from.carry_forward_hashes(&mut quick_files);
+        }
+
         results.files = quick_files;
+        results.directories = directories;
         results.total_files = results.files.len() as u64;
-        results.total_size = results.files.iter().map(|f| f.size).sum();
+        results.total_size = results.files.total_size(SizeBasis::Apparent);
+        results.total_allocated_size = results.files.total_size(SizeBasis::Allocated);
+
+        if let Some(previous) = &previous {
+            let reused = previous.carry_forward_hashes(&mut results.files);
+            let changes = previous.diff(&results.files);
+            tracing::info!(
+                "Incremental scan of {}: {} added, {} removed, {} modified, {} hashes reused",
+                session.disk_path,
+                changes.added.len(),
+                changes.removed.len(),
+                changes.modified.len(),
+                reused
+            );
+            results.changes = Some(changes);
+        }
 
         // Phase 2: Deep scan (if requested)
-        if matches!(session.scan_type, ScanType::Deep | ScanType::Custom) {
+        if matches!(
+            session.scan_type,
+            ScanType::Deep | ScanType::Custom | ScanType::Incremental
+        ) {
             self.update_session_phase(session_id, "deep").await?;
-            self.perform_deep_scan(session_id, &mut results, &session.config)
+            checkpointer.save("deep", &results.directories, &results.files);
+            self.perform_deep_scan(session, &mut results, &mut checkpointer)
                 .await?;
         }
 
-        if self.should_cancel.load(Ordering::SeqCst) {
+        if session.control.is_cancelled() {
+            checkpointer.discard().await;
             return Ok(());
         }
 
+        // Analyze large files with advanced filtering
+        results.large_files = self
+            .analyze_large_files(&results.files, &session.config)
+            .await;
+
         // Complete the session
         results.scan_duration = start_time.elapsed().as_secs();
         self.complete_scan_session(session_id, results).await?;
+        checkpointer.discard().await;
 
         Ok(())
     }
 
+    /// Load the last indexed scan of `root`, if any
+    async fn load_previous_snapshot(&self, root: &str) -> Option<PreviousSnapshot> {
+        let storage = self.storage.as_ref()?.read().await;
+        match storage.index.get(root) {
+            Some(indexed) => Some(PreviousSnapshot::new(
+                &indexed.files,
+                &indexed.directories,
+                self.hashing.algorithm,
+            )),
+            None => {
+                tracing::info!("No previous scan of {}, scanning everything", root);
+                None
+            }
+        }
+    }
+
+    /// Quick scan progress of the most recently started session
     pub async fn get_progress(&self) -> ScanProgress {
-        self.progress.lock().await.clone()
+        let sessions = self.active_sessions.read().await;
+        sessions
+            .values()
+            .filter(|s| s.started_at.is_some())
+            .max_by_key(|s| s.started_at)
+            .map(|s| s.progress.quick_scan.clone())
+            .unwrap_or_default()
     }
 
     /// Perform quick scan (metadata only) - MFT optimized for Windows
     async fn perform_quick_scan(
         &self,
-        session_id: &str,
-        path: &str,
-        config: &ScanConfig,
-    ) -> Result<Vec<FileInfo>> {
+        session: &ScanSession,
+        previous: Option<Arc<PreviousSnapshot>>,
+        checkpointer: &mut Checkpointer,
+    ) -> Result<(FileTable, HashMap<String, DateTime<Utc>>)> {
+        let session_id = session.id.as_str();
+        let path = session.disk_path.as_str();
+        let config = &session.config;
+        let rules = ExclusionRules::new(Path::new(path), config)?;
+
         // Try MFT scan first on Windows for maximum speed
         #[cfg(target_os = "windows")]
         {
@@ -382,7 +682,11 @@ impl DiskAnalyzer {
                         let files = mft_result
                             .files
                             .into_iter()
-                            .filter(|f| !Self::should_exclude_file(&f.path, config))
+                            .filter(|f| {
+                                !Self::should_exclude_file(&f.path, f.is_directory, &rules, config)
+                            })
+                            .filter(|f| f.is_directory || Self::within_size_limits(f.size, config))
+                            .filter(|f| Self::within_depth_limit(path, &f.path, config))
                             .map(|mft_file| {
                                 let extension = std::path::Path::new(&mft_file.path)
                                     .extension()
@@ -405,146 +709,289 @@ impl DiskAnalyzer {
                                     is_directory: mft_file.is_directory,
                                     extension,
                                     hash: None,
+                                    inode: None,
+                                    device: None,
+                                    links: None,
+                                    allocated_size: None,
+                                    generation: None,
+                                    display_path: None,
                                 }
                             })
-                            .collect::<Vec<_>>();
+                            .collect::<FileTable>();
+                        self.check_memory_limit(&files)?;
 
                         // Send progress updates
                         self.send_mft_progress_updates(session_id, &files).await;
 
-                        return Ok(files);
+                        return Ok((files, HashMap::new()));
                     }
                 }
             }
         }
 
         // Fallback to regular scanning
+        session
+            .control
+            .expect_bytes(self.expected_scan_bytes(path).await);
         let path = PathBuf::from(path);
-        let (tx, mut rx) = mpsc::channel::<FileInfo>(1000);
-        let progress = self.progress.clone();
-        let config = config.clone();
-        let session_id = session_id.to_string();
-        let websocket_manager = self.websocket_manager.clone();
-        let is_paused = self.is_paused.clone();
-        let should_cancel = self.should_cancel.clone();
-
-        // Reset progress
-        {
-            let mut prog = progress.lock().await;
-            prog.total_files = 0;
-            prog.processed_files = 0;
-            prog.total_size = 0;
-            prog.errors.clear();
+        let (tx, mut rx) = mpsc::channel::<WalkEntry>(1000);
+        let progress = Arc::new(std::sync::Mutex::new(ScanProgress::default()));
+
+        let walker = Walker::new(
+            tx,
+            progress.clone(),
+            session,
+            rules,
+            previous,
+            self.websocket_manager.clone(),
+            self.throttle.clone(),
+        );
+
+        // Walk on a dedicated pool so the runtime stays free for the collector below
+        let threads = self.scanner.max_threads;
+        let scan_task = tokio::task::spawn_blocking(move || walker.run(path, threads));
+
+        // Collect results; returning early drops `rx`, which stops the walker
+        let mut files = FileTable::new();
+        let mut directories = HashMap::new();
+        while let Some(entry) = rx.recv().await {
+            match entry {
+                WalkEntry::File(file) => {
+                    let id = files.push(&file);
+                    checkpointer.file_added(id);
+                    if files.len().is_multiple_of(MEMORY_CHECK_INTERVAL) {
+                        self.check_memory_limit(&files)?;
+                    }
+                    if files.len().is_multiple_of(100) {
+                        let progress = Self::walk_progress(&progress, &session.control);
+                        let remaining = session.control.remaining_time();
+                        self.update_session_progress(session_id, |p| {
+                            p.quick_scan = progress;
+                            p.set_timing(session.started_at, remaining);
+                        })
+                        .await;
+                    }
+                }
+                WalkEntry::Directory(path, modified) => {
+                    checkpointer.directory_walked(&path, modified);
+                    directories.insert(path, modified);
+                }
+            }
+            checkpointer.maybe_save("quick", &directories, &files);
         }
 
-        // Spawn task to scan directory
-        let scan_task = tokio::spawn(async move {
-            Self::scan_recursive_quick(
-                path,
-                tx,
-                progress,
-                &config,
-                session_id,
-                websocket_manager,
-                is_paused,
-                should_cancel,
-            )
-            .await
-        });
+        // Wait for scan to complete
+        scan_task.await??;
+        self.check_memory_limit(&files)?;
+
+        let progress = Self::walk_progress(&progress, &session.control);
+        self.update_session_progress(session_id, |p| {
+            p.quick_scan = progress;
+            p.set_timing(session.started_at, Some(Duration::ZERO));
+        })
+        .await;
 
-        // Collect results
-        let mut files = Vec::new();
-        while let Some(file) = rx.recv().await {
-            files.push(file);
+        Ok((files, directories))
+    }
+
+    /// Bytes the quick scan of `root` is expected to find: the size found by
+    /// the last scan of the same root, or else the space used on its disk
+    async fn expected_scan_bytes(&self, root: &str) -> u64 {
+        if let Some(storage) = &self.storage {
+            if let Some(indexed) = storage.read().await.index.get(root) {
+                return indexed.record.total_size;
+            }
         }
 
-        // Wait for scan to complete
-        scan_task.await??;
+        let disks = crate::file_system::get_system_disks()
+            .await
+            .unwrap_or_default();
+        disks
+            .iter()
+            .filter(|disk| Path::new(root).starts_with(&disk.mount_point))
+            .max_by_key(|disk| disk.mount_point.len())
+            .map(|disk| disk.used_space)
+            .unwrap_or_default()
+    }
+
+    /// Fail once the file table outgrows `max_memory_mb`
+    fn check_memory_limit(&self, files: &FileTable) -> Result<()> {
+        let limit = self.performance.max_memory_mb.saturating_mul(1024 * 1024);
+        let used = files.memory_usage() as u64;
+        if used > limit {
+            anyhow::bail!(
+                "Scan stopped: file table reached the {} MB memory limit after {} files \
+                 (raise max_memory_mb in config.toml to scan this volume)",
+                self.performance.max_memory_mb,
+                files.len()
+            );
+        }
+        Ok(())
+    }
 
-        Ok(files)
+    /// Quick scan progress from the walker's state and the session counters
+    fn walk_progress(
+        progress: &std::sync::Mutex<ScanProgress>,
+        control: &ScanControl,
+    ) -> ScanProgress {
+        let progress = progress.lock().unwrap();
+        ScanProgress {
+            total_files: control.files_processed(),
+            processed_files: control.files_processed(),
+            total_size: control.bytes_processed(),
+            current_path: progress.current_path.clone(),
+            errors: progress.errors.clone(),
+        }
     }
 
     /// Perform deep scan (content analysis and hashing) - Parallel optimized
     async fn perform_deep_scan(
         &self,
-        session_id: &str,
+        session: &ScanSession,
         results: &mut ScanResults,
-        config: &ScanConfig,
+        checkpointer: &mut Checkpointer,
     ) -> Result<()> {
         if results.files.is_empty() {
             return Ok(());
         }
 
-        let total_files = results.files.len();
-
-        // Filter files for hashing (only files above threshold)
-        let files_to_hash: Vec<_> = results
-            .files
-            .iter()
-            .enumerate()
-            .filter(|(_, file)| file.size > 1024 * 1024) // 1MB threshold
-            .collect();
-
-        let total_hash_files = files_to_hash.len();
-
-        if total_hash_files == 0 {
-            return Ok(());
-        }
+        let counters = Arc::new(HashCounters::default());
+        let duplicate_groups = self
+            .find_duplicates_by_strategy(session, results, checkpointer, &counters)
+            .await;
+        self.hash_cache.persist().await;
+        results.duplicate_groups = duplicate_groups?;
+        results.hash_cache = counters.snapshot();
 
-        // Use parallel processing for hash calculation
-        let (hash_tx, mut hash_rx) = tokio::sync::mpsc::channel::<(usize, String, String)>(100);
+        Ok(())
+    }
 
-        // Spawn parallel hash calculation task
-        let files_for_hashing = files_to_hash
+    /// Hash `files` on the hashing workers for a stage of the duplicate search,
+    /// handing every hash to `on_hash` as it arrives. Returns the bytes read.
+    async fn hash_stage(
+        &self,
+        session: &ScanSession,
+        stage: DedupeStage,
+        files_for_hashing: Vec<(EntryId, String, u64)>,
+        counters: &Arc<HashCounters>,
+        mut on_hash: impl FnMut(EntryId, &str),
+    ) -> Result<u64> {
+        let session_id = session.id.as_str();
+        let sampled = move |size: u64| match stage {
+            DedupeStage::Blocks => hashing::sampled_bytes(size),
+            _ => size,
+        };
+        let total_hash_files = files_for_hashing.len();
+        let total_hash_bytes: u64 = files_for_hashing
             .iter()
-            .map(|(idx, file)| (*idx, file.path.clone(), file.size))
-            .collect::<Vec<_>>();
-
-        let hash_tx_clone = hash_tx.clone();
-        let is_paused = self.is_paused.clone();
-        let should_cancel = self.should_cancel.clone();
-
-        tokio::spawn(async move {
-            use rayon::prelude::*;
-
-            // Use Rayon for parallel hash computation
-            files_for_hashing.par_iter().for_each(|(idx, path, size)| {
+            .map(|(_, _, size)| sampled(*size))
+            .sum();
+        self.update_session_progress(session_id, |p| p.dedupe_stage = Some(stage))
+            .await;
+
+        // Hashing workers stop when the session is cancelled or when collection below ends
+        let hashing = session.control.child();
+        let worker_control = hashing.clone();
+        hashing.expect_bytes(total_hash_bytes);
+
+        let throttle = self.throttle.clone();
+        let cache = self.hash_cache.clone();
+        let (kind, algorithm) = match stage {
+            DedupeStage::Blocks => (HashKind::Partial, self.hashing.prefilter_algorithm),
+            _ => (HashKind::Full, self.hashing.algorithm),
+        };
+        let worker_counters = counters.clone();
+        let bytes_read = Arc::new(AtomicU64::new(0));
+        let worker_bytes_read = bytes_read.clone();
+        // Workers of their own, as idle mode lowers the I/O priority of the threads
+        let pool = rayon::ThreadPoolBuilder::new()
+            .thread_name(|i| format!("hash-worker-{}", i))
+            .build()?;
+
+        let mut hash_rx = hash_workers::spawn(
+            pool,
+            files_for_hashing,
+            hash_workers::RESULT_CAPACITY,
+            move |(_, path, size)| {
                 // Check for pause/cancel in parallel threads
-                while is_paused.load(Ordering::SeqCst) {
-                    std::thread::sleep(std::time::Duration::from_millis(100));
-                }
+                worker_control.wait_if_paused_blocking();
+                throttle.wait(1, 0, &worker_control);
 
-                if should_cancel.load(Ordering::SeqCst) {
-                    return;
+                if worker_control.is_cancelled() {
+                    return None;
                 }
 
-                // Calculate hash
-                if let Ok(hash) = Self::calculate_file_hash_sync(path) {
-                    let _ = hash_tx_clone.try_send((*idx, path.clone(), hash));
-                }
-            });
-        });
-
-        drop(hash_tx); // Close sender to signal completion
+                let hash = cache.hash_sync(
+                    &path_encoding::decode_path(path),
+                    kind,
+                    algorithm,
+                    &worker_counters,
+                    || match kind {
+                        HashKind::Partial => Self::calculate_partial_hash_sync(
+                            path,
+                            *size,
+                            algorithm,
+                            &throttle,
+                            &worker_control,
+                            &worker_bytes_read,
+                        ),
+                        HashKind::Full => Self::calculate_file_hash_sync(
+                            path,
+                            algorithm,
+                            &throttle,
+                            &worker_control,
+                            &worker_bytes_read,
+                        ),
+                    },
+                );
+                worker_control.add_file(sampled(*size));
+                Some(hash)
+            },
+        );
 
-        // Collect hash results
-        let mut hash_groups: HashMap<String, Vec<FileInfo>> = HashMap::new();
+        // Collect hash results; every file is either hashed or recorded as an error
         let mut processed = 0;
 
-        while let Some((file_idx, _path, hash)) = hash_rx.recv().await {
-            if let Some(file) = results.files.get_mut(file_idx) {
-                file.hash = Some(hash.clone());
-                hash_groups
-                    .entry(hash)
-                    .or_default()
-                    .push(file.clone());
+        while let Some(((file_id, path, _), hash)) = hash_rx.recv().await {
+            match hash {
+                Ok(hash) => on_hash(file_id, &hash),
+                Err(e) => {
+                    let path = Path::new(&path);
+                    let error = match e.downcast::<std::io::Error>() {
+                        Ok(e) => DiskDominatorError::from_io(path, e),
+                        Err(e) => DiskDominatorError::Other(e),
+                    };
+                    session
+                        .errors
+                        .lock()
+                        .unwrap()
+                        .record(ScanError::new(path, &error));
+                }
             }
 
             processed += 1;
             let progress_percent = (processed as f64 / total_hash_files as f64) * 100.0;
 
+            if session.control.is_cancelled() {
+                break;
+            }
+
             // Update progress every 10 files to avoid spam
             if processed % 10 == 0 || processed == total_hash_files {
+                let current_path = path.clone();
+                let remaining = hashing.remaining_time();
+                self.update_session_progress(session_id, |p| {
+                    p.deep_scan = ScanProgress {
+                        total_files: total_hash_files as u64,
+                        processed_files: processed as u64,
+                        total_size: hashing.bytes_processed(),
+                        current_path: current_path.clone(),
+                        errors: Vec::new(),
+                    };
+                    p.set_timing(session.started_at, remaining);
+                })
+                .await;
+
                 let _ = self
                     .websocket_manager
                     .update_scan_progress(
@@ -556,18 +1003,12 @@ impl DiskAnalyzer {
                             progress: progress_percent,
                             quick_scan_progress: Some(100.0),
                             deep_scan_progress: Some(progress_percent),
-                            remaining_time: self
-                                .estimate_remaining_time(processed, total_hash_files)
-                                .await,
+                            remaining_time: remaining.map_or(0, |r| r.as_secs()),
                             files_scanned: processed as u64,
                             total_files: total_hash_files as u64,
-                            bytes_scanned: self.bytes_processed.load(Ordering::SeqCst),
-                            total_bytes: results.total_size,
-                            current_path: results
-                                .files
-                                .get(file_idx)
-                                .map(|f| f.path.clone())
-                                .unwrap_or_default(),
+                            bytes_scanned: hashing.bytes_processed(),
+                            total_bytes: total_hash_bytes,
+                            current_path,
                             scan_status: "running".to_string(),
                             errors: Vec::new(),
                         },
@@ -576,21 +1017,34 @@ impl DiskAnalyzer {
             }
         }
 
-        // Find duplicates using the configured strategy
-        let duplicate_groups = self
-            .find_duplicates_by_strategy(&results.files, config)
-            .await?;
-        results.duplicate_groups = duplicate_groups;
-
-        // Sort duplicate groups by potential savings
-        results
-            .duplicate_groups
-            .sort_by(|a, b| b.potential_savings.cmp(&a.potential_savings));
-
-        // Analyze large files with advanced filtering
-        results.large_files = self.analyze_large_files(&results.files, config).await;
+        hashing.cancel();
+        Ok(bytes_read.load(Ordering::Relaxed))
+    }
 
-        Ok(())
+    /// Record a finished stage of the duplicate search and announce it
+    async fn finish_dedupe_stage(
+        &self,
+        session_id: &str,
+        results: &mut ScanResults,
+        report: DedupeStageReport,
+    ) {
+        tracing::debug!(
+            "Duplicate search {:?}: {} of {} files left in {} groups, {} bytes read, {} saved",
+            report.stage,
+            report.candidates,
+            report.files_in,
+            report.groups,
+            report.bytes_read,
+            report.bytes_saved
+        );
+        let _ = self
+            .websocket_manager
+            .broadcast_message(
+                "dedupe_stage_finished".to_string(),
+                serde_json::json!({ "session_id": session_id, "report": report }),
+            )
+            .await;
+        results.dedupe.push(report);
     }
 
     /// Scan directory and return file information with real-time progress (backward compatibility)
@@ -598,15 +1052,17 @@ impl DiskAnalyzer {
         // Convert old ScanOptions to new ScanConfig
         let config = ScanConfig {
             exclude_patterns: options.exclude_patterns.clone(),
-            include_hidden: false,
-            follow_symlinks: false,
-            max_depth: None,
-            min_file_size: None,
-            max_file_size: None,
+            include_hidden: options.include_hidden,
+            follow_symlinks: options.follow_symlinks,
+            max_depth: options.max_depth,
+            min_file_size: options.min_file_size,
+            max_file_size: options.max_file_size,
             calculate_hashes: false,
             quick_hash_threshold: 1024 * 1024, // 1MB
             duplicate_strategy: DuplicateStrategy::NameAndSize,
             large_file_threshold: 100 * 1024 * 1024, // 100MB
+            same_filesystem: options.same_filesystem,
+            use_ignore_files: options.use_ignore_files,
         };
 
         // Create a temporary session for backward compatibility
@@ -622,7 +1078,10 @@ impl DiskAnalyzer {
             if let Some(session) = self.get_scan_session(&session_id).await {
                 match session.status {
                     ScanSessionStatus::Completed => {
-                        return Ok(session.results.map(|r| r.files).unwrap_or_default());
+                        return Ok(session
+                            .results
+                            .map(|r| r.files.to_vec())
+                            .unwrap_or_default());
                     }
                     ScanSessionStatus::Error(e) => {
                         return Err(anyhow::anyhow!(e));
@@ -633,166 +1092,44 @@ impl DiskAnalyzer {
         }
     }
 
-    async fn scan_recursive_quick(
-        path: PathBuf,
-        tx: mpsc::Sender<FileInfo>,
-        progress: Arc<Mutex<ScanProgress>>,
-        config: &ScanConfig,
-        session_id: String,
-        websocket_manager: Arc<WebSocketManager>,
-        is_paused: Arc<AtomicBool>,
-        should_cancel: Arc<AtomicBool>,
-    ) -> Result<()> {
-        let mut entries = fs::read_dir(&path).await?;
-
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            let metadata = match entry.metadata().await {
-                Ok(m) => m,
-                Err(e) => {
-                    let mut prog = progress.lock().await;
-                    prog.errors
-                        .push(format!("Error reading {}: {}", path.display(), e));
-                    continue;
-                }
-            };
-
-            // Update current path
-            {
-                let mut prog = progress.lock().await;
-                prog.current_path = path.to_string_lossy().to_string();
-            }
-
-            // Check for pause/cancel
-            while is_paused.load(Ordering::SeqCst) {
-                tokio::time::sleep(Duration::from_millis(100)).await;
-            }
-
-            if should_cancel.load(Ordering::SeqCst) {
-                return Ok(());
-            }
-
-            // Check excluded patterns
-            let path_str = path.to_string_lossy();
-            if config.exclude_patterns.iter().any(|p| path_str.contains(p)) {
-                continue;
-            }
-
-            // Check if hidden files should be included
-            if !config.include_hidden
-                && path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .map(|n| n.starts_with('.'))
-                    .unwrap_or(false)
-            {
-                continue;
-            }
-
-            if metadata.is_dir() {
-                // Recursively scan subdirectory
-                if let Err(e) = Box::pin(Self::scan_recursive_quick(
-                    path.clone(),
-                    tx.clone(),
-                    progress.clone(),
-                    config,
-                    session_id.clone(),
-                    websocket_manager.clone(),
-                    is_paused.clone(),
-                    should_cancel.clone(),
-                ))
-                .await
-                {
-                    let mut prog = progress.lock().await;
-                    prog.errors.push(format!(
-                        "Error scanning directory {}: {}",
-                        path.display(),
-                        e
-                    ));
-                }
-            } else if metadata.is_file() {
-                // Process file
-                let file_info = FileInfo {
-                    path: path.to_string_lossy().to_string(),
-                    name: path
-                        .file_name()
-                        .unwrap_or_default()
-                        .to_string_lossy()
-                        .to_string(),
-                    size: metadata.len(),
-                    modified: metadata
-                        .modified()
-                        .unwrap_or(std::time::SystemTime::now())
-                        .into(),
-                    created: metadata
-                        .created()
-                        .unwrap_or(std::time::SystemTime::now())
-                        .into(),
-                    is_directory: false,
-                    extension: path
-                        .extension()
-                        .and_then(|e| e.to_str())
-                        .map(|e| e.to_string()),
-                    hash: None,
-                };
-
-                // Update progress
-                let (processed, total, current_size) = {
-                    let mut prog = progress.lock().await;
-                    prog.total_files += 1;
-                    prog.processed_files += 1;
-                    prog.total_size += metadata.len();
-                    prog.current_path = file_info.path.clone();
-                    (prog.processed_files, prog.total_files, prog.total_size)
-                };
-
-                // Send progress update via WebSocket (every 100 files to avoid spam)
-                if processed % 100 == 0 {
-                    let progress_percent = if total > 0 {
-                        (processed as f64 / total as f64) * 100.0
-                    } else {
-                        0.0
-                    };
-                    let _ = websocket_manager
-                        .update_scan_progress(
-                            &session_id,
-                            ScanProgressMessage {
-                                scan_id: session_id.clone(),
-                                disk_id: "unknown".to_string(),
-                                scan_type: "quick".to_string(),
-                                progress: progress_percent,
-                                quick_scan_progress: Some(progress_percent),
-                                deep_scan_progress: None,
-                                remaining_time: 0, // TODO: Calculate estimate
-                                files_scanned: processed,
-                                total_files: total,
-                                bytes_scanned: current_size,
-                                total_bytes: current_size,
-                                current_path: file_info.path.clone(),
-                                scan_status: "running".to_string(),
-                                errors: Vec::new(),
-                            },
-                        )
-                        .await;
-                }
+    /// Whether a directory `depth` levels below the root may be walked
+    fn within_depth(depth: usize, config: &ScanConfig) -> bool {
+        config.max_depth.is_none_or(|max| depth <= max)
+    }
 
-                // Send file info
-                if tx.send(file_info).await.is_err() {
-                    break; // Receiver dropped
-                }
-            }
-        }
+    /// Whether a file found under `root` lies within the configured depth
+    pub fn within_depth_limit(root: &str, path: &str, config: &ScanConfig) -> bool {
+        let depth = Path::new(path)
+            .strip_prefix(root)
+            .map(|relative| relative.components().count().saturating_sub(1))
+            .unwrap_or(0);
+        Self::within_depth(depth, config)
+    }
 
-        Ok(())
+    /// Whether a file size lies within the configured size window
+    pub fn within_size_limits(size: u64, config: &ScanConfig) -> bool {
+        config.min_file_size.is_none_or(|min| size >= min)
+            && config.max_file_size.is_none_or(|max| size <= max)
     }
 
     /// Find duplicate files
     pub async fn find_duplicates(&self, mut files: Vec<FileInfo>) -> Result<Vec<DuplicateGroup>> {
         let mut hash_groups: HashMap<String, Vec<FileInfo>> = HashMap::new();
+        let counters = HashCounters::default();
 
         // Calculate hashes for all files
         for file in &mut files {
-            if let Ok(hash) = self.calculate_file_hash(&file.path).await {
+            let hash = self
+                .hash_cache
+                .hash_async(
+                    &path_encoding::decode_path(&file.path),
+                    HashKind::Full,
+                    self.hashing.algorithm,
+                    &counters,
+                    || self.calculate_file_hash(&file.path),
+                )
+                .await;
+            if let Ok(hash) = hash {
                 file.hash = Some(hash.clone());
                 hash_groups
                     .entry(hash)
@@ -805,66 +1142,63 @@ impl DiskAnalyzer {
         let mut duplicate_groups = Vec::new();
         for (hash, group) in hash_groups {
             if group.len() > 1 {
-                let total_size: u64 = group.iter().map(|f| f.size).sum();
-                let potential_savings = total_size - group[0].size;
-
-                duplicate_groups.push(DuplicateGroup {
-                    hash,
-                    files: group,
-                    total_size,
-                    potential_savings,
-                });
+                duplicate_groups.push(DuplicateGroup::new(hash, group));
             }
         }
 
         // Sort by potential savings
         duplicate_groups.sort_by(|a, b| b.potential_savings.cmp(&a.potential_savings));
 
+        self.hash_cache.persist().await;
         Ok(duplicate_groups)
     }
 
-    /// Calculate SHA256 hash for a file (async version)
+    /// Full hash of a file by the configured algorithm, on a blocking thread
     async fn calculate_file_hash(&self, path: &str) -> Result<String> {
-        const BUFFER_SIZE: usize = 65536; // 64KB buffer
-
-        let file = fs::File::open(path).await?;
-        let mut reader = BufReader::with_capacity(BUFFER_SIZE, file);
-        let mut hasher = Sha256::new();
-        let mut buffer = vec![0u8; BUFFER_SIZE];
-
-        loop {
-            let bytes_read = reader.read(&mut buffer).await?;
-            if bytes_read == 0 {
-                break;
-            }
-            hasher.update(&buffer[..bytes_read]);
-        }
-
-        let result = hasher.finalize();
-        Ok(format!("{:x}", result))
+        hashing::hash_file(path_encoding::decode_path(path), self.hashing.algorithm).await
     }
 
-    /// Calculate SHA256 hash for a file (sync version for parallel processing)
-    fn calculate_file_hash_sync(path: &str) -> Result<String> {
-        use std::fs::File;
-        use std::io::Read;
-
-        const BUFFER_SIZE: usize = 65536; // 64KB buffer
+    /// Full hash of a file for the parallel workers, reading no faster than
+    /// the throttle allows and counting the bytes read
+    fn calculate_file_hash_sync(
+        path: &str,
+        algorithm: HashAlgorithm,
+        throttle: &Throttle,
+        control: &ScanControl,
+        bytes_read: &AtomicU64,
+    ) -> Result<String> {
+        hashing::hash_file_sync(&path_encoding::decode_path(path), algorithm, |read| {
+            bytes_read.fetch_add(read as u64, Ordering::Relaxed);
+            throttle.wait(0, read as u64, control)
+        })
+    }
 
-        let mut file = File::open(path)?;
-        let mut hasher = Sha256::new();
-        let mut buffer = vec![0u8; BUFFER_SIZE];
+    /// Hash of the first, middle and last blocks of a file for the parallel workers
+    fn calculate_partial_hash_sync(
+        path: &str,
+        size: u64,
+        algorithm: HashAlgorithm,
+        throttle: &Throttle,
+        control: &ScanControl,
+        bytes_read: &AtomicU64,
+    ) -> Result<String> {
+        let hash = hashing::hash_partial_sync(&path_encoding::decode_path(path), algorithm)?;
+        let read = hashing::sampled_bytes(size);
+        bytes_read.fetch_add(read, Ordering::Relaxed);
+        throttle.wait(0, read, control);
+        Ok(hash)
+    }
 
-        loop {
-            let bytes_read = file.read(&mut buffer)?;
-            if bytes_read == 0 {
-                break;
-            }
-            hasher.update(&buffer[..bytes_read]);
+    /// Update the live progress of a session
+    async fn update_session_progress(
+        &self,
+        session_id: &str,
+        update: impl FnOnce(&mut DualScanProgress),
+    ) {
+        let mut sessions = self.active_sessions.write().await;
+        if let Some(session) = sessions.get_mut(session_id) {
+            update(&mut session.progress);
         }
-
-        let result = hasher.finalize();
-        Ok(format!("{:x}", result))
     }
 
     /// Update session phase
@@ -878,9 +1212,9 @@ impl DiskAnalyzer {
         Ok(())
     }
 
-    /// Complete scan session
+    /// Complete scan session and publish its results
     async fn complete_scan_session(&self, session_id: &str, results: ScanResults) -> Result<()> {
-        {
+        let session = {
             let mut sessions = self.active_sessions.write().await;
             if let Some(session) = sessions.get_mut(session_id) {
                 session.status = ScanSessionStatus::Completed;
@@ -888,31 +1222,52 @@ impl DiskAnalyzer {
                 session.results = Some(results);
                 session.progress.overall_progress = 100.0;
                 session.progress.current_phase = "completed".to_string();
+                Some(session.clone())
+            } else {
+                None
             }
-        }
+        };
 
         self.websocket_manager
             .complete_scan_session(session_id)
             .await?;
+
+        if let Some(session) = session {
+            self.publish_results(&session).await?;
+        }
         Ok(())
     }
 
-    /// Estimate remaining time based on progress
-    async fn estimate_remaining_time(&self, processed: usize, total: usize) -> u64 {
-        if processed == 0 || total == 0 {
-            return 0;
-        }
+    /// Register a completed session's results with the shared store and announce them
+    async fn publish_results(&self, session: &ScanSession) -> Result<()> {
+        let (Some(storage), Some(results)) = (&self.storage, &session.results) else {
+            return Ok(());
+        };
 
-        let start_time = self.scan_start_time.lock().await;
-        if let Some(start) = *start_time {
-            let elapsed = start.elapsed().as_secs();
-            let progress_ratio = processed as f64 / total as f64;
-            let estimated_total = elapsed as f64 / progress_ratio;
-            let remaining = estimated_total - elapsed as f64;
-            remaining.max(0.0) as u64
-        } else {
-            0
-        }
+        // Copied before taking the lock, and written out after releasing it
+        let record = ScanRecord::from_session(session);
+        let indexed = results.clone();
+        let writes = {
+            let mut storage = storage.write().await;
+            storage.index.replace_root(record, indexed);
+            storage.index.pending_writes()
+        };
+        writes.persist().await;
+
+        self.websocket_manager
+            .broadcast_message(
+                "scan_results_available".to_string(),
+                serde_json::json!({
+                    "session_id": session.id,
+                    "root": session.disk_path,
+                    "total_files": results.total_files,
+                    "total_size": results.total_size,
+                    "total_allocated_size": results.total_allocated_size,
+                    "duplicate_groups": results.duplicate_groups.len(),
+                    "large_files": results.large_files.len(),
+                }),
+            )
+            .await
     }
 
     /// Extract drive letter from path (Windows)
@@ -930,14 +1285,15 @@ impl DiskAnalyzer {
     }
 
     /// Check if file should be excluded based on config
-    fn should_exclude_file(path: &str, config: &ScanConfig) -> bool {
-        let path_lower = path.to_lowercase();
-
-        // Check user-defined exclusions
-        for pattern in &config.exclude_patterns {
-            if path_lower.contains(&pattern.to_lowercase()) {
-                return true;
-            }
+    pub fn should_exclude_file(
+        path: &str,
+        is_dir: bool,
+        rules: &ExclusionRules,
+        config: &ScanConfig,
+    ) -> bool {
+        // Check user-defined exclusions, including those of parent directories
+        if rules.is_excluded_with_parents(Path::new(path), is_dir) {
+            return true;
         }
 
         // Check if hidden files should be excluded
@@ -955,15 +1311,21 @@ impl DiskAnalyzer {
     }
 
     /// Send MFT scan progress updates via WebSocket
-    async fn send_mft_progress_updates(&self, session_id: &str, files: &[FileInfo]) {
+    async fn send_mft_progress_updates(&self, session_id: &str, files: &FileTable) {
+        const CHUNK_SIZE: usize = 1000;
+        const CHUNK_DELAY: Duration = Duration::from_millis(50);
+
         let file_count = files.len();
-        let total_size: u64 = files.iter().map(|f| f.size).sum();
+        let total_size = files.total_size(SizeBasis::Apparent);
+        let entries: Vec<_> = files.entries().collect();
+        let chunk_count = entries.len().div_ceil(CHUNK_SIZE);
 
         // Send progress in chunks to show realistic progress
-        let chunk_size = 1000;
-        for (i, chunk) in files.chunks(chunk_size).enumerate() {
-            let progress =
-                ((i + 1) * chunk_size).min(file_count) as f64 / file_count as f64 * 100.0;
+        let mut bytes_scanned = 0;
+        for (i, chunk) in entries.chunks(CHUNK_SIZE).enumerate() {
+            let scanned = ((i + 1) * CHUNK_SIZE).min(file_count);
+            let progress = scanned as f64 / file_count as f64 * 100.0;
+            bytes_scanned += chunk.iter().map(|f| f.size()).sum::<u64>();
 
             let _ = self
                 .websocket_manager
@@ -976,12 +1338,16 @@ impl DiskAnalyzer {
                         progress,
                         quick_scan_progress: Some(progress),
                         deep_scan_progress: None,
-                        remaining_time: 0, // MFT scan is very fast
-                        files_scanned: ((i + 1) * chunk_size).min(file_count) as u64,
+                        // The MFT is already read, only the remaining updates are left
+                        remaining_time: (CHUNK_DELAY * (chunk_count - i - 1) as u32).as_secs(),
+                        files_scanned: scanned as u64,
                         total_files: file_count as u64,
-                        bytes_scanned: chunk.iter().map(|f| f.size).sum::<u64>(),
+                        bytes_scanned,
                         total_bytes: total_size,
-                        current_path: chunk.first().map(|f| f.path.clone()).unwrap_or_default(),
+                        current_path: chunk
+                            .first()
+                            .map(|f| f.path().to_string_lossy().to_string())
+                            .unwrap_or_default(),
                         scan_status: "running".to_string(),
                         errors: Vec::new(),
                     },
@@ -989,246 +1355,145 @@ impl DiskAnalyzer {
                 .await;
 
             // Small delay to make progress visible
-            tokio::time::sleep(Duration::from_millis(50)).await;
+            tokio::time::sleep(CHUNK_DELAY).await;
         }
     }
 
-    /// Advanced duplicate detection with multiple strategies
+    /// Find duplicates in stages, each one reading only the files the
+    /// previous ones could not tell apart: equal sizes, then for the name based
+    /// strategies equal names, then the first, middle and last blocks, then
+    /// the whole contents. The duplicate strategy picks the stages.
     async fn find_duplicates_by_strategy(
         &self,
-        files: &[FileInfo],
-        config: &ScanConfig,
-    ) -> Result<Vec<DuplicateGroup>> {
-        match config.duplicate_strategy {
-            DuplicateStrategy::HashOnly => self.find_duplicates_by_hash(files).await,
-            DuplicateStrategy::NameAndSize => self.find_duplicates_by_name_size(files).await,
-            DuplicateStrategy::NameSizePartialHash => {
-                self.find_duplicates_by_name_size_partial_hash(files).await
-            }
-            DuplicateStrategy::SmartDetection => self.find_duplicates_smart(files, config).await,
-        }
-    }
-
-    /// Find duplicates by SHA-256 hash (most accurate)
-    async fn find_duplicates_by_hash(&self, files: &[FileInfo]) -> Result<Vec<DuplicateGroup>> {
-        let mut hash_groups: HashMap<String, Vec<FileInfo>> = HashMap::new();
-
-        // Group files by hash
-        for file in files {
-            if let Some(hash) = &file.hash {
-                hash_groups
-                    .entry(hash.clone())
-                    .or_default()
-                    .push(file.clone());
-            }
-        }
-
-        // Find groups with duplicates
-        let mut duplicate_groups = Vec::new();
-        for (hash, group) in hash_groups {
-            if group.len() > 1 {
-                let total_size: u64 = group.iter().map(|f| f.size).sum();
-                let potential_savings = total_size - group[0].size;
-
-                duplicate_groups.push(DuplicateGroup {
-                    hash,
-                    files: group,
-                    total_size,
-                    potential_savings,
-                });
-            }
-        }
-
-        duplicate_groups.sort_by(|a, b| b.potential_savings.cmp(&a.potential_savings));
-        Ok(duplicate_groups)
-    }
-
-    /// Find duplicates by name and size (fast but less accurate)
-    async fn find_duplicates_by_name_size(
-        &self,
-        files: &[FileInfo],
+        session: &ScanSession,
+        results: &mut ScanResults,
+        checkpointer: &mut Checkpointer,
+        counters: &Arc<HashCounters>,
     ) -> Result<Vec<DuplicateGroup>> {
-        let mut name_size_groups: HashMap<String, Vec<FileInfo>> = HashMap::new();
-
-        // Group files by name+size combination
-        for file in files {
-            let key = format!("{}:{}", file.name, file.size);
-            name_size_groups
-                .entry(key)
-                .or_default()
-                .push(file.clone());
-        }
-
-        // Find groups with duplicates
-        let mut duplicate_groups = Vec::new();
-        for (key, group) in name_size_groups {
-            if group.len() > 1 {
-                let total_size: u64 = group.iter().map(|f| f.size).sum();
-                let potential_savings = total_size - group[0].size;
-
-                duplicate_groups.push(DuplicateGroup {
-                    hash: key, // Use name:size as identifier
-                    files: group,
-                    total_size,
-                    potential_savings,
-                });
-            }
-        }
-
-        duplicate_groups.sort_by(|a, b| b.potential_savings.cmp(&a.potential_savings));
-        Ok(duplicate_groups)
-    }
+        let session_id = session.id.as_str();
+        let plan = DedupePlan::for_config(&session.config);
+        let hashed_later = |candidates: &Candidates, later: bool, files: &FileTable| match later {
+            true => candidates.bytes(files),
+            false => 0,
+        };
 
-    /// Find duplicates by name, size and partial hash (balanced approach)
-    async fn find_duplicates_by_name_size_partial_hash(
-        &self,
-        files: &[FileInfo],
-    ) -> Result<Vec<DuplicateGroup>> {
-        let mut candidate_groups: HashMap<String, Vec<FileInfo>> = HashMap::new();
-
-        // First pass: group by name+size
-        for file in files {
-            let key = format!("{}:{}", file.name, file.size);
-            candidate_groups
-                .entry(key)
-                .or_default()
-                .push(file.clone());
+        // Every strategy needs equal sizes, which the file table already knows
+        let files_in = results
+            .files
+            .entries()
+            .filter(|file| !file.is_directory())
+            .fold((0, 0), |(count, bytes), file| {
+                (count + 1, bytes + file.size())
+            });
+        let mut candidates = Candidates::by_size(&results.files);
+        let mut before = (
+            candidates.file_count() as u64,
+            candidates.bytes(&results.files),
+        );
+        let pending = hashed_later(&candidates, plan.blocks || plan.full, &results.files);
+        let report = candidates.report(DedupeStage::Size, files_in, 0, pending);
+        self.finish_dedupe_stage(session_id, results, report).await;
+
+        if plan.by_name {
+            let files = &results.files;
+            candidates = candidates.refine(|id| files.get(id).map(|file| file.name().to_string()));
+            let pending = hashed_later(&candidates, plan.blocks || plan.full, files);
+            let report = candidates.report(DedupeStage::Name, before, 0, pending);
+            before = (candidates.file_count() as u64, candidates.bytes(files));
+            self.finish_dedupe_stage(session_id, results, report).await;
         }
 
-        // Second pass: calculate partial hash for candidates with multiple files
-        let mut duplicate_groups = Vec::new();
-        for (_, group) in candidate_groups {
-            if group.len() > 1 {
-                // Calculate partial hash for verification
-                let verified_groups = self.verify_candidates_with_partial_hash(group).await?;
-                duplicate_groups.extend(verified_groups);
+        if plan.blocks {
+            let sampled: Vec<(EntryId, String, u64)> = candidates
+                .files()
+                .filter_map(|id| results.files.get(id))
+                .filter(|file| plan.samples_blocks(file.size()))
+                .map(|file| {
+                    let path = file.path().to_string_lossy().to_string();
+                    (file.id(), path, file.size())
+                })
+                .collect();
+            let sampled_ids: HashSet<EntryId> = sampled.iter().map(|(id, _, _)| *id).collect();
+            let mut block_hashes: HashMap<EntryId, String> = HashMap::new();
+            let bytes_read = self
+                .hash_stage(
+                    session,
+                    DedupeStage::Blocks,
+                    sampled,
+                    counters,
+                    |id, hash| {
+                        block_hashes.insert(id, hash.to_string());
+                    },
+                )
+                .await?;
+            if session.control.is_cancelled() {
+                return Ok(Vec::new());
             }
-        }
 
-        duplicate_groups.sort_by(|a, b| b.potential_savings.cmp(&a.potential_savings));
-        Ok(duplicate_groups)
-    }
-
-    /// Smart duplicate detection (adaptive strategy)
-    async fn find_duplicates_smart(
-        &self,
-        files: &[FileInfo],
-        config: &ScanConfig,
-    ) -> Result<Vec<DuplicateGroup>> {
-        let mut all_duplicates = Vec::new();
-
-        // Separate files by size thresholds
-        let (small_files, medium_files, large_files): (Vec<_>, Vec<_>, Vec<_>) =
-            files.iter().cloned().partition_into_three(|f| {
-                if f.size < 1024 * 1024 {
-                    0
-                }
-                // < 1MB
-                else if f.size < 100 * 1024 * 1024 {
-                    1
-                }
-                // < 100MB
-                else {
-                    2
-                } // >= 100MB
+            // Files too small to be worth sampling pass through with their group
+            candidates = candidates.refine(|id| match sampled_ids.contains(&id) {
+                true => block_hashes.remove(&id),
+                false => Some(String::new()),
             });
-
-        // Small files: Use name+size (fast)
-        if !small_files.is_empty() {
-            let small_duplicates = self.find_duplicates_by_name_size(&small_files).await?;
-            all_duplicates.extend(small_duplicates);
+            let pending = hashed_later(&candidates, plan.full, &results.files);
+            let report = candidates.report(DedupeStage::Blocks, before, bytes_read, pending);
+            before = (
+                candidates.file_count() as u64,
+                candidates.bytes(&results.files),
+            );
+            self.finish_dedupe_stage(session_id, results, report).await;
         }
 
-        // Medium files: Use name+size+partial hash (balanced)
-        if !medium_files.is_empty() {
-            let medium_duplicates = self
-                .find_duplicates_by_name_size_partial_hash(&medium_files)
+        if plan.full {
+            // Hashes carried forward by an incremental scan are not read again
+            let algorithm = self.hashing.algorithm;
+            let unhashed: Vec<(EntryId, String, u64)> = candidates
+                .files()
+                .filter_map(|id| results.files.get(id))
+                .filter(|file| file.hash().is_none_or(|hash| !algorithm.tagged(hash)))
+                .map(|file| {
+                    let path = file.path().to_string_lossy().to_string();
+                    (file.id(), path, file.size())
+                })
+                .collect();
+            let bytes_read = self
+                .hash_stage(
+                    session,
+                    DedupeStage::Full,
+                    unhashed,
+                    counters,
+                    |id, hash| {
+                        results.files.set_hash(id, hash);
+                        checkpointer.file_hashed(id);
+                        checkpointer.maybe_save("deep", &results.directories, &results.files);
+                    },
+                )
                 .await?;
-            all_duplicates.extend(medium_duplicates);
-        }
-
-        // Large files: Use full hash if configured, otherwise name+size
-        if !large_files.is_empty() {
-            let large_duplicates = if config.calculate_hashes {
-                self.find_duplicates_by_hash(&large_files).await?
-            } else {
-                self.find_duplicates_by_name_size(&large_files).await?
-            };
-            all_duplicates.extend(large_duplicates);
-        }
-
-        all_duplicates.sort_by(|a, b| b.potential_savings.cmp(&a.potential_savings));
-        Ok(all_duplicates)
-    }
-
-    /// Verify candidate duplicates with partial hash
-    async fn verify_candidates_with_partial_hash(
-        &self,
-        candidates: Vec<FileInfo>,
-    ) -> Result<Vec<DuplicateGroup>> {
-        let mut hash_groups: HashMap<String, Vec<FileInfo>> = HashMap::new();
-
-        // Calculate partial hash for each candidate
-        for file in candidates {
-            if let Ok(partial_hash) = self.calculate_partial_hash(&file.path).await {
-                let key = format!("{}:{}:{}", file.name, file.size, partial_hash);
-                hash_groups.entry(key).or_default().push(file);
+            if session.control.is_cancelled() {
+                return Ok(Vec::new());
             }
-        }
-
-        // Find verified duplicates
-        let mut duplicate_groups = Vec::new();
-        for (key, group) in hash_groups {
-            if group.len() > 1 {
-                let total_size: u64 = group.iter().map(|f| f.size).sum();
-                let potential_savings = total_size - group[0].size;
-
-                duplicate_groups.push(DuplicateGroup {
-                    hash: key,
-                    files: group,
-                    total_size,
-                    potential_savings,
-                });
-            }
-        }
-
-        Ok(duplicate_groups)
-    }
-
-    /// Calculate partial hash (first 64KB + last 64KB)
-    async fn calculate_partial_hash(&self, path: &str) -> Result<String> {
-        use tokio::fs::File;
-        use tokio::io::{AsyncReadExt, AsyncSeekExt, SeekFrom};
-
-        let mut file = File::open(path).await?;
-        let metadata = file.metadata().await?;
-        let file_size = metadata.len();
-
-        let mut hasher = Sha256::new();
 
-        // Read first 64KB
-        let mut buffer = vec![0u8; 65536];
-        let bytes_read = file.read(&mut buffer).await?;
-        hasher.update(&buffer[..bytes_read]);
-
-        // If file is large enough, also read last 64KB
-        if file_size > 131072 {
-            // 128KB
-            file.seek(SeekFrom::End(-65536)).await?;
-            let bytes_read = file.read(&mut buffer).await?;
-            hasher.update(&buffer[..bytes_read]);
+            let files = &results.files;
+            candidates = candidates.regroup(|id| {
+                files
+                    .get(id)
+                    .and_then(|file| file.hash())
+                    .filter(|hash| algorithm.tagged(hash))
+                    .map(str::to_string)
+            });
+            let report = candidates.report(DedupeStage::Full, before, bytes_read, 0);
+            self.finish_dedupe_stage(session_id, results, report).await;
         }
 
-        let result = hasher.finalize();
-        Ok(format!("{:x}", result))
+        Ok(candidates.into_duplicate_groups(&results.files))
     }
 
     /// Advanced large file analysis
-    async fn analyze_large_files(&self, files: &[FileInfo], config: &ScanConfig) -> Vec<FileInfo> {
+    async fn analyze_large_files(&self, files: &FileTable, config: &ScanConfig) -> Vec<FileInfo> {
         let mut large_files: Vec<FileInfo> = files
-            .iter()
-            .filter(|f| f.size >= config.large_file_threshold)
-            .cloned()
+            .entries()
+            .filter(|f| f.size() >= config.large_file_threshold)
+            .map(|f| f.to_file_info())
             .collect();
 
         // Sort by size (largest first)
@@ -1294,31 +1559,3 @@ impl DiskAnalyzer {
         }
     }
 }
-
-// Helper trait for three-way partitioning
-trait PartitionIntoThree<T> {
-    fn partition_into_three<F>(self, f: F) -> (Vec<T>, Vec<T>, Vec<T>)
-    where
-        F: Fn(&T) -> usize;
-}
-
-impl<T, I: Iterator<Item = T>> PartitionIntoThree<T> for I {
-    fn partition_into_three<F>(self, f: F) -> (Vec<T>, Vec<T>, Vec<T>)
-    where
-        F: Fn(&T) -> usize,
-    {
-        let mut group0 = Vec::new();
-        let mut group1 = Vec::new();
-        let mut group2 = Vec::new();
-
-        for item in self {
-            match f(&item) {
-                0 => group0.push(item),
-                1 => group1.push(item),
-                _ => group2.push(item),
-            }
-        }
-
-        (group0, group1, group2)
-    }
-}
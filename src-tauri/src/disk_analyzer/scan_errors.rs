@@ -0,0 +1,144 @@
+use crate::error::DiskDominatorError;
+use crate::file_system::path_encoding;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Errors kept with their path; past this only the counts per kind grow
+pub const MAX_RECORDED_ERRORS: usize = 1000;
+
+/// Example paths listed in each summary
+const SUMMARY_EXAMPLES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanErrorKind {
+    PermissionDenied,
+    PathVanished,  // Removed while the scan was running
+    BrokenSymlink, // Followed link whose target does not exist
+    PathTooLong,
+    Io,
+}
+
+/// What the UI can offer to get past an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanErrorAction {
+    RetryElevated,
+    ExcludePath,
+}
+
+impl ScanErrorKind {
+    pub fn of(error: &DiskDominatorError) -> Self {
+        match error {
+            DiskDominatorError::PermissionDenied(_) => Self::PermissionDenied,
+            DiskDominatorError::PathNotFound(_) => Self::PathVanished,
+            DiskDominatorError::BrokenSymlink(_) => Self::BrokenSymlink,
+            DiskDominatorError::PathTooLong(_) => Self::PathTooLong,
+            _ => Self::Io,
+        }
+    }
+
+    pub fn suggested_action(self) -> Option<ScanErrorAction> {
+        match self {
+            Self::PermissionDenied => Some(ScanErrorAction::RetryElevated),
+            Self::PathVanished => None,
+            Self::BrokenSymlink | Self::PathTooLong | Self::Io => {
+                Some(ScanErrorAction::ExcludePath)
+            }
+        }
+    }
+}
+
+/// An error hit while scanning one path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanError {
+    pub kind: ScanErrorKind,
+    pub path: String,
+    pub message: String,
+}
+
+impl ScanError {
+    pub fn new(path: &Path, error: &DiskDominatorError) -> Self {
+        Self {
+            kind: ScanErrorKind::of(error),
+            path: path_encoding::encode_path(path),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Errors of one kind
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanErrorSummary {
+    pub kind: ScanErrorKind,
+    pub count: u64,
+    pub suggested_action: Option<ScanErrorAction>,
+    pub example_paths: Vec<String>,
+}
+
+/// Everything that went wrong during a scan, as returned to the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanErrorReport {
+    pub total: u64,
+    pub truncated: bool, // More errors happened than are listed in `errors`
+    pub summaries: Vec<ScanErrorSummary>, // Most frequent kind first
+    pub errors: Vec<ScanError>,
+}
+
+/// Errors collected by a scan session.
+///
+/// The first [`MAX_RECORDED_ERRORS`] errors are kept with their path, so a
+/// scan of a volume full of unreadable directories cannot grow the list
+/// without bound; every error still counts towards the summary of its kind.
+#[derive(Debug, Clone, Default)]
+pub struct ScanErrors {
+    errors: Vec<ScanError>,
+    counts: HashMap<ScanErrorKind, u64>,
+}
+
+impl ScanErrors {
+    /// Count an error, returning whether it was kept with its path
+    pub fn record(&mut self, error: ScanError) -> bool {
+        *self.counts.entry(error.kind).or_default() += 1;
+        if self.errors.len() < MAX_RECORDED_ERRORS {
+            self.errors.push(error);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    pub fn report(&self) -> ScanErrorReport {
+        let mut summaries: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(&kind, &count)| ScanErrorSummary {
+                kind,
+                count,
+                suggested_action: kind.suggested_action(),
+                example_paths: self
+                    .errors
+                    .iter()
+                    .filter(|e| e.kind == kind)
+                    .take(SUMMARY_EXAMPLES)
+                    .map(|e| e.path.clone())
+                    .collect(),
+            })
+            .collect();
+        summaries.sort_by_key(|summary| (Reverse(summary.count), summary.kind));
+
+        let total = self.total();
+        ScanErrorReport {
+            total,
+            truncated: total > self.errors.len() as u64,
+            summaries,
+            errors: self.errors.clone(),
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use super::control::ScanControl;
+use crate::app_config::ThrottleConfig;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Longest single sleep, so cancelling a scan is noticed quickly
+const SLEEP_STEP: Duration = Duration::from_millis(100);
+
+/// Wait between checks while other programs keep the disks busy
+const IDLE_BACKOFF: Duration = Duration::from_millis(500);
+
+/// How often disk activity is sampled in idle mode
+const ACTIVITY_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Throughput limiter shared by the walker and hashing workers of every scan.
+///
+/// Limits are token buckets holding up to one second of throughput. A worker
+/// takes what it is about to use and sleeps off any debt, so a large read is
+/// paid for afterwards instead of waiting for a bucket that never fills.
+/// Settings can be replaced while scans run; workers sleeping under the old
+/// ones wake up and continue under the new ones.
+#[derive(Debug, Default)]
+pub struct Throttle {
+    settings: RwLock<ThrottleConfig>,
+    generation: AtomicU64, // Bumped whenever the settings change
+    bytes: Mutex<TokenBucket>,
+    files: Mutex<TokenBucket>,
+    activity: Mutex<DiskActivity>,
+}
+
+/// Current settings and whether idle mode is holding scans back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThrottleStatus {
+    pub settings: ThrottleConfig,
+    pub backing_off: bool,           // Other programs are using the disks
+    pub io_priority_supported: bool, // Idle mode lowers the workers' I/O priority here
+}
+
+impl Throttle {
+    pub fn new(settings: ThrottleConfig) -> Self {
+        Self {
+            settings: RwLock::new(settings),
+            ..Self::default()
+        }
+    }
+
+    pub fn settings(&self) -> ThrottleConfig {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Replace the limits; debt built up under the old ones is forgiven
+    pub fn set_settings(&self, settings: ThrottleConfig) {
+        *self.settings.write().unwrap() = settings;
+        *self.bytes.lock().unwrap() = TokenBucket::default();
+        *self.files.lock().unwrap() = TokenBucket::default();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn status(&self) -> ThrottleStatus {
+        let settings = self.settings();
+        ThrottleStatus {
+            backing_off: settings.idle_mode && self.is_disk_busy(&settings),
+            settings,
+            io_priority_supported: cfg!(any(target_os = "linux", windows)),
+        }
+    }
+
+    /// Block a worker thread until it may process `files` files and `bytes`
+    /// bytes. Cancelling the scan or changing the settings ends the wait.
+    pub fn wait(&self, files: u64, bytes: u64, control: &ScanControl) {
+        let generation = self.generation.load(Ordering::SeqCst);
+        let settings = self.settings();
+        set_thread_io_priority(settings.idle_mode);
+
+        if settings.idle_mode {
+            while self.is_disk_busy(&settings) && !self.interrupted(generation, control) {
+                std::thread::sleep(IDLE_BACKOFF);
+            }
+        }
+
+        let now = Instant::now();
+        let delay = self
+            .files
+            .lock()
+            .unwrap()
+            .take(files, settings.max_files_per_sec, now)
+            .max(
+                self.bytes
+                    .lock()
+                    .unwrap()
+                    .take(bytes, settings.max_bytes_per_sec, now),
+            );
+        let until = now + delay;
+        while !self.interrupted(generation, control) {
+            let left = until.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                break;
+            }
+            std::thread::sleep(left.min(SLEEP_STEP));
+        }
+    }
+
+    /// The scan was cancelled or the settings changed since `generation`
+    fn interrupted(&self, generation: u64, control: &ScanControl) -> bool {
+        control.is_cancelled() || self.generation.load(Ordering::SeqCst) != generation
+    }
+
+    fn is_disk_busy(&self, settings: &ThrottleConfig) -> bool {
+        self.activity
+            .lock()
+            .unwrap()
+            .is_busy(settings.busy_bytes_per_sec, Instant::now())
+    }
+}
+
+/// Tokens refilled at the limit's rate, capped at one second's worth
+#[derive(Debug, Default)]
+pub struct TokenBucket {
+    available: f64, // Negative while in debt
+    updated: Option<Instant>,
+}
+
+impl TokenBucket {
+    /// Take `amount` tokens at `rate` per second, returning how long the
+    /// caller has to wait to pay off the resulting debt. A rate of 0 is
+    /// unlimited.
+    pub fn take(&mut self, amount: u64, rate: u64, now: Instant) -> Duration {
+        if rate == 0 || amount == 0 {
+            return Duration::ZERO;
+        }
+        let rate = rate as f64;
+        let elapsed = match self.updated {
+            Some(updated) => now.saturating_duration_since(updated).as_secs_f64(),
+            None => 1.0, // Starts full
+        };
+        self.updated = Some(now);
+        self.available = (self.available + elapsed * rate).min(rate) - amount as f64;
+
+        match self.available < 0.0 {
+            true => Duration::from_secs_f64(-self.available / rate),
+            false => Duration::ZERO,
+        }
+    }
+}
+
+/// Disk traffic of other programs, sampled at most once a second
+#[derive(Debug, Default)]
+struct DiskActivity {
+    last: Option<(Instant, u64, u64)>, // Sample time, system and own bytes
+    busy: bool,
+}
+
+impl DiskActivity {
+    fn is_busy(&mut self, threshold: u64, now: Instant) -> bool {
+        if let Some((sampled, _, _)) = self.last {
+            if now.saturating_duration_since(sampled) < ACTIVITY_SAMPLE_INTERVAL {
+                return self.busy;
+            }
+        }
+        let (Some(system), Some(own)) = (system_io_bytes(), own_io_bytes()) else {
+            return false; // Not measurable here
+        };
+
+        if let Some((sampled, last_system, last_own)) = self.last {
+            let seconds = now.saturating_duration_since(sampled).as_secs_f64();
+            let others = system
+                .saturating_sub(last_system)
+                .saturating_sub(own.saturating_sub(last_own));
+            self.busy = others as f64 / seconds > threshold as f64;
+        }
+        self.last = Some((now, system, own));
+        self.busy
+    }
+}
+
+/// Bytes read and written by every disk since boot
+#[cfg(target_os = "linux")]
+fn system_io_bytes() -> Option<u64> {
+    let stats = std::fs::read_to_string("/proc/diskstats").ok()?;
+    Some(parse_diskstats(&stats))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn system_io_bytes() -> Option<u64> {
+    None
+}
+
+/// Bytes this process made the disks read and write
+#[cfg(target_os = "linux")]
+fn own_io_bytes() -> Option<u64> {
+    let io = std::fs::read_to_string("/proc/self/io").ok()?;
+    let field = |name: &str| -> Option<u64> {
+        io.lines()
+            .find_map(|line| line.strip_prefix(name)?.trim().parse().ok())
+    };
+    Some(field("read_bytes:")? + field("write_bytes:")?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn own_io_bytes() -> Option<u64> {
+    None
+}
+
+/// Bytes read and written by the whole disks listed in `/proc/diskstats`.
+///
+/// Partitions, and virtual devices stacked on other disks, are left out so
+/// the same traffic is not counted twice.
+pub fn parse_diskstats(stats: &str) -> u64 {
+    const SECTOR_BYTES: u64 = 512;
+    const VIRTUAL: [&str; 5] = ["loop", "ram", "zram", "dm-", "md"];
+
+    let devices: Vec<(&str, u64)> = stats
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // major minor name reads merged sectors_read ms writes merged sectors_written ...
+            let name = *fields.get(2)?;
+            let read: u64 = fields.get(5)?.parse().ok()?;
+            let written: u64 = fields.get(9)?.parse().ok()?;
+            Some((name, read + written))
+        })
+        .filter(|(name, _)| !VIRTUAL.iter().any(|prefix| name.starts_with(prefix)))
+        .collect();
+
+    // sda1 and nvme0n1p1 are partitions of sda and nvme0n1
+    let is_partition = |name: &str| {
+        devices.iter().any(|(disk, _)| {
+            name.strip_prefix(disk).is_some_and(|rest| {
+                let number = rest.strip_prefix('p').unwrap_or(rest);
+                !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+            })
+        })
+    };
+    devices
+        .iter()
+        .filter(|(name, _)| !is_partition(name))
+        .map(|(_, sectors)| sectors * SECTOR_BYTES)
+        .sum()
+}
+
+thread_local! {
+    static IO_PRIORITY_LOWERED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run the calling thread's I/O at idle priority, or back at the default
+fn set_thread_io_priority(low: bool) {
+    if IO_PRIORITY_LOWERED.with(|lowered| lowered.replace(low)) == low {
+        return;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+        const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+        const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+        // Class 0 gives the thread back the priority derived from its nice value
+        let priority = if low {
+            IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT
+        } else {
+            0
+        };
+        // SAFETY: plain syscall; a `who` of 0 is the calling thread
+        let result =
+            unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, priority) };
+        if result != 0 {
+            tracing::debug!(
+                "Could not change I/O priority: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        use winapi::um::processthreadsapi::{GetCurrentThread, SetThreadPriority};
+        use winapi::um::winbase::{THREAD_MODE_BACKGROUND_BEGIN, THREAD_MODE_BACKGROUND_END};
+        let mode = if low {
+            THREAD_MODE_BACKGROUND_BEGIN
+        } else {
+            THREAD_MODE_BACKGROUND_END
+        };
+        // SAFETY: the pseudo handle always refers to the calling thread
+        if unsafe { SetThreadPriority(GetCurrentThread(), mode as i32) } == 0 {
+            tracing::debug!(
+                "Could not change I/O priority: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use super::control::ScanControl;
+use super::exclusion::{ExclusionRules, IgnoreFiles};
+use super::incremental::PreviousSnapshot;
+use super::scan_errors::{ScanError, ScanErrors};
+use super::throttle::Throttle;
+use super::{DiskAnalyzer, ScanConfig, ScanProgress, ScanSession};
+use crate::error::DiskDominatorError;
+use crate::file_system::{path_encoding, FileIdentity, FileInfo, HardLinkSet};
+use crate::websocket::{ScanProgressMessage, WebSocketManager};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+/// Entry produced by the quick scan walker
+pub enum WalkEntry {
+    File(Box<FileInfo>),
+    Directory(String, DateTime<Utc>),
+}
+
+/// Parallel directory walker of the quick scan.
+///
+/// Every directory is listed as its own task on a bounded pool of worker
+/// threads; idle workers steal pending directories from busy ones, so both
+/// wide and deep trees keep the pool busy. Files and directories stream to
+/// the collector through the channel as they are found.
+///
+/// A directory is only sent once its whole subtree has been walked, so the
+/// directories collected always describe complete subtrees. Subtrees cut
+/// short by the depth, size or filesystem limits of the config do not count
+/// as complete.
+///
+/// When the walker holds a previous snapshot and a directory's modification
+/// time matches it, the directory is not listed again: its files are carried
+/// forward from the snapshot and only its known subdirectories are visited.
+/// Files rewritten in place do not change their directory's modification
+/// time, so a full scan is still needed to pick those up.
+pub struct Walker {
+    tx: mpsc::Sender<WalkEntry>,
+    progress: Arc<Mutex<ScanProgress>>,
+    errors: Arc<Mutex<ScanErrors>>,
+    config: ScanConfig,
+    rules: ExclusionRules,
+    session_id: String,
+    websocket_manager: Arc<WebSocketManager>,
+    control: Arc<ScanControl>,
+    previous: Option<Arc<PreviousSnapshot>>,
+    runtime: tokio::runtime::Handle,
+    root_device: Option<u64>,
+    visited: Mutex<HashSet<(u64, u64)>>, // (device, inode) of directories entered
+    hard_links: Mutex<HardLinkSet>,      // Files whose size was already counted
+    throttle: Arc<Throttle>,
+}
+
+/// A directory whose subtree is still being walked
+struct DirNode {
+    path: PathBuf,
+    modified: DateTime<Utc>,
+    pending: AtomicUsize, // Its own listing plus subdirectories not finished yet
+    complete: AtomicBool,
+    parent: Option<Arc<DirNode>>,
+}
+
+impl Walker {
+    /// Must be called from within the Tokio runtime, which sends progress messages
+    pub fn new(
+        tx: mpsc::Sender<WalkEntry>,
+        progress: Arc<Mutex<ScanProgress>>,
+        session: &ScanSession,
+        rules: ExclusionRules,
+        previous: Option<Arc<PreviousSnapshot>>,
+        websocket_manager: Arc<WebSocketManager>,
+        throttle: Arc<Throttle>,
+    ) -> Self {
+        Self {
+            tx,
+            progress,
+            errors: session.errors.clone(),
+            config: session.config.clone(),
+            rules,
+            session_id: session.id.clone(),
+            websocket_manager,
+            control: session.control.clone(),
+            previous,
+            runtime: tokio::runtime::Handle::current(),
+            root_device: fs::metadata(&session.disk_path)
+                .ok()
+                .and_then(|metadata| {
+                    crate::file_system::identity_of(Path::new(&session.disk_path), &metadata)
+                })
+                .map(|identity| identity.device),
+            visited: Mutex::new(HashSet::new()),
+            hard_links: Mutex::new(HardLinkSet::default()),
+            throttle,
+        }
+    }
+
+    /// Walk `root` on `threads` worker threads, blocking until done
+    pub fn run(self, root: PathBuf, threads: usize) -> Result<()> {
+        // An unreadable root fails the scan instead of being reported as an error entry
+        fs::read_dir(&root)?;
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.max(1))
+            .thread_name(|i| format!("scan-walker-{}", i))
+            .build()?;
+        pool.scope(|scope| self.walk_dir(scope, root, 0, IgnoreFiles::default(), None));
+
+        Ok(())
+    }
+
+    fn walk_dir<'s>(
+        &'s self,
+        scope: &rayon::Scope<'s>,
+        path: PathBuf,
+        depth: usize,
+        ignore_files: IgnoreFiles,
+        parent: Option<Arc<DirNode>>,
+    ) {
+        let (metadata, modified) = match fs::metadata(&path).and_then(|m| Ok((m.modified()?, m))) {
+            Ok((modified, m)) => (m, DateTime::<Utc>::from(modified)),
+            Err(e) => {
+                self.record_error(&path, DiskDominatorError::from_io(&path, e));
+                return self.release(parent, false);
+            }
+        };
+
+        // Stay on the root's filesystem when asked to
+        let identity = crate::file_system::identity_of(&path, &metadata);
+        if self.config.same_filesystem && identity.map(|i| i.device) != self.root_device {
+            return self.release(parent, false);
+        }
+
+        // Never enter the same directory twice, which breaks symlink loops
+        if let Some(FileIdentity { device, inode, .. }) = identity {
+            if !self.visited.lock().unwrap().insert((device, inode)) {
+                return self.release(parent, true);
+            }
+        }
+
+        let ignore_files = self.rules.enter(&path, &ignore_files);
+        let node = Arc::new(DirNode {
+            path,
+            modified,
+            pending: AtomicUsize::new(1),
+            complete: AtomicBool::new(true),
+            parent,
+        });
+
+        let complete = match &self.previous {
+            Some(previous) if previous.is_unchanged(&node.path, modified) => {
+                self.carry_forward(scope, &node, previous, depth, &ignore_files)
+            }
+            _ => match self.list_directory(scope, &node, depth, &ignore_files) {
+                Ok(complete) => complete,
+                Err(e) => {
+                    self.record_error(&node.path, DiskDominatorError::from_io(&node.path, e));
+                    false
+                }
+            },
+        };
+
+        self.release(Some(node), complete);
+    }
+
+    /// List a directory's entries, sending its files and queueing its subdirectories
+    fn list_directory<'s>(
+        &'s self,
+        scope: &rayon::Scope<'s>,
+        dir: &Arc<DirNode>,
+        depth: usize,
+        ignore_files: &IgnoreFiles,
+    ) -> std::io::Result<bool> {
+        let descend = DiskAnalyzer::within_depth(depth + 1, &self.config);
+        let mut complete = true;
+
+        for entry in fs::read_dir(&dir.path)? {
+            let entry = entry?;
+            let path = entry.path();
+            let mut metadata = match entry.metadata() {
+                Ok(m) => m,
+                Err(e) => {
+                    self.record_error(&path, DiskDominatorError::from_io(&path, e));
+                    continue;
+                }
+            };
+
+            // Update current path
+            self.progress.lock().unwrap().current_path = path.to_string_lossy().to_string();
+
+            // Check for pause/cancel
+            self.control.wait_if_paused_blocking();
+            self.throttle.wait(1, 0, &self.control);
+
+            if self.control.is_cancelled() {
+                return Ok(false);
+            }
+
+            if metadata.file_type().is_symlink() {
+                if !self.config.follow_symlinks {
+                    continue;
+                }
+                metadata = match fs::metadata(&path) {
+                    Ok(m) => m,
+                    Err(e) => {
+                        self.record_error(&path, DiskDominatorError::from_io(&path, e));
+                        continue;
+                    }
+                };
+            }
+
+            // Excluded directories are pruned before descending into them
+            if self.is_skipped(&path, metadata.is_dir(), ignore_files) {
+                continue;
+            }
+
+            if metadata.is_dir() {
+                if !descend {
+                    complete = false;
+                    continue;
+                }
+                self.spawn_subdirectory(scope, path, depth + 1, ignore_files, dir);
+            } else if metadata.is_file() {
+                if !DiskAnalyzer::within_size_limits(metadata.len(), &self.config) {
+                    complete = false;
+                    continue;
+                }
+
+                // Process file
+                let file_info = crate::file_system::file_info(&path, &metadata);
+
+                if !self.emit_file(file_info) {
+                    return Ok(false); // Receiver dropped
+                }
+            }
+        }
+
+        Ok(complete)
+    }
+
+    /// Send the files of an unchanged directory from the previous snapshot
+    fn carry_forward<'s>(
+        &'s self,
+        scope: &rayon::Scope<'s>,
+        dir: &Arc<DirNode>,
+        previous: &PreviousSnapshot,
+        depth: usize,
+        ignore_files: &IgnoreFiles,
+    ) -> bool {
+        if self.control.is_cancelled() {
+            return false;
+        }
+
+        let descend = DiskAnalyzer::within_depth(depth + 1, &self.config);
+        let mut complete = true;
+
+        for file in previous.files_in(&dir.path) {
+            if self.is_skipped(&path_encoding::decode_path(&file.path), false, ignore_files) {
+                continue;
+            }
+            if !DiskAnalyzer::within_size_limits(file.size, &self.config) {
+                complete = false;
+                continue;
+            }
+            if !self.emit_file(file) {
+                return false;
+            }
+        }
+
+        for subdir in previous.subdirs_of(&dir.path) {
+            if self.is_skipped(subdir, true, ignore_files) {
+                continue;
+            }
+            if !descend {
+                complete = false;
+                continue;
+            }
+            self.spawn_subdirectory(scope, subdir.clone(), depth + 1, ignore_files, dir);
+        }
+
+        complete
+    }
+
+    /// Queue a subdirectory for whichever worker is free next
+    fn spawn_subdirectory<'s>(
+        &'s self,
+        scope: &rayon::Scope<'s>,
+        path: PathBuf,
+        depth: usize,
+        ignore_files: &IgnoreFiles,
+        parent: &Arc<DirNode>,
+    ) {
+        parent.pending.fetch_add(1, Ordering::SeqCst);
+        let ignore_files = ignore_files.clone();
+        let parent = parent.clone();
+        scope.spawn(move |scope| self.walk_dir(scope, path, depth, ignore_files, Some(parent)));
+    }
+
+    /// Finish one piece of work on `node`; the last one sends the directory
+    /// when its subtree is complete and reports to the parent
+    fn release(&self, node: Option<Arc<DirNode>>, complete: bool) {
+        let Some(node) = node else {
+            return;
+        };
+        if !complete {
+            node.complete.store(false, Ordering::SeqCst);
+        }
+        if node.pending.fetch_sub(1, Ordering::SeqCst) != 1 {
+            return;
+        }
+
+        let mut complete = node.complete.load(Ordering::SeqCst);
+        if complete {
+            let entry = WalkEntry::Directory(path_encoding::encode_path(&node.path), node.modified);
+            complete = self.tx.blocking_send(entry).is_ok();
+        }
+        self.release(node.parent.clone(), complete);
+    }
+
+    /// Count a file towards the progress and send it; returns false once the receiver is gone
+    fn emit_file(&self, file_info: FileInfo) -> bool {
+        // Update progress, counting the data of hard-linked files once
+        let first = self
+            .hard_links
+            .lock()
+            .unwrap()
+            .first_sighting(file_info.hard_link_key());
+        let processed = self
+            .control
+            .add_file(if first { file_info.size } else { 0 });
+        let current_size = self.control.bytes_processed();
+        self.progress.lock().unwrap().current_path = file_info.path.clone();
+
+        // Send progress update via WebSocket (every 100 files to avoid spam)
+        if processed.is_multiple_of(100) {
+            let websocket_manager = self.websocket_manager.clone();
+            // The expected size is only an estimate, so the walk may outgrow it
+            let total_size = self.control.expected_bytes().max(current_size);
+            let progress = if total_size > 0 {
+                current_size as f64 / total_size as f64 * 100.0
+            } else {
+                0.0
+            };
+            let message = ScanProgressMessage {
+                scan_id: self.session_id.clone(),
+                disk_id: "unknown".to_string(),
+                scan_type: "quick".to_string(),
+                progress,
+                quick_scan_progress: Some(progress),
+                deep_scan_progress: None,
+                remaining_time: self.control.remaining_time().map_or(0, |r| r.as_secs()),
+                files_scanned: processed,
+                total_files: processed,
+                bytes_scanned: current_size,
+                total_bytes: total_size,
+                current_path: file_info.path.clone(),
+                scan_status: "running".to_string(),
+                errors: Vec::new(),
+            };
+            self.runtime.spawn(async move {
+                let _ = websocket_manager
+                    .update_scan_progress(&message.scan_id.clone(), message)
+                    .await;
+            });
+        }
+
+        // Send file info
+        self.tx
+            .blocking_send(WalkEntry::File(Box::new(file_info)))
+            .is_ok()
+    }
+
+    /// Check excluded patterns, ignore files and hidden entries
+    fn is_skipped(&self, path: &Path, is_dir: bool, ignore_files: &IgnoreFiles) -> bool {
+        if self.rules.is_excluded(path, is_dir, ignore_files) {
+            return true;
+        }
+
+        // Check if hidden files should be included
+        !self.config.include_hidden
+            && path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with('.'))
+                .unwrap_or(false)
+    }
+
+    /// Record an error on `path`; the first ones also show up in the progress
+    fn record_error(&self, path: &Path, error: DiskDominatorError) {
+        let error = ScanError::new(path, &error);
+        let message = error.message.clone();
+        if self.errors.lock().unwrap().record(error) {
+            self.progress.lock().unwrap().errors.push(message);
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::io::ErrorKind;
+use std::path::Path;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -23,6 +25,21 @@ pub enum DiskDominatorError {
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
 
+    #[error("Path no longer exists: {0}")]
+    PathNotFound(String),
+
+    #[error("Path too long: {0}")]
+    PathTooLong(String),
+
+    #[error("Symbolic link points to a missing target: {0}")]
+    BrokenSymlink(String),
+
+    #[error("File changed since the scan: {0}")]
+    FileChanged(String),
+
+    #[error("Contents differ from the kept copy: {0}")]
+    ContentMismatch(String),
+
     #[error("Windows API error: {0}")]
     #[cfg(windows)]
     WindowsApi(String),
@@ -37,6 +54,25 @@ pub enum DiskDominatorError {
     Other(#[from] anyhow::Error),
 }
 
+impl DiskDominatorError {
+    /// Classify an I/O error hit while accessing `path`
+    pub fn from_io(path: &Path, error: std::io::Error) -> Self {
+        // A link that is still there was not removed, only its target is missing
+        let broken_symlink = error.kind() == ErrorKind::NotFound && path.is_symlink();
+        let path = path.display().to_string();
+        match error.kind() {
+            ErrorKind::PermissionDenied => Self::PermissionDenied(path),
+            ErrorKind::NotFound if broken_symlink => Self::BrokenSymlink(path),
+            ErrorKind::NotFound => Self::PathNotFound(path),
+            ErrorKind::InvalidFilename => Self::PathTooLong(path),
+            _ => Self::FileSystem(std::io::Error::new(
+                error.kind(),
+                format!("{}: {}", path, error),
+            )),
+        }
+    }
+}
+
 // Implementación para convertir a string para Tauri
 impl From<DiskDominatorError> for String {
     fn from(err: DiskDominatorError) -> Self {
@@ -0,0 +1,60 @@
+use super::{path_encoding, FileInfo};
+use crate::error::{DiskDominatorError, DiskResult};
+use chrono::{DateTime, Utc};
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+use tokio::fs;
+
+/// Identifier of a file that survives renames and moves within its filesystem.
+///
+/// Built from the device and inode, plus the birth time as a generation where
+/// the platform reports one, so a later file that reuses the inode gets a new
+/// ID. Hard links share their inode, so theirs also carry a hash of the path.
+/// Files without an inode fall back to a hash of the path, which changes
+/// when they are renamed.
+pub fn file_id(
+    device: Option<u64>,
+    inode: Option<u64>,
+    generation: Option<u64>,
+    links: Option<u64>,
+    path: &str,
+) -> String {
+    let (Some(device), Some(inode)) = (device, inode) else {
+        return format!("p{:x}", md5::compute(path));
+    };
+
+    let mut id = format!("{:x}-{:x}", device, inode);
+    if let Some(generation) = generation {
+        id.push_str(&format!("-{:x}", generation));
+    }
+    if links.is_some_and(|links| links > 1) {
+        id.push_str(&format!("-{:x}", md5::compute(path)));
+    }
+    id
+}
+
+/// Birth time in nanoseconds, telling apart files that reuse the same inode
+pub fn generation_of(metadata: &std::fs::Metadata) -> Option<u64> {
+    let created = metadata.created().ok()?;
+    let nanos = created.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+    u64::try_from(nanos).ok()
+}
+
+/// Exact path of a scanned file, provided the file found there is still the
+/// one that was scanned: same identity, size and modification time
+pub async fn resolve_unchanged(file: &FileInfo) -> DiskResult<PathBuf> {
+    let path = path_encoding::decode_path(&file.path);
+    let metadata = fs::symlink_metadata(&path)
+        .await
+        .map_err(|e| DiskDominatorError::from_io(&path, e))?;
+
+    let modified = metadata.modified().ok().map(DateTime::<Utc>::from);
+    let identity = super::identity_of(&path, &metadata);
+    let same_identity = (file.inode.is_none() || file.inode == identity.map(|i| i.inode))
+        && (file.device.is_none() || file.device == identity.map(|i| i.device))
+        && (file.generation.is_none() || file.generation == generation_of(&metadata));
+    if !same_identity || metadata.len() != file.size || modified != Some(file.modified) {
+        return Err(DiskDominatorError::FileChanged(path.display().to_string()));
+    }
+    Ok(path)
+}
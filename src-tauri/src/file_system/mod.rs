@@ -1,11 +1,15 @@
-// Error types will be used later
-// use crate::error::{DiskDominatorError, DiskResult};
+use crate::error::DiskDominatorError;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Read;
 use std::path::Path;
 use tokio::fs;
 
+pub mod file_id;
+pub mod path_encoding;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileInfo {
     pub path: String,
@@ -16,6 +20,84 @@ pub struct FileInfo {
     pub is_directory: bool,
     pub extension: Option<String>,
     pub hash: Option<String>,
+    #[serde(default)]
+    pub inode: Option<u64>, // File identity used to detect replaced files
+    #[serde(default)]
+    pub device: Option<u64>, // Filesystem the inode belongs to
+    #[serde(default)]
+    pub links: Option<u64>, // Hard links to the file's data
+    #[serde(default)]
+    pub allocated_size: Option<u64>, // Bytes the file takes on disk, when known
+    #[serde(default)]
+    pub generation: Option<u64>, // Tells apart files that reuse an inode, see `file_id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_path: Option<String>, // Readable form of a path that is not valid Unicode, see `path_encoding`
+}
+
+/// Which size of a file totals and rankings use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeBasis {
+    #[default]
+    Apparent, // Length of the contents, as `metadata.len()` reports it
+    Allocated, // Space taken on disk, including cluster slack, less sparse or compressed ranges
+}
+
+impl FileInfo {
+    /// Size on the given basis; an unknown allocation falls back to the apparent size
+    pub fn size_on(&self, basis: SizeBasis) -> u64 {
+        match basis {
+            SizeBasis::Apparent => self.size,
+            SizeBasis::Allocated => self.allocated_size.unwrap_or(self.size),
+        }
+    }
+
+    /// Space allocated beyond the file's contents
+    pub fn slack(&self) -> u64 {
+        self.allocated_size
+            .map_or(0, |allocated| allocated.saturating_sub(self.size))
+    }
+
+    /// Identity shared by every hard link to the same data; `None` for files
+    /// with a single link or when the platform does not report links
+    pub fn hard_link_key(&self) -> Option<(u64, u64)> {
+        hard_link_key(self.device, self.inode, self.links)
+    }
+
+    /// Identifier that stays the same when the file is renamed or moved
+    pub fn file_id(&self) -> String {
+        file_id::file_id(
+            self.device,
+            self.inode,
+            self.generation,
+            self.links,
+            &self.path,
+        )
+    }
+}
+
+/// `(device, inode)` of a file with more than one hard link
+pub fn hard_link_key(
+    device: Option<u64>,
+    inode: Option<u64>,
+    links: Option<u64>,
+) -> Option<(u64, u64)> {
+    match (device, inode, links) {
+        (Some(device), Some(inode), Some(links)) if links > 1 => Some((device, inode)),
+        _ => None,
+    }
+}
+
+/// Hard-linked files already counted, so their size is only added once
+#[derive(Debug, Default)]
+pub struct HardLinkSet(HashSet<(u64, u64)>);
+
+impl HardLinkSet {
+    /// Whether the data behind `key` is seen for the first time; files
+    /// without a hard link key always are
+    pub fn first_sighting(&mut self, key: Option<(u64, u64)>) -> bool {
+        key.is_none_or(|key| self.0.insert(key))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -391,48 +473,276 @@ async fn get_unix_disks() -> Result<Vec<DiskInfo>> {
 
 /// Get file information
 pub async fn get_file_info(path: &str) -> Result<FileInfo> {
-    let path = Path::new(path);
+    let path = &path_encoding::decode_path(path);
     let metadata = fs::metadata(path).await?;
+    Ok(file_info(path, &metadata))
+}
 
-    let name = path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("")
-        .to_string();
-
-    let extension = path
-        .extension()
-        .and_then(|e| e.to_str())
-        .map(|e| e.to_string());
-
-    Ok(FileInfo {
-        path: path.to_string_lossy().to_string(),
-        name,
+/// Describe the file at `path` from its metadata; times the platform does not
+/// report are taken as now
+pub fn file_info(path: &Path, metadata: &std::fs::Metadata) -> FileInfo {
+    let now = std::time::SystemTime::now();
+    let identity = identity_of(path, metadata);
+    FileInfo {
+        path: path_encoding::encode_path(path),
+        name: path
+            .file_name()
+            .map(path_encoding::display_name)
+            .unwrap_or_default(),
         size: metadata.len(),
-        modified: metadata.modified()?.into(),
-        created: metadata.created()?.into(),
+        modified: metadata.modified().unwrap_or(now).into(),
+        created: metadata.created().unwrap_or(now).into(),
         is_directory: metadata.is_dir(),
-        extension,
+        extension: path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_string()),
         hash: None,
-    })
+        inode: identity.map(|identity| identity.inode),
+        device: identity.map(|identity| identity.device),
+        links: identity.map(|identity| identity.links),
+        allocated_size: allocated_size(path, metadata),
+        generation: file_id::generation_of(metadata),
+        display_path: path_encoding::display_path_of(path),
+    }
+}
+
+/// Where a file's data lives: the device (volume) and inode (file index)
+/// shared by every hard link to it, and how many links there are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileIdentity {
+    pub device: u64,
+    pub inode: u64,
+    pub links: u64,
+}
+
+/// Identity of the file at `path`, described by `metadata`, when the platform
+/// exposes one. Windows only reports it for an open handle, so the file is
+/// opened without access rights to read it.
+pub fn identity_of(path: &Path, metadata: &std::fs::Metadata) -> Option<FileIdentity> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let _ = path;
+        Some(FileIdentity {
+            device: metadata.dev(),
+            inode: metadata.ino(),
+            links: metadata.nlink(),
+        })
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        use winapi::um::fileapi::{
+            CreateFileW, GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION, OPEN_EXISTING,
+        };
+        use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+        use winapi::um::winbase::{FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OPEN_REPARSE_POINT};
+        use winapi::um::winnt::{FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE};
+
+        let wide: Vec<u16> = path
+            .as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+        // Backup semantics open directories too; a symlink's metadata describes the link itself
+        let mut flags = FILE_FLAG_BACKUP_SEMANTICS;
+        if metadata.file_type().is_symlink() {
+            flags |= FILE_FLAG_OPEN_REPARSE_POINT;
+        }
+        let handle = unsafe {
+            CreateFileW(
+                wide.as_ptr(),
+                0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                std::ptr::null_mut(),
+                OPEN_EXISTING,
+                flags,
+                std::ptr::null_mut(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return None;
+        }
+
+        let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+        let read = unsafe { GetFileInformationByHandle(handle, &mut info) } != 0;
+        unsafe { CloseHandle(handle) };
+        read.then(|| FileIdentity {
+            device: info.dwVolumeSerialNumber as u64,
+            inode: ((info.nFileIndexHigh as u64) << 32) | info.nFileIndexLow as u64,
+            links: info.nNumberOfLinks as u64,
+        })
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (path, metadata);
+        None
+    }
+}
+
+/// Bytes a file takes on disk: allocated blocks on Unix, the compressed or
+/// sparse size on Windows
+pub fn allocated_size(path: &Path, metadata: &std::fs::Metadata) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let _ = path;
+        Some(metadata.blocks() * 512) // `st_blocks` is always in 512-byte units
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        use winapi::um::fileapi::{GetCompressedFileSizeW, INVALID_FILE_SIZE};
+        let _ = metadata;
+
+        let wide: Vec<u16> = path
+            .as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+        let mut high = 0u32;
+        let low = unsafe { GetCompressedFileSizeW(wide.as_ptr(), &mut high) };
+        if low == INVALID_FILE_SIZE && std::io::Error::last_os_error().raw_os_error() != Some(0) {
+            return None;
+        }
+        Some(((high as u64) << 32) | low as u64)
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (path, metadata);
+        None
+    }
+}
+
+/// Bytes freed by removing one link to a file; nothing while other links remain
+pub fn reclaimable_size(path: &Path, metadata: &std::fs::Metadata) -> u64 {
+    match identity_of(path, metadata) {
+        Some(identity) if identity.links > 1 => 0,
+        _ => metadata.len(),
+    }
+}
+
+/// Delete a file found by a scan, returning the bytes freed.
+///
+/// The file is resolved by its id first, so a file changed or replaced since
+/// the scan is left alone and reported as an error.
+pub async fn delete_indexed_file(file: &FileInfo, move_to_trash: bool) -> Result<u64> {
+    let path = file_id::resolve_unchanged(file).await?;
+    remove_scanned(&path, move_to_trash).await
+}
+
+/// Delete a duplicate found by a scan once it is confirmed, byte for byte,
+/// to hold the same contents as the copy kept in its place. Returns the
+/// bytes freed.
+///
+/// The comparison runs right before removal, so a file changed since the
+/// scan is checked again rather than trusted; it is deleted only if it
+/// still matches the kept copy. With `hashed`, for groups confirmed by a
+/// full hash, files the scan hashed alike and that are both unchanged since
+/// are deleted without it. A path that leads to the kept copy itself, by
+/// another name or link, is never deleted.
+pub async fn delete_indexed_duplicate(
+    file: &FileInfo,
+    kept: &FileInfo,
+    hashed: bool,
+    move_to_trash: bool,
+) -> Result<u64> {
+    let path = path_encoding::decode_path(&file.path);
+    let kept_path = path_encoding::decode_path(&kept.path);
+    let mut identities = Vec::with_capacity(2);
+    for path in [&path, &kept_path] {
+        let metadata = fs::symlink_metadata(path)
+            .await
+            .map_err(|e| DiskDominatorError::from_io(path, e))?;
+        if !metadata.is_file() {
+            return Err(DiskDominatorError::FileChanged(path.display().to_string()).into());
+        }
+        identities.push(identity_of(path, &metadata));
+    }
+    let same_file = match (identities[0], identities[1]) {
+        (Some(a), Some(b)) => (a.device, a.inode) == (b.device, b.inode),
+        _ => false,
+    };
+    if path == kept_path || same_file {
+        anyhow::bail!("Cannot delete the kept copy: {}", path.display());
+    }
+
+    let trusted = hashed
+        && file.hash.is_some()
+        && file.hash == kept.hash
+        && file_id::resolve_unchanged(file).await.is_ok()
+        && file_id::resolve_unchanged(kept).await.is_ok();
+    let (a, b) = (path.clone(), kept_path.clone());
+    if !trusted && !tokio::task::spawn_blocking(move || same_contents(&a, &b)).await?? {
+        return Err(DiskDominatorError::ContentMismatch(path.display().to_string()).into());
+    }
+    remove_scanned(&path, move_to_trash).await
+}
+
+/// Whether two files hold the same bytes, reading both side by side and
+/// stopping at the first difference
+pub fn same_contents(a: &Path, b: &Path) -> std::io::Result<bool> {
+    const CHUNK_SIZE: u64 = 64 * 1024;
+
+    let (mut a, mut b) = (std::fs::File::open(a)?, std::fs::File::open(b)?);
+    if a.metadata()?.len() != b.metadata()?.len() {
+        return Ok(false);
+    }
+    let mut chunk_a = Vec::with_capacity(CHUNK_SIZE as usize);
+    let mut chunk_b = Vec::with_capacity(CHUNK_SIZE as usize);
+    loop {
+        chunk_a.clear();
+        chunk_b.clear();
+        // Sizes can change while reading, so the chunks are compared whole
+        let read = (&mut a).take(CHUNK_SIZE).read_to_end(&mut chunk_a)?;
+        (&mut b).take(CHUNK_SIZE).read_to_end(&mut chunk_b)?;
+        if chunk_a != chunk_b {
+            return Ok(false);
+        }
+        if read == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Remove a scanned file or move it aside, returning the bytes freed
+async fn remove_scanned(path: &Path, move_to_trash: bool) -> Result<u64> {
+    let metadata = fs::metadata(path).await?;
+    let size = reclaimable_size(path, &metadata);
+
+    if move_to_trash {
+        // In a real implementation, you would use a proper trash library
+        // For now, we'll just rename the file
+        let trash_path = path_encoding::with_suffix(path, ".trash");
+        fs::rename(path, trash_path).await?;
+    } else {
+        fs::remove_file(path).await?;
+    }
+
+    Ok(size)
 }
 
 /// Move file to destination
 pub async fn move_file(source: &str, destination: &str) -> Result<()> {
-    fs::rename(source, destination).await?;
+    fs::rename(
+        path_encoding::decode_path(source),
+        path_encoding::decode_path(destination),
+    )
+    .await?;
     Ok(())
 }
 
 /// Delete file (move to trash)
 pub async fn delete_file(path: &str) -> Result<()> {
     // In production, use trash crate for safe deletion
-    fs::remove_file(path).await?;
+    fs::remove_file(path_encoding::decode_path(path)).await?;
     Ok(())
 }
 
 /// Rename file
 pub async fn rename_file(path: &str, new_name: &str) -> Result<()> {
-    let path = Path::new(path);
+    let path = &path_encoding::decode_path(path);
     let parent = path.parent().unwrap();
     let new_path = parent.join(new_name);
 
@@ -0,0 +1,187 @@
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::path::{Component, Path, PathBuf};
+
+/// Starts an encoded component; NUL cannot occur in a real file name
+const MARKER: char = '\0';
+
+/// Lossless text form of a path.
+///
+/// Paths travel through the app as strings: in [`FileInfo`](super::FileInfo),
+/// the scan index and command arguments. A path that is valid Unicode is
+/// returned as is. In any other path, each component that is not valid
+/// Unicode becomes a NUL followed by the hex of its raw OS encoding (bytes on
+/// Unix, UTF-16 units on Windows), so [`decode_path`] gets the exact path
+/// back. Components stay separated by the usual separator, so parents and
+/// file names can still be taken from the encoded form.
+pub fn encode_path(path: &Path) -> String {
+    if let Some(path) = path.to_str() {
+        return path.to_string();
+    }
+
+    let mut encoded = PathBuf::new();
+    for component in path.components() {
+        let name = component.as_os_str();
+        match name.to_str() {
+            Some(name) => encoded.push(name),
+            None => encoded.push(encode_component(name)),
+        }
+    }
+    encoded.to_string_lossy().into_owned()
+}
+
+/// The exact path an [`encode_path`] string stands for
+pub fn decode_path(path: &str) -> PathBuf {
+    if !is_encoded(path) {
+        return PathBuf::from(path);
+    }
+
+    Path::new(path)
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => name
+                .to_str()
+                .and_then(decode_component)
+                .unwrap_or_else(|| name.to_os_string()),
+            other => other.as_os_str().to_os_string(),
+        })
+        .collect()
+}
+
+/// Whether `path` has components that are not valid Unicode
+pub fn is_encoded(path: &str) -> bool {
+    path.contains(MARKER)
+}
+
+/// Readable form of an encoded path for the UI, with the bytes that are not
+/// valid Unicode escaped as `\xNN` (or `\u{NNNN}` for unpaired surrogates on Windows)
+pub fn display_path(path: &str) -> Cow<'_, str> {
+    if !is_encoded(path) {
+        return Cow::Borrowed(path);
+    }
+
+    let mut display = PathBuf::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(name) => {
+                let name = name.to_str().unwrap_or_default();
+                match decode_component(name) {
+                    Some(raw) => display.push(display_name(&raw)),
+                    None => display.push(name),
+                }
+            }
+            other => display.push(other.as_os_str()),
+        }
+    }
+    Cow::Owned(display.to_string_lossy().into_owned())
+}
+
+/// Display form of a path, only set for those that are not valid Unicode
+pub fn display_path_of(path: &Path) -> Option<String> {
+    match path.to_str() {
+        Some(_) => None,
+        None => Some(display_path(&encode_path(path)).into_owned()),
+    }
+}
+
+/// Readable form of a file name, escaping whatever is not valid Unicode
+pub fn display_name(name: &OsStr) -> String {
+    if let Some(name) = name.to_str() {
+        return name.to_string();
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let mut display = String::new();
+        for chunk in name.as_bytes().utf8_chunks() {
+            display.push_str(chunk.valid());
+            for byte in chunk.invalid() {
+                display.push_str(&format!("\\x{:02X}", byte));
+            }
+        }
+        display
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        char::decode_utf16(name.encode_wide())
+            .map(|c| match c {
+                Ok(c) => c.to_string(),
+                Err(e) => format!("\\u{{{:04X}}}", e.unpaired_surrogate()),
+            })
+            .collect()
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        name.to_string_lossy().into_owned()
+    }
+}
+
+/// `path` with `suffix` appended to its file name, keeping any bytes that are not valid Unicode
+pub fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_os_string();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+fn encode_component(name: &OsStr) -> String {
+    let mut encoded = String::from(MARKER);
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        for byte in name.as_bytes() {
+            encoded.push_str(&format!("{:02x}", byte));
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        for unit in name.encode_wide() {
+            encoded.push_str(&format!("{:04x}", unit));
+        }
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        encoded.push_str(&name.to_string_lossy());
+    }
+    encoded
+}
+
+fn decode_component(name: &str) -> Option<OsString> {
+    let hex = name.strip_prefix(MARKER)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        let bytes = parse_hex(hex, 2)?
+            .into_iter()
+            .map(|byte| byte as u8)
+            .collect();
+        Some(OsString::from_vec(bytes))
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStringExt;
+        let units: Vec<u16> = parse_hex(hex, 4)?
+            .into_iter()
+            .map(|unit| unit as u16)
+            .collect();
+        Some(OsString::from_wide(&units))
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        Some(OsString::from(hex))
+    }
+}
+
+/// Split `hex` into numbers of `digits` hex digits each
+#[cfg(any(unix, windows))]
+fn parse_hex(hex: &str, digits: usize) -> Option<Vec<u32>> {
+    if !hex.len().is_multiple_of(digits) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(digits)
+        .map(|i| u32::from_str_radix(&hex[i..i + digits], 16).ok())
+        .collect()
+}
@@ -0,0 +1,796 @@
+use crate::file_system::{path_encoding, FileInfo, HardLinkSet, SizeBasis};
+use chrono::{DateTime, Utc};
+use hashbrown::hash_table::{Entry as TableEntry, HashTable};
+use serde::de::{SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasher, RandomState};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// Identifier of an entry in a [`FileTable`]
+pub type EntryId = u32;
+
+type NameId = u32;
+
+const NO_PARENT: EntryId = EntryId::MAX;
+
+const LISTED: u8 = 1 << 0; // Found by the scan, not only the parent of something that was
+const DIRECTORY: u8 = 1 << 1;
+const HAS_INODE: u8 = 1 << 2;
+const HAS_ALLOCATED: u8 = 1 << 3;
+const HAS_GENERATION: u8 = 1 << 4;
+const REMOVED: u8 = 1 << 5; // Deleted after the scan; kept so the entry can be reused
+
+/// Bytes of packed metadata kept for every entry
+pub const ENTRY_BYTES: usize = std::mem::size_of::<Entry>();
+
+/// Compact store of the files found by a scan.
+///
+/// Paths are kept as a parent-pointer tree: every entry points at the entry
+/// of its directory and names a segment in a table of interned names, so a
+/// directory's path and a name shared by many files are stored once. Each
+/// entry takes [`ENTRY_BYTES`] (64) bytes of packed metadata and every
+/// distinct name about 13 bytes plus its length; content
+/// hashes are only stored for the files that have one. [`FileInfo`] values
+/// are built on demand when the table is iterated.
+///
+/// The table is shared copy-on-write, so handing scan results to sessions
+/// and to the scan index does not copy the entries. It serializes as a list
+/// of [`FileInfo`], which keeps stored indexes and checkpoints readable.
+#[derive(Clone, Default)]
+pub struct FileTable {
+    inner: Arc<Inner>,
+}
+
+#[derive(Clone, Default)]
+struct Inner {
+    names: Names,
+    entries: Vec<Entry>,
+    listed: usize,
+    hashes: HashMap<EntryId, Box<str>>,
+    hash_bytes: usize,
+    devices: Vec<u64>, // Distinct devices, referenced by index + 1
+    dirs: HashMap<(EntryId, NameId), EntryId>, // Directory entries by parent and name
+    last_dir: Option<(PathBuf, EntryId)>, // Files mostly arrive grouped by directory
+    children: OnceLock<Children>, // Built on the first lookup
+}
+
+/// Packed metadata of one entry
+#[derive(Debug, Clone)]
+struct Entry {
+    size: u64,
+    allocated: u64, // Bytes taken on disk
+    modified: i64,  // Nanoseconds since the Unix epoch
+    created: i64,
+    inode: u64,
+    generation: u64,
+    parent: EntryId,
+    name: NameId,
+    links: u32,  // Saturated hard link count, 0 when unknown
+    device: u16, // Index + 1 into `devices`, 0 when unknown
+    flags: u8,
+}
+
+/// Interned name segments stored back to back in one buffer
+#[derive(Clone, Default)]
+struct Names {
+    bytes: String,
+    spans: Vec<(u32, u32)>, // Offset and length in `bytes`
+    lookup: HashTable<NameId>,
+    hasher: RandomState,
+}
+
+/// Children of every entry, sorted by name id
+#[derive(Clone)]
+struct Children {
+    offsets: Vec<u32>,
+    ids: Vec<EntryId>,
+}
+
+/// Recursive size and number of files below an entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubtreeSize {
+    pub size: u64,
+    pub files: u64,
+}
+
+/// Borrowed view of one entry, readable without building a [`FileInfo`]
+#[derive(Clone, Copy)]
+pub struct FileRef<'a> {
+    table: &'a FileTable,
+    id: EntryId,
+}
+
+impl FileTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of files in the table
+    pub fn len(&self) -> usize {
+        self.inner.listed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.listed == 0
+    }
+
+    /// Add a file, returning its id. The name and extension are taken from its path.
+    pub fn push(&mut self, file: &FileInfo) -> EntryId {
+        let inner = Arc::make_mut(&mut self.inner);
+        inner.children = OnceLock::new();
+
+        let path = Path::new(&file.path);
+        let parent = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => inner.directory(dir),
+            _ => NO_PARENT,
+        };
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_else(|| file.path.as_str().into());
+        let name = inner.names.intern(&name);
+        let entry = inner.entry(file, parent, name);
+
+        // A directory may already be in the table as the parent of earlier entries
+        let id = match file.is_directory {
+            true => match inner.dirs.get(&(parent, name)) {
+                Some(&id) => {
+                    if inner.entries[id as usize].flags & LISTED == 0 {
+                        inner.listed += 1;
+                    }
+                    inner.entries[id as usize] = entry;
+                    id
+                }
+                None => {
+                    let id = inner.add(entry);
+                    inner.dirs.insert((parent, name), id);
+                    inner.listed += 1;
+                    id
+                }
+            },
+            false => {
+                inner.listed += 1;
+                inner.add(entry)
+            }
+        };
+
+        if let Some(hash) = &file.hash {
+            inner.set_hash(id, hash);
+        }
+        id
+    }
+
+    /// Ids of every file, in the order they were added
+    pub fn ids(&self) -> impl Iterator<Item = EntryId> + '_ {
+        self.inner
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.flags & LISTED != 0)
+            .map(|(id, _)| id as EntryId)
+    }
+
+    /// Borrowed views of every file, in the order they were added
+    pub fn entries(&self) -> impl Iterator<Item = FileRef<'_>> + '_ {
+        self.ids().map(move |id| FileRef { table: self, id })
+    }
+
+    /// Every file, built on demand
+    pub fn iter(&self) -> impl Iterator<Item = FileInfo> + '_ {
+        self.entries().map(|file| file.to_file_info())
+    }
+
+    pub fn to_vec(&self) -> Vec<FileInfo> {
+        self.iter().collect()
+    }
+
+    pub fn get(&self, id: EntryId) -> Option<FileRef<'_>> {
+        self.inner
+            .entries
+            .get(id as usize)
+            .filter(|entry| entry.flags & LISTED != 0)
+            .map(|_| FileRef { table: self, id })
+    }
+
+    pub fn set_hash(&mut self, id: EntryId, hash: &str) {
+        Arc::make_mut(&mut self.inner).set_hash(id, hash);
+    }
+
+    /// Add or replace files by path, returning what was stored at each path before.
+    ///
+    /// A file deleted with [`FileTable::remove`] leaves its entry behind, and
+    /// adding the same path again reuses it. Every path is looked up before
+    /// the first change, so a batch only rebuilds the child index once; the
+    /// paths of one batch must be distinct.
+    pub fn upsert_all(&mut self, files: &[FileInfo]) -> Vec<Option<FileInfo>> {
+        let existing: Vec<_> = files
+            .iter()
+            .map(|file| self.lookup(Path::new(&file.path)))
+            .collect();
+        let previous = existing
+            .iter()
+            .map(|id| {
+                id.and_then(|id| self.get(id))
+                    .map(|file| file.to_file_info())
+            })
+            .collect();
+
+        for (file, id) in files.iter().zip(existing) {
+            match id {
+                Some(id) => self.replace(id, file),
+                None => {
+                    self.push(file);
+                }
+            }
+        }
+        previous
+    }
+
+    /// Remove the entry at `path` and everything below it, returning the files removed
+    pub fn remove(&mut self, path: &Path) -> Vec<FileInfo> {
+        let Some(id) = self.lookup(path) else {
+            return Vec::new();
+        };
+        let mut subtree = vec![id];
+        let mut next = 0;
+        while next < subtree.len() {
+            subtree.extend_from_slice(self.children().of(subtree[next]));
+            next += 1;
+        }
+        let removed = subtree
+            .iter()
+            .filter_map(|&id| self.get(id))
+            .filter(|file| !file.is_directory())
+            .map(|file| file.to_file_info())
+            .collect();
+
+        // Entries stay where they are, so the child index remains valid
+        let inner = Arc::make_mut(&mut self.inner);
+        inner.last_dir = None;
+        for id in subtree {
+            let entry = &mut inner.entries[id as usize];
+            if entry.flags & LISTED != 0 {
+                inner.listed -= 1;
+            }
+            entry.flags = (entry.flags & !LISTED) | REMOVED;
+            if let Some(hash) = inner.hashes.remove(&id) {
+                inner.hash_bytes -= hash.len();
+            }
+        }
+        removed
+    }
+
+    /// The file stored at `path`
+    pub fn find(&self, path: &Path) -> Option<FileRef<'_>> {
+        let id = self.lookup(path)?;
+        self.get(id)
+    }
+
+    /// The entry at `path`, including directories only known as the parent of scanned files
+    pub fn node(&self, path: &Path) -> Option<FileRef<'_>> {
+        self.lookup(path)
+            .filter(|&id| self.inner.entries[id as usize].flags & REMOVED == 0)
+            .map(|id| FileRef { table: self, id })
+    }
+
+    /// Files and directories found directly inside `dir`
+    pub fn children_of(&self, dir: &Path) -> impl Iterator<Item = FileRef<'_>> + '_ {
+        let children = self.lookup(dir).map(|id| self.children().of(id));
+        children
+            .into_iter()
+            .flatten()
+            .filter_map(move |&id| self.get(id))
+    }
+
+    /// Total size of the files, counting the data of hard-linked files once
+    pub fn total_size(&self, basis: SizeBasis) -> u64 {
+        let mut hard_links = HardLinkSet::default();
+        self.entries()
+            .filter(|file| hard_links.first_sighting(file.hard_link_key()))
+            .map(|file| file.size_on(basis))
+            .sum()
+    }
+
+    /// Recursive size and file count of every entry, indexed by id. The
+    /// data of hard-linked files is counted once, under the first link.
+    pub fn subtree_sizes(&self, basis: SizeBasis) -> Vec<SubtreeSize> {
+        let entries = &self.inner.entries;
+        let mut sizes = vec![SubtreeSize::default(); entries.len()];
+        let mut hard_links = HardLinkSet::default();
+        for file in self.entries().filter(|file| !file.is_directory()) {
+            let own = &mut sizes[file.id as usize];
+            own.files = 1;
+            if hard_links.first_sighting(file.hard_link_key()) {
+                own.size = file.size_on(basis);
+            }
+        }
+
+        // Parents are always added before their children, so one backwards pass sums every subtree
+        for id in (0..entries.len()).rev() {
+            let parent = entries[id].parent;
+            if parent != NO_PARENT {
+                let own = sizes[id];
+                let total = &mut sizes[parent as usize];
+                total.size += own.size;
+                total.files += own.files;
+            }
+        }
+        sizes
+    }
+
+    /// Approximate heap memory held by the table
+    pub fn memory_usage(&self) -> usize {
+        let inner = &*self.inner;
+        let names = &inner.names;
+        inner.entries.capacity() * ENTRY_BYTES
+            + names.bytes.capacity()
+            + names.spans.capacity() * std::mem::size_of::<(u32, u32)>()
+            + names.lookup.capacity() * (std::mem::size_of::<NameId>() + 1)
+            + inner.hashes.capacity() * (std::mem::size_of::<(EntryId, Box<str>)>() + 1)
+            + inner.hash_bytes
+            + inner.dirs.capacity() * (std::mem::size_of::<((EntryId, NameId), EntryId)>() + 1)
+            + inner
+                .children
+                .get()
+                .map(|c| (c.offsets.capacity() + c.ids.capacity()) * 4)
+                .unwrap_or_default()
+    }
+
+    /// Overwrite entry `id` with `file`, bringing back its directories if they were removed
+    fn replace(&mut self, id: EntryId, file: &FileInfo) {
+        let inner = Arc::make_mut(&mut self.inner);
+        inner.children = OnceLock::new();
+
+        let old = &inner.entries[id as usize];
+        let (mut parent, name) = (old.parent, old.name);
+        let entry = inner.entry(file, parent, name);
+        if inner.entries[id as usize].flags & LISTED == 0 {
+            inner.listed += 1;
+        }
+        inner.entries[id as usize] = entry;
+        match &file.hash {
+            Some(hash) => inner.set_hash(id, hash),
+            None => {
+                if let Some(hash) = inner.hashes.remove(&id) {
+                    inner.hash_bytes -= hash.len();
+                }
+            }
+        }
+
+        while parent != NO_PARENT {
+            let entry = &mut inner.entries[parent as usize];
+            entry.flags &= !REMOVED;
+            parent = entry.parent;
+        }
+    }
+
+    fn lookup(&self, path: &Path) -> Option<EntryId> {
+        let inner = &*self.inner;
+        let parent = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => inner.find_directory(dir)?,
+            _ => NO_PARENT,
+        };
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_else(|| path.to_string_lossy());
+        let name = inner.names.get(&name)?;
+
+        if let Some(&id) = inner.dirs.get(&(parent, name)) {
+            return Some(id);
+        }
+        if parent == NO_PARENT {
+            return None;
+        }
+        let siblings = self.children().of(parent);
+        siblings
+            .binary_search_by_key(&name, |&id| inner.entries[id as usize].name)
+            .ok()
+            .map(|i| siblings[i])
+    }
+
+    fn children(&self) -> &Children {
+        self.inner.children.get_or_init(|| {
+            let entries = &self.inner.entries;
+            let mut offsets = vec![0u32; entries.len() + 1];
+            for entry in entries.iter().filter(|e| e.parent != NO_PARENT) {
+                offsets[entry.parent as usize + 1] += 1;
+            }
+            for i in 1..offsets.len() {
+                offsets[i] += offsets[i - 1];
+            }
+
+            let mut next = offsets.clone();
+            let mut ids = vec![0; offsets[entries.len()] as usize];
+            for (id, entry) in entries.iter().enumerate() {
+                if entry.parent != NO_PARENT {
+                    let slot = &mut next[entry.parent as usize];
+                    ids[*slot as usize] = id as EntryId;
+                    *slot += 1;
+                }
+            }
+            for parent in 0..entries.len() {
+                let range = offsets[parent] as usize..offsets[parent + 1] as usize;
+                ids[range].sort_unstable_by_key(|&id| entries[id as usize].name);
+            }
+
+            Children { offsets, ids }
+        })
+    }
+
+    fn path_of(&self, id: EntryId) -> PathBuf {
+        let inner = &*self.inner;
+        let mut segments = Vec::new();
+        let mut current = id;
+        while current != NO_PARENT {
+            let entry = &inner.entries[current as usize];
+            segments.push(inner.names.resolve(entry.name));
+            current = entry.parent;
+        }
+
+        let mut path = PathBuf::new();
+        for segment in segments.iter().rev() {
+            path.push(segment);
+        }
+        path
+    }
+
+    fn to_nanos(time: DateTime<Utc>) -> i64 {
+        time.timestamp_nanos_opt()
+            .unwrap_or(if time.timestamp() < 0 {
+                i64::MIN
+            } else {
+                i64::MAX
+            })
+    }
+}
+
+impl Inner {
+    /// Packed metadata of `file`
+    fn entry(&mut self, file: &FileInfo, parent: EntryId, name: NameId) -> Entry {
+        let mut flags = LISTED;
+        if file.is_directory {
+            flags |= DIRECTORY;
+        }
+        if file.inode.is_some() {
+            flags |= HAS_INODE;
+        }
+        if file.allocated_size.is_some() {
+            flags |= HAS_ALLOCATED;
+        }
+        if file.generation.is_some() {
+            flags |= HAS_GENERATION;
+        }
+        Entry {
+            size: file.size,
+            allocated: file.allocated_size.unwrap_or_default(),
+            modified: FileTable::to_nanos(file.modified),
+            created: FileTable::to_nanos(file.created),
+            inode: file.inode.unwrap_or_default(),
+            generation: file.generation.unwrap_or_default(),
+            parent,
+            name,
+            links: file.links.map_or(0, |n| n.min(u32::MAX as u64) as u32),
+            device: file.device.map_or(0, |d| self.device_index(d)),
+            flags,
+        }
+    }
+
+    fn add(&mut self, entry: Entry) -> EntryId {
+        let id = self.entries.len() as EntryId;
+        self.entries.push(entry);
+        id
+    }
+
+    /// Id of the directory at `path`, adding it and its parents as needed
+    fn directory(&mut self, path: &Path) -> EntryId {
+        if let Some((last, id)) = &self.last_dir {
+            if last == path {
+                return *id;
+            }
+        }
+
+        let mut parent = NO_PARENT;
+        let mut current = PathBuf::new();
+        for component in path.components() {
+            current.push(component);
+            let name = self.names.intern(&component.as_os_str().to_string_lossy());
+            parent = match self.dirs.get(&(parent, name)) {
+                Some(&id) => {
+                    self.entries[id as usize].flags &= !REMOVED;
+                    id
+                }
+                None => {
+                    let id = self.add(Entry {
+                        size: 0,
+                        allocated: 0,
+                        modified: 0,
+                        created: 0,
+                        inode: 0,
+                        generation: 0,
+                        parent,
+                        name,
+                        links: 0,
+                        device: 0,
+                        flags: DIRECTORY,
+                    });
+                    self.dirs.insert((parent, name), id);
+                    id
+                }
+            };
+        }
+
+        self.last_dir = Some((path.to_path_buf(), parent));
+        parent
+    }
+
+    fn find_directory(&self, path: &Path) -> Option<EntryId> {
+        let mut parent = NO_PARENT;
+        for component in path.components() {
+            let name = self.names.get(&component.as_os_str().to_string_lossy())?;
+            parent = *self.dirs.get(&(parent, name))?;
+        }
+        Some(parent)
+    }
+
+    /// Index + 1 of `device`, or 0 once more devices than fit were seen
+    fn device_index(&mut self, device: u64) -> u16 {
+        if let Some(index) = self.devices.iter().position(|&d| d == device) {
+            return index as u16 + 1;
+        }
+        if self.devices.len() == u16::MAX as usize {
+            return 0;
+        }
+        self.devices.push(device);
+        self.devices.len() as u16
+    }
+
+    fn set_hash(&mut self, id: EntryId, hash: &str) {
+        self.hash_bytes += hash.len();
+        if let Some(old) = self.hashes.insert(id, hash.into()) {
+            self.hash_bytes -= old.len();
+        }
+    }
+}
+
+impl Names {
+    fn intern(&mut self, name: &str) -> NameId {
+        let hash = self.hasher.hash_one(name);
+        let Self {
+            bytes,
+            spans,
+            lookup,
+            hasher,
+        } = self;
+        let entry = lookup.entry(
+            hash,
+            |&id| Self::slice(bytes, spans, id) == name,
+            |&id| hasher.hash_one(Self::slice(bytes, spans, id)),
+        );
+        match entry {
+            TableEntry::Occupied(entry) => *entry.get(),
+            TableEntry::Vacant(entry) => {
+                let id = spans.len() as NameId;
+                spans.push((bytes.len() as u32, name.len() as u32));
+                bytes.push_str(name);
+                entry.insert(id);
+                id
+            }
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<NameId> {
+        self.lookup
+            .find(self.hasher.hash_one(name), |&id| self.resolve(id) == name)
+            .copied()
+    }
+
+    fn resolve(&self, id: NameId) -> &str {
+        Self::slice(&self.bytes, &self.spans, id)
+    }
+
+    fn slice<'a>(bytes: &'a str, spans: &[(u32, u32)], id: NameId) -> &'a str {
+        let (offset, len) = spans[id as usize];
+        &bytes[offset as usize..(offset + len) as usize]
+    }
+}
+
+impl Children {
+    fn of(&self, id: EntryId) -> &[EntryId] {
+        let id = id as usize;
+        &self.ids[self.offsets[id] as usize..self.offsets[id + 1] as usize]
+    }
+}
+
+impl<'a> FileRef<'a> {
+    pub fn id(&self) -> EntryId {
+        self.id
+    }
+
+    pub fn size(&self) -> u64 {
+        self.entry().size
+    }
+
+    pub fn allocated_size(&self) -> Option<u64> {
+        let entry = self.entry();
+        (entry.flags & HAS_ALLOCATED != 0).then_some(entry.allocated)
+    }
+
+    /// Size on the given basis; an unknown allocation falls back to the apparent size
+    pub fn size_on(&self, basis: SizeBasis) -> u64 {
+        match basis {
+            SizeBasis::Apparent => self.size(),
+            SizeBasis::Allocated => self.allocated_size().unwrap_or(self.size()),
+        }
+    }
+
+    /// Name as stored, in the encoded form of [`path_encoding`] when it is not valid Unicode
+    pub fn name(&self) -> &'a str {
+        self.table.inner.names.resolve(self.entry().name)
+    }
+
+    /// Readable name for the UI
+    pub fn display_name(&self) -> Cow<'a, str> {
+        path_encoding::display_path(self.name())
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.entry().flags & DIRECTORY != 0
+    }
+
+    pub fn modified(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp_nanos(self.entry().modified)
+    }
+
+    pub fn inode(&self) -> Option<u64> {
+        let entry = self.entry();
+        (entry.flags & HAS_INODE != 0).then_some(entry.inode)
+    }
+
+    pub fn generation(&self) -> Option<u64> {
+        let entry = self.entry();
+        (entry.flags & HAS_GENERATION != 0).then_some(entry.generation)
+    }
+
+    pub fn device(&self) -> Option<u64> {
+        let device = self.entry().device;
+        (device != 0).then(|| self.table.inner.devices[device as usize - 1])
+    }
+
+    pub fn links(&self) -> Option<u64> {
+        let links = self.entry().links;
+        (links != 0).then_some(links as u64)
+    }
+
+    pub fn hard_link_key(&self) -> Option<(u64, u64)> {
+        crate::file_system::hard_link_key(self.device(), self.inode(), self.links())
+    }
+
+    /// Identifier that stays the same when the file is renamed or moved
+    pub fn file_id(&self) -> String {
+        crate::file_system::file_id::file_id(
+            self.device(),
+            self.inode(),
+            self.generation(),
+            self.links(),
+            &self.path().to_string_lossy(),
+        )
+    }
+
+    pub fn hash(&self) -> Option<&'a str> {
+        self.table.inner.hashes.get(&self.id).map(|h| &**h)
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.table.path_of(self.id)
+    }
+
+    /// Entries directly inside this one, including directories only known as
+    /// the parent of scanned files
+    pub fn children(&self) -> impl Iterator<Item = FileRef<'a>> + 'a {
+        let table = self.table;
+        table
+            .children()
+            .of(self.id)
+            .iter()
+            .filter(move |&&id| table.inner.entries[id as usize].flags & REMOVED == 0)
+            .map(move |&id| FileRef { table, id })
+    }
+
+    pub fn to_file_info(self) -> FileInfo {
+        let entry = self.entry();
+        let name = self.display_name();
+        let path = self.path().to_string_lossy().into_owned();
+        FileInfo {
+            display_path: path_encoding::is_encoded(&path)
+                .then(|| path_encoding::display_path(&path).into_owned()),
+            path,
+            name: name.to_string(),
+            size: entry.size,
+            modified: self.modified(),
+            created: DateTime::from_timestamp_nanos(entry.created),
+            is_directory: self.is_directory(),
+            extension: Path::new(name.as_ref())
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_string()),
+            hash: self.hash().map(|h| h.to_string()),
+            inode: self.inode(),
+            device: self.device(),
+            links: self.links(),
+            allocated_size: self.allocated_size(),
+            generation: self.generation(),
+        }
+    }
+
+    fn entry(&self) -> &'a Entry {
+        &self.table.inner.entries[self.id as usize]
+    }
+}
+
+impl fmt::Debug for FileTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileTable")
+            .field("files", &self.len())
+            .field("memory_usage", &self.memory_usage())
+            .finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a FileTable {
+    type Item = FileInfo;
+    type IntoIter = Box<dyn Iterator<Item = FileInfo> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+impl FromIterator<FileInfo> for FileTable {
+    fn from_iter<I: IntoIterator<Item = FileInfo>>(files: I) -> Self {
+        let mut table = Self::new();
+        for file in files {
+            table.push(&file);
+        }
+        table
+    }
+}
+
+impl Serialize for FileTable {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for file in self.iter() {
+            seq.serialize_element(&file)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for FileTable {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TableVisitor;
+
+        impl<'de> Visitor<'de> for TableVisitor {
+            type Value = FileTable;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a list of files")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<FileTable, A::Error> {
+                let mut table = FileTable::new();
+                while let Some(file) = seq.next_element::<FileInfo>()? {
+                    table.push(&file);
+                }
+                Ok(table)
+            }
+        }
+
+        deserializer.deserialize_seq(TableVisitor)
+    }
+}
@@ -0,0 +1,403 @@
+use crate::file_system::{file_id, identity_of};
+use crate::hashing::HashAlgorithm;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+/// Name of the cache file inside the data directory
+pub const HASH_CACHE_FILE: &str = "hash_cache.jsonl";
+
+/// Files kept by an in-memory cache
+const DEFAULT_MAX_ENTRIES: usize = 100_000;
+
+/// Which hash of a file's contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashKind {
+    Partial, // First, middle and last 64 KB
+    Full,
+}
+
+/// Hits and misses of the cache, for one scan or since the app started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct HashCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate: f64, // Share of lookups answered by the cache, 0 to 1
+}
+
+/// Counters behind [`HashCacheStats`], shared by hashing workers
+#[derive(Debug, Default)]
+pub struct HashCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl HashCounters {
+    fn record(&self, hit: bool) {
+        match hit {
+            true => self.hits.fetch_add(1, Ordering::Relaxed),
+            false => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+
+    pub fn snapshot(&self) -> HashCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+        HashCacheStats {
+            hits,
+            misses,
+            hit_rate: match lookups {
+                0 => 0.0,
+                _ => hits as f64 / lookups as f64,
+            },
+        }
+    }
+}
+
+/// Size of the cache and its hit rate since the app started
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashCacheStatus {
+    pub entries: usize,
+    pub max_entries: usize,
+    pub stats: HashCacheStats,
+}
+
+/// What identifies the contents of a file without reading it
+#[derive(Debug, Clone, PartialEq)]
+struct Stamp {
+    key: String, // Device and inode; a hash of the path without them
+    size: u64,
+    modified_ns: u64,
+    generation: Option<u64>, // Birth time, for inodes reused by a new file
+}
+
+impl Stamp {
+    fn of(path: &Path, metadata: &fs::Metadata) -> Option<Self> {
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        let identity = identity_of(path, metadata);
+        Some(Self {
+            key: file_id::file_id(
+                identity.map(|identity| identity.device),
+                identity.map(|identity| identity.inode),
+                None,
+                None,
+                &path.to_string_lossy(),
+            ),
+            size: metadata.len(),
+            modified_ns: u64::try_from(modified.as_nanos()).ok()?,
+            generation: file_id::generation_of(metadata),
+        })
+    }
+}
+
+/// Hashes of one file, stored as one line of the cache file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedHashes {
+    key: String,
+    size: u64,
+    modified_ns: u64,
+    generation: Option<u64>,
+    #[serde(default, rename = "blocks")]
+    partial: Option<String>, // Tagged with its algorithm; earlier versions sampled only both ends
+    #[serde(default)]
+    full: Option<String>,
+    last_used: u64, // Value of the cache's clock at the last hit or insert
+}
+
+impl CachedHashes {
+    fn matches(&self, stamp: &Stamp) -> bool {
+        self.size == stamp.size
+            && self.modified_ns == stamp.modified_ns
+            && self.generation == stamp.generation
+    }
+
+    fn hash(&mut self, kind: HashKind) -> &mut Option<String> {
+        match kind {
+            HashKind::Partial => &mut self.partial,
+            HashKind::Full => &mut self.full,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    entries: HashMap<String, CachedHashes>,
+    clock: u64,
+    dirty: bool, // Changed since the file was last written
+}
+
+/// Content hashes of files, reused while the files stay unchanged.
+///
+/// A file is known by its device and inode, so the cache follows it through
+/// renames; its size, modification time and birth time must still match for
+/// a cached hash to be used, and a file changed while it was being hashed is
+/// not cached. Past `max_entries` files, the least recently used are dropped.
+#[derive(Debug)]
+pub struct HashCache {
+    path: Option<PathBuf>,
+    max_entries: usize,
+    inner: Mutex<Inner>,
+    counters: HashCounters, // Since the app started
+}
+
+impl Default for HashCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_ENTRIES)
+    }
+}
+
+impl HashCache {
+    /// A cache kept in memory only
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            path: None,
+            max_entries,
+            inner: Mutex::default(),
+            counters: HashCounters::default(),
+        }
+    }
+
+    /// Open the cache stored in `path`; a missing file holds no hashes, and
+    /// unreadable lines are dropped
+    pub fn open(path: impl Into<PathBuf>, max_entries: usize) -> Result<Self> {
+        let path = path.into();
+        let mut inner = Inner::default();
+        if path.exists() {
+            for line in BufReader::new(fs::File::open(&path)?).lines() {
+                let Ok(entry) = serde_json::from_str::<CachedHashes>(&line?) else {
+                    continue;
+                };
+                inner.clock = inner.clock.max(entry.last_used);
+                inner.entries.insert(entry.key.clone(), entry);
+            }
+        }
+        inner.evict(max_entries);
+
+        Ok(Self {
+            path: Some(path),
+            max_entries,
+            inner: Mutex::new(inner),
+            counters: HashCounters::default(),
+        })
+    }
+
+    pub fn status(&self) -> HashCacheStatus {
+        HashCacheStatus {
+            entries: self.inner.lock().unwrap().entries.len(),
+            max_entries: self.max_entries,
+            stats: self.counters.snapshot(),
+        }
+    }
+
+    /// Forget every hash
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.clear();
+        inner.dirty = true;
+    }
+
+    /// The `kind` hash of `path` by `algorithm`, from the cache or else from
+    /// `compute`. A cached hash of another algorithm counts as a miss.
+    pub fn hash_sync(
+        &self,
+        path: &Path,
+        kind: HashKind,
+        algorithm: HashAlgorithm,
+        counters: &HashCounters,
+        compute: impl FnOnce() -> Result<String>,
+    ) -> Result<String> {
+        let before = Stamp::of(path, &fs::metadata(path)?);
+        if let Some(hash) = self.lookup(before.as_ref(), kind, algorithm, counters) {
+            return Ok(hash);
+        }
+
+        let hash = compute()?;
+        let after = fs::metadata(path).ok().and_then(|m| Stamp::of(path, &m));
+        self.store(before, after, kind, &hash);
+        Ok(hash)
+    }
+
+    /// Like [`HashCache::hash_sync`], for hashing done on the async runtime
+    pub async fn hash_async<F, Fut>(
+        &self,
+        path: &Path,
+        kind: HashKind,
+        algorithm: HashAlgorithm,
+        counters: &HashCounters,
+        compute: F,
+    ) -> Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let before = Stamp::of(path, &tokio::fs::metadata(path).await?);
+        if let Some(hash) = self.lookup(before.as_ref(), kind, algorithm, counters) {
+            return Ok(hash);
+        }
+
+        let hash = compute().await?;
+        let after = tokio::fs::metadata(path)
+            .await
+            .ok()
+            .and_then(|m| Stamp::of(path, &m));
+        self.store(before, after, kind, &hash);
+        Ok(hash)
+    }
+
+    fn lookup(
+        &self,
+        stamp: Option<&Stamp>,
+        kind: HashKind,
+        algorithm: HashAlgorithm,
+        counters: &HashCounters,
+    ) -> Option<String> {
+        let hash = stamp.filter(|_| self.max_entries > 0).and_then(|stamp| {
+            let mut inner = self.inner.lock().unwrap();
+            inner.clock += 1;
+            let clock = inner.clock;
+            let entry = inner.entries.get_mut(&stamp.key)?;
+            if !entry.matches(stamp) {
+                // The file changed since it was hashed
+                inner.entries.remove(&stamp.key);
+                inner.dirty = true;
+                return None;
+            }
+            let hash = entry
+                .hash(kind)
+                .clone()
+                .filter(|hash| algorithm.tagged(hash))?;
+            entry.last_used = clock;
+            Some(hash)
+        });
+
+        let hit = hash.is_some();
+        counters.record(hit);
+        self.counters.record(hit);
+        hash
+    }
+
+    /// Cache a hash computed between `before` and `after`, unless the file
+    /// changed in between
+    fn store(&self, before: Option<Stamp>, after: Option<Stamp>, kind: HashKind, hash: &str) {
+        let Some(stamp) = before else {
+            return;
+        };
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        inner.dirty = true;
+        if after.as_ref() != Some(&stamp) {
+            inner.entries.remove(&stamp.key);
+            return;
+        }
+
+        inner.clock += 1;
+        let clock = inner.clock;
+        let entry = inner
+            .entries
+            .entry(stamp.key.clone())
+            .or_insert_with(|| CachedHashes {
+                key: stamp.key.clone(),
+                size: stamp.size,
+                modified_ns: stamp.modified_ns,
+                generation: stamp.generation,
+                partial: None,
+                full: None,
+                last_used: clock,
+            });
+        if !entry.matches(&stamp) {
+            *entry = CachedHashes {
+                partial: None,
+                full: None,
+                size: stamp.size,
+                modified_ns: stamp.modified_ns,
+                generation: stamp.generation,
+                ..entry.clone()
+            };
+        }
+        *entry.hash(kind) = Some(hash.to_string());
+        entry.last_used = clock;
+
+        if inner.entries.len() > self.max_entries {
+            inner.evict(self.max_entries);
+        }
+    }
+
+    /// Write the cache next to the old file, then move it into place
+    pub fn flush(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let entries: Vec<CachedHashes> = {
+            let mut inner = self.inner.lock().unwrap();
+            if !inner.dirty {
+                return Ok(());
+            }
+            inner.dirty = false;
+            inner.entries.values().cloned().collect()
+        };
+
+        let written = (|| -> Result<()> {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let tmp = path.with_extension("jsonl.tmp");
+            let mut writer = BufWriter::new(fs::File::create(&tmp)?);
+            for entry in &entries {
+                serde_json::to_writer(&mut writer, entry)?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+            drop(writer);
+            fs::rename(tmp, path)?;
+            Ok(())
+        })();
+        if written.is_err() {
+            self.inner.lock().unwrap().dirty = true;
+        }
+        written
+    }
+
+    /// Flush on a blocking thread, logging failures
+    pub async fn persist(self: &Arc<Self>) {
+        let cache = self.clone();
+        match tokio::task::spawn_blocking(move || cache.flush()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::error!("Failed to persist hash cache: {}", e),
+            Err(e) => tracing::error!("Hash cache flush panicked: {}", e),
+        }
+    }
+}
+
+impl Inner {
+    /// Drop the least recently used entries, leaving room to grow before the next eviction
+    fn evict(&mut self, max_entries: usize) {
+        if self.entries.len() <= max_entries {
+            return;
+        }
+        let keep = max_entries - max_entries / 10;
+        let mut by_age: Vec<(u64, String)> = self
+            .entries
+            .values()
+            .map(|entry| (entry.last_used, entry.key.clone()))
+            .collect();
+        let evicted = by_age.len() - keep;
+        if keep > 0 {
+            by_age.select_nth_unstable(evicted - 1);
+        }
+        for (_, key) in by_age.into_iter().take(evicted) {
+            self.entries.remove(&key);
+        }
+        self.dirty = true;
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Read size for hashing, and the size of each block read by a partial hash
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Read size for BLAKE3 on big files, large enough to spread over all cores
+const PARALLEL_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Files from this size on are hashed by BLAKE3 on several threads
+const PARALLEL_MIN_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Algorithm behind a content hash.
+///
+/// Hashes are written as `<algorithm>:<hex digest>`, so hashes of different
+/// algorithms never compare equal, and untagged hashes from older versions
+/// match none of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    Sha256,
+    #[default]
+    Blake3, // Several threads per file on big files
+    Xxh3, // 128-bit, not cryptographic; meant for the pre-filter
+}
+
+impl HashAlgorithm {
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Xxh3 => "xxh3",
+        }
+    }
+
+    /// Algorithm named by the tag of `hash`
+    pub fn of(hash: &str) -> Option<Self> {
+        let (tag, _) = hash.split_once(':')?;
+        [Self::Sha256, Self::Blake3, Self::Xxh3]
+            .into_iter()
+            .find(|algorithm| algorithm.name() == tag)
+    }
+
+    /// Whether `hash` was computed by this algorithm
+    pub fn tagged(self, hash: &str) -> bool {
+        Self::of(hash) == Some(self)
+    }
+
+    pub fn hasher(self) -> ContentHasher {
+        let state = match self {
+            HashAlgorithm::Sha256 => HasherState::Sha256(Sha256::new()),
+            HashAlgorithm::Blake3 => HasherState::Blake3(Box::default()),
+            HashAlgorithm::Xxh3 => HasherState::Xxh3(Box::default()),
+        };
+        ContentHasher {
+            algorithm: self,
+            state,
+        }
+    }
+}
+
+/// Incremental hash of content read piece by piece
+pub struct ContentHasher {
+    algorithm: HashAlgorithm,
+    state: HasherState,
+}
+
+enum HasherState {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+    Xxh3(Box<xxhash_rust::xxh3::Xxh3>),
+}
+
+impl ContentHasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.state {
+            HasherState::Sha256(hasher) => hasher.update(data),
+            HasherState::Blake3(hasher) if data.len() >= PARALLEL_CHUNK_SIZE => {
+                hasher.update_rayon(data);
+            }
+            HasherState::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            HasherState::Xxh3(hasher) => hasher.update(data),
+        }
+    }
+
+    /// Tagged hex digest
+    pub fn finalize(self) -> String {
+        let digest = match self.state {
+            HasherState::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            HasherState::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+            HasherState::Xxh3(hasher) => format!("{:032x}", hasher.digest128()),
+        };
+        format!("{}:{}", self.algorithm.name(), digest)
+    }
+}
+
+/// Hash the contents of `path`, calling `on_read` with the size of every
+/// chunk read so the caller can throttle
+pub fn hash_file_sync(
+    path: &Path,
+    algorithm: HashAlgorithm,
+    mut on_read: impl FnMut(usize),
+) -> Result<String> {
+    let mut file = File::open(path)?;
+    let parallel =
+        algorithm == HashAlgorithm::Blake3 && file.metadata()?.len() >= PARALLEL_MIN_SIZE;
+    let chunk_size = if parallel {
+        PARALLEL_CHUNK_SIZE
+    } else {
+        CHUNK_SIZE
+    };
+    let mut buffer = vec![0u8; chunk_size];
+    let mut hasher = algorithm.hasher();
+
+    loop {
+        let filled = fill(&mut file, &mut buffer)?;
+        if filled == 0 {
+            break;
+        }
+        hasher.update(&buffer[..filled]);
+        on_read(filled);
+    }
+    Ok(hasher.finalize())
+}
+
+/// Bytes of a `size` byte file read by [`hash_partial_sync`]
+pub fn sampled_bytes(size: u64) -> u64 {
+    size.min(3 * CHUNK_SIZE as u64)
+}
+
+/// Hash of the first, middle and last 64 KB of `path`, the whole file when
+/// smaller than the three blocks
+pub fn hash_partial_sync(path: &Path, algorithm: HashAlgorithm) -> Result<String> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let block = CHUNK_SIZE as u64;
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut hasher = algorithm.hasher();
+
+    if size <= 3 * block {
+        loop {
+            let filled = fill(&mut file, &mut buffer)?;
+            if filled == 0 {
+                break;
+            }
+            hasher.update(&buffer[..filled]);
+        }
+        return Ok(hasher.finalize());
+    }
+    for offset in [0, size / 2 - block / 2, size - block] {
+        file.seek(SeekFrom::Start(offset))?;
+        let filled = fill(&mut file, &mut buffer)?;
+        hasher.update(&buffer[..filled]);
+    }
+    Ok(hasher.finalize())
+}
+
+/// [`hash_file_sync`] on a blocking thread
+pub async fn hash_file(path: PathBuf, algorithm: HashAlgorithm) -> Result<String> {
+    tokio::task::spawn_blocking(move || hash_file_sync(&path, algorithm, |_| {})).await?
+}
+
+/// Read until `buffer` is full or the file ends, returning the bytes read
+fn fill(file: &mut File, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
@@ -0,0 +1,28 @@
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fs;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+/// Read a JSON list from `path`; a missing file holds an empty list
+pub fn read_list<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_reader(BufReader::new(fs::File::open(
+        path,
+    )?))?)
+}
+
+/// Write a JSON list next to the old file, then move it into place
+pub fn write_list<T: Serialize>(path: &Path, items: &[T]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let tmp = path.with_extension("json.tmp");
+    let writer = BufWriter::new(fs::File::create(&tmp)?);
+    serde_json::to_writer_pretty(writer, items)?;
+    fs::rename(tmp, path)?;
+    Ok(())
+}
@@ -1,12 +1,22 @@
 // Library exports for testing and external use
 
+pub mod app_config;
 pub mod app_state;
 pub mod commands;
+pub mod directory_tree;
 pub mod disk_analyzer;
 pub mod error;
 pub mod file_system;
+pub mod file_table;
+pub mod hash_cache;
+pub mod hashing;
+pub mod json_file;
 pub mod logging;
 pub mod mft_scanner;
+pub mod scan_index;
+pub mod scan_profiles;
+pub mod scheduler;
+pub mod watcher;
 pub mod websocket;
 
 #[cfg(test)]
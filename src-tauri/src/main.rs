@@ -5,13 +5,23 @@
 
 use tauri::Manager;
 
+mod app_config;
 mod app_state;
 mod commands;
+mod directory_tree;
 mod disk_analyzer;
 mod error;
 mod file_system;
+mod file_table;
+mod hash_cache;
+mod hashing;
+mod json_file;
 mod logging;
 mod mft_scanner;
+mod scan_index;
+mod scan_profiles;
+mod scheduler;
+mod watcher;
 mod websocket;
 
 #[cfg(test)]
@@ -20,6 +30,45 @@ mod tests;
 use app_state::AppState;
 use std::sync::Arc;
 
+/// Watch every indexed root, and each root as soon as a scan of it completes
+async fn watch_indexed_roots(app_state: Arc<AppState>) {
+    // Subscribe first so no scan completing meanwhile is missed
+    let mut messages = app_state.websocket_manager.subscribe();
+    let roots: Vec<String> = {
+        let storage = app_state.storage.read().await;
+        storage
+            .index
+            .roots()
+            .map(|root| root.record.root.clone())
+            .collect()
+    };
+
+    let watch = |root: String| {
+        let app_state = app_state.clone();
+        async move {
+            let analyzer = app_state.analyzer().await;
+            if let Err(e) = app_state.watcher.watch(&root, analyzer).await {
+                tracing::warn!("Could not watch {}: {}", root, e);
+            }
+        }
+    };
+    for root in roots {
+        watch(root).await;
+    }
+
+    loop {
+        match messages.recv().await {
+            Ok(message) if message.message_type == "scan_results_available" => {
+                if let Some(root) = message.data["root"].as_str() {
+                    watch(root.to_string()).await;
+                }
+            }
+            Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
 fn main() {
     // Inicializar logging antes que nada
     if let Err(e) = logging::init_logging() {
@@ -59,6 +108,16 @@ fn main() {
                 }
             });
 
+            if app_state.config.watcher.enabled {
+                tauri::async_runtime::spawn(watch_indexed_roots(app_state.clone()));
+            }
+
+            if app_state.config.scheduler.enabled {
+                let scheduler = app_state.scheduler.clone();
+                let app_state = app_state.clone();
+                tauri::async_runtime::spawn(async move { scheduler.run(app_state).await });
+            }
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -70,10 +129,33 @@ fn main() {
             // Disk Analyzer commands (new real implementation)
             commands::disk_analyzer_commands::scan_disk_new,
             commands::disk_analyzer_commands::get_scan_progress_new,
+            commands::disk_analyzer_commands::get_scan_errors,
             commands::disk_analyzer_commands::get_disk_statuses,
             commands::disk_analyzer_commands::pause_scan,
             commands::disk_analyzer_commands::resume_scan,
             commands::disk_analyzer_commands::cancel_scan,
+            commands::disk_analyzer_commands::resume_interrupted_sessions,
+            commands::disk_analyzer_commands::get_scan_throttle,
+            commands::disk_analyzer_commands::set_scan_throttle,
+            // Scan profiles
+            commands::scan_profile_commands::list_scan_profiles,
+            commands::scan_profile_commands::save_scan_profile,
+            commands::scan_profile_commands::delete_scan_profile,
+            commands::scan_profile_commands::scan_with_profile,
+            // Scheduled jobs
+            commands::scheduler_commands::list_scheduled_jobs,
+            commands::scheduler_commands::create_scheduled_job,
+            commands::scheduler_commands::pause_scheduled_job,
+            commands::scheduler_commands::run_scheduled_job_now,
+            commands::scheduler_commands::delete_scheduled_job,
+            // Directory size tree and treemap
+            commands::directory_tree_commands::get_directory_tree,
+            commands::directory_tree_commands::get_directory_subtree,
+            commands::directory_tree_commands::get_treemap_layout,
+            // Live index updates
+            commands::watcher_commands::watch_root,
+            commands::watcher_commands::unwatch_root,
+            commands::watcher_commands::get_watch_status,
             // Legacy file commands (keep for compatibility)
             commands::file_commands::get_disk_info,
             commands::file_commands::get_large_files,
@@ -94,6 +176,8 @@ fn main() {
             commands::duplicate_commands::delete_duplicates_batch,
             commands::duplicate_commands::smart_select_duplicates,
             commands::duplicate_commands::preview_duplicate,
+            commands::duplicate_commands::get_hash_cache_status,
+            commands::duplicate_commands::clear_hash_cache,
             // Large files management
             commands::large_files_commands::find_large_files,
             commands::large_files_commands::get_file_space_analysis,
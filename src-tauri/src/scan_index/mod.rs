@@ -0,0 +1,717 @@
+use crate::disk_analyzer::{DuplicateGroup, ScanConfig, ScanResults, ScanSession, ScanType};
+use crate::file_system::{FileInfo, SizeBasis};
+use crate::file_table::{EntryId, FileTable, SubtreeSize};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+const META_FILE: &str = "meta.json";
+const FILES_FILE: &str = "files.jsonl";
+const DUPLICATES_FILE: &str = "duplicates.json";
+const LARGE_FILES_FILE: &str = "large_files.json";
+const DIRECTORIES_FILE: &str = "directories.json";
+const CHANGES_FILE: &str = "changes.jsonl";
+const CURRENT_FILE: &str = "CURRENT"; // Name of the generation directory in use
+
+/// Changes logged on top of a stored root before it is written out in full again
+const MAX_LOGGED_CHANGES: usize = 10_000;
+
+/// Metadata describing the scan that produced an indexed root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanRecord {
+    pub root: String,
+    pub session_id: String,
+    pub scan_type: ScanType,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    pub config: ScanConfig,
+    pub total_files: u64,
+    pub total_size: u64,
+    #[serde(default)]
+    pub total_allocated_size: u64,
+}
+
+impl ScanRecord {
+    /// Build the record for a completed analyzer session
+    pub fn from_session(session: &ScanSession) -> Self {
+        let (total_files, total_size, total_allocated_size) = session
+            .results
+            .as_ref()
+            .map(|r| (r.total_files, r.total_size, r.total_allocated_size))
+            .unwrap_or((0, 0, 0));
+
+        Self {
+            root: session.disk_path.clone(),
+            session_id: session.id.clone(),
+            scan_type: session.scan_type.clone(),
+            started_at: session.started_at.unwrap_or(session.created_at),
+            completed_at: session.completed_at.unwrap_or_else(Utc::now),
+            config: session.config.clone(),
+            total_files,
+            total_size,
+            total_allocated_size,
+        }
+    }
+}
+
+/// Everything the index knows about a single scan root
+#[derive(Debug, Clone)]
+pub struct IndexedRoot {
+    pub record: ScanRecord,
+    pub files: FileTable,
+    pub duplicate_groups: Vec<DuplicateGroup>,
+    pub large_files: Vec<FileInfo>,
+    pub directories: HashMap<String, DateTime<Utc>>,
+    pub ids: HashMap<String, EntryId>, // Files by their stable file id
+    sizes: SubtreeSizes,               // Summed on first use, dropped whenever the files change
+    logged: usize, // Changes logged on disk since the root was last written in full
+}
+
+/// Recursive sizes of every entry of an indexed root, one table per size basis
+#[derive(Debug, Clone, Default)]
+struct SubtreeSizes {
+    apparent: OnceLock<Vec<SubtreeSize>>,
+    allocated: OnceLock<Vec<SubtreeSize>>,
+}
+
+/// A change to apply to an indexed root after its scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FileChange {
+    Upsert(Box<FileInfo>), // Created or modified file
+    Remove(String),        // Deleted file or directory, with everything below it
+}
+
+/// A file that moved, paired up by its stable file id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenamedFile {
+    pub from: String,
+    pub to: String,
+}
+
+/// What applying a batch of [`FileChange`]s changed in an indexed root
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+    pub renamed: Vec<RenamedFile>,
+}
+
+impl IndexChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+            && self.renamed.is_empty()
+    }
+}
+
+impl IndexedRoot {
+    /// Map every file in `files` to its entry by stable file id
+    fn file_ids(files: &FileTable) -> HashMap<String, EntryId> {
+        files
+            .entries()
+            .filter(|file| !file.is_directory())
+            .map(|file| (file.file_id(), file.id()))
+            .collect()
+    }
+
+    /// Recursive size and file count of every entry, indexed by id. Summing
+    /// them walks the whole table, so they are kept until the files change.
+    pub fn subtree_sizes(&self, basis: SizeBasis) -> &[SubtreeSize] {
+        let sizes = match basis {
+            SizeBasis::Apparent => &self.sizes.apparent,
+            SizeBasis::Allocated => &self.sizes.allocated,
+        };
+        sizes.get_or_init(|| self.files.subtree_sizes(basis))
+    }
+
+    /// Bring the root up to date with changes seen after its scan.
+    ///
+    /// Removals are applied first, so a file that shows up again elsewhere
+    /// with the same file id is reported as renamed and keeps its hash. The
+    /// totals, duplicate groups and large files follow the changes: removed
+    /// and modified files leave their duplicate groups (their hash is no
+    /// longer known) and new files only join one at the next deep scan.
+    /// Directory sizes are summed from the table again on their next use.
+    /// Every directory that changed is dropped from `directories`, so
+    /// an incremental rescan lists it again.
+    pub fn apply_changes(&mut self, changes: Vec<FileChange>) -> IndexChanges {
+        let mut result = IndexChanges::default();
+        let mut upserts = Vec::new();
+        let mut gone: HashMap<String, FileInfo> = HashMap::new(); // Removed files by file id
+        let mut removed_dirs: HashSet<String> = HashSet::new(); // Walked directories removed
+        self.sizes = SubtreeSizes::default();
+
+        for change in changes {
+            match change {
+                FileChange::Remove(path) => {
+                    self.forget_parent(&path);
+                    if self.directories.contains_key(&path) {
+                        removed_dirs.insert(path.clone());
+                    }
+                    for file in self.files.remove(Path::new(&path)) {
+                        let id = file.file_id();
+                        self.ids.remove(&id);
+                        gone.insert(id, file);
+                    }
+                }
+                FileChange::Upsert(file) => upserts.push(*file),
+            }
+        }
+        self.forget_directories(&removed_dirs);
+
+        let previous = self.files.upsert_all(&upserts);
+        let mut stale: HashSet<String> = HashSet::new(); // Paths whose content is no longer known
+        let mut moved: HashMap<String, FileInfo> = HashMap::new(); // New location by old path
+        let mut large = Vec::new();
+        for (mut file, previous) in upserts.into_iter().zip(previous) {
+            self.forget_parent(&file.path);
+            match previous {
+                Some(previous) if Self::same_content(&previous, &file) => {
+                    continue; // Only metadata such as permissions changed
+                }
+                Some(previous) => {
+                    self.ids.remove(&previous.file_id());
+                    stale.insert(file.path.clone());
+                    result.modified.push(file.path.clone());
+                }
+                None => match gone.remove(&file.file_id()) {
+                    Some(old) => {
+                        if Self::same_content(&old, &file) {
+                            file.hash = old.hash.clone();
+                            moved.insert(old.path.clone(), file.clone());
+                        } else {
+                            stale.insert(old.path.clone());
+                        }
+                        result.renamed.push(RenamedFile {
+                            from: old.path,
+                            to: file.path.clone(),
+                        });
+                    }
+                    None => result.added.push(file.path.clone()),
+                },
+            }
+
+            if let Some(entry) = self.files.find(Path::new(&file.path)).map(|f| f.id()) {
+                if let Some(hash) = &file.hash {
+                    self.files.set_hash(entry, hash);
+                }
+                self.ids.insert(file.file_id(), entry);
+            }
+            if file.size >= self.record.config.large_file_threshold {
+                large.push(file);
+            }
+        }
+        for (_, file) in gone {
+            stale.insert(file.path.clone());
+            result.removed.push(file.path);
+        }
+        if result.is_empty() {
+            return result;
+        }
+
+        self.update_duplicates(&stale, &moved);
+        let replaced: HashSet<&str> = large.iter().map(|f| f.path.as_str()).collect();
+        self.large_files.retain(|f| {
+            !stale.contains(&f.path)
+                && !moved.contains_key(&f.path)
+                && !replaced.contains(f.path.as_str())
+        });
+        self.large_files.extend(large);
+        self.large_files.sort_by_key(|f| std::cmp::Reverse(f.size));
+
+        self.record.total_files = self.files.len() as u64;
+        self.record.total_size = self.files.total_size(SizeBasis::Apparent);
+        self.record.total_allocated_size = self.files.total_size(SizeBasis::Allocated);
+        result
+    }
+
+    /// Drop stale members from the duplicate groups and follow moved ones
+    fn update_duplicates(&mut self, stale: &HashSet<String>, moved: &HashMap<String, FileInfo>) {
+        let groups = std::mem::take(&mut self.duplicate_groups);
+        self.duplicate_groups = groups
+            .into_iter()
+            .filter_map(|group| {
+                let affected = group
+                    .files
+                    .iter()
+                    .any(|f| stale.contains(&f.path) || moved.contains_key(&f.path));
+                if !affected {
+                    return Some(group);
+                }
+                let files: Vec<_> = group
+                    .files
+                    .into_iter()
+                    .filter(|f| !stale.contains(&f.path))
+                    .map(|f| moved.get(&f.path).cloned().unwrap_or(f))
+                    .collect();
+                (files.len() > 1).then(|| DuplicateGroup::new(group.hash, files))
+            })
+            .collect();
+    }
+
+    fn same_content(a: &FileInfo, b: &FileInfo) -> bool {
+        a.size == b.size && a.modified == b.modified
+    }
+
+    /// Make an incremental rescan list the directory holding `path` again
+    fn forget_parent(&mut self, path: &str) {
+        if let Some(parent) = Path::new(path).parent() {
+            self.directories.remove(parent.to_string_lossy().as_ref());
+        }
+    }
+
+    /// Make an incremental rescan list everything below `removed` again, in
+    /// a single pass however many directories a batch removes
+    fn forget_directories(&mut self, removed: &HashSet<String>) {
+        if removed.is_empty() {
+            return;
+        }
+        self.directories.retain(|dir, _| {
+            !Path::new(dir)
+                .ancestors()
+                .any(|ancestor| removed.contains(ancestor.to_string_lossy().as_ref()))
+        });
+    }
+}
+
+/// On-disk index of scan results keyed by scan root.
+///
+/// Each root lives in its own directory under the index directory, stored as
+/// numbered generations: `CURRENT` names the generation in use, and its
+/// directory holds `meta.json` with the [`ScanRecord`], `files.jsonl` with
+/// one file entry per line, `duplicates.json` and `large_files.json` with the
+/// duplicate groups and large files found by the scan, and `directories.json`
+/// with the modification time of every directory walked, used by incremental
+/// rescans. Writing a root fills and syncs a new generation, then renames a
+/// new `CURRENT` into place, so a crash leaves either the old generation or
+/// the new one in use, never a mix of both.
+///
+/// Changes seen after a scan are appended to `changes.jsonl` in the
+/// generation in use, one batch per line, and replayed when the root is
+/// loaded; once [`MAX_LOGGED_CHANGES`] have piled up the root is written out
+/// in full as a new generation, which starts an empty log.
+#[derive(Debug, Default)]
+pub struct ScanIndex {
+    dir: Option<PathBuf>,
+    roots: HashMap<String, IndexedRoot>,
+    writes: Arc<WriteQueue>,
+}
+
+/// Index writes queued by [`ScanIndex::replace_root`] and [`ScanIndex::update_root`]
+#[must_use = "queued writes only reach the disk once persisted"]
+pub struct PendingWrites(Arc<WriteQueue>);
+
+impl PendingWrites {
+    /// Run the queued writes on a blocking thread, after any still running
+    pub async fn persist(self) {
+        let queue = self.0;
+        if let Err(e) = tokio::task::spawn_blocking(move || queue.run()).await {
+            tracing::error!("Index writes failed to run: {}", e);
+        }
+    }
+}
+
+/// Index writes waiting to run, in the order they were queued
+#[derive(Debug, Default)]
+struct WriteQueue {
+    pending: Mutex<VecDeque<DiskWrite>>,
+    running: Mutex<()>, // Held while writes run, so they run one at a time and in order
+}
+
+impl WriteQueue {
+    fn push(&self, write: DiskWrite) {
+        self.pending.lock().unwrap().push_back(write);
+    }
+
+    /// Run every queued write
+    fn run(&self) {
+        let _running = self.running.lock().unwrap();
+        loop {
+            let Some(write) = self.pending.lock().unwrap().pop_front() else {
+                return;
+            };
+            if let Err(e) = write.run() {
+                tracing::error!("Failed to persist index changes: {}", e);
+            }
+        }
+    }
+}
+
+/// A write to the stored index, queued to run outside the index lock
+#[derive(Debug)]
+enum DiskWrite {
+    Append {
+        root_dir: PathBuf,
+        changes: Vec<FileChange>, // One more batch for the root's change log
+    },
+    Rewrite {
+        root_dir: PathBuf,
+        root: Box<StoredRoot>, // Replaces the stored root and its change log
+    },
+}
+
+/// The parts of an indexed root written to disk; the rest is rebuilt on load
+#[derive(Debug)]
+struct StoredRoot {
+    record: ScanRecord,
+    files: FileTable,
+    duplicate_groups: Vec<DuplicateGroup>,
+    large_files: Vec<FileInfo>,
+    directories: HashMap<String, DateTime<Utc>>,
+}
+
+impl From<&IndexedRoot> for StoredRoot {
+    fn from(root: &IndexedRoot) -> Self {
+        Self {
+            record: root.record.clone(),
+            files: root.files.clone(), // Shared until either side changes
+            duplicate_groups: root.duplicate_groups.clone(),
+            large_files: root.large_files.clone(),
+            directories: root.directories.clone(),
+        }
+    }
+}
+
+impl DiskWrite {
+    fn run(self) -> Result<()> {
+        match self {
+            Self::Append { root_dir, changes } => ScanIndex::append_changes(&root_dir, &changes),
+            Self::Rewrite { root_dir, root } => ScanIndex::write_root(&root_dir, &root),
+        }
+    }
+}
+
+impl ScanIndex {
+    /// Open (or create) the index stored in `dir`, loading every stored root
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let mut roots = HashMap::new();
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+
+            match Self::load_root(&entry.path()) {
+                Ok(Some(root)) => {
+                    roots.insert(root.record.root.clone(), root);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(
+                        "Skipping unreadable index entry {}: {}",
+                        entry.path().display(),
+                        e
+                    );
+                }
+            }
+        }
+
+        tracing::info!(
+            "Scan index loaded: {} root(s) from {}",
+            roots.len(),
+            dir.display()
+        );
+
+        Ok(Self {
+            dir: Some(dir),
+            roots,
+            writes: Arc::default(),
+        })
+    }
+
+    /// Store the results of a scan, replacing whatever was indexed for the same root.
+    ///
+    /// Only the in-memory index is updated here. The root is written out when
+    /// the queued writes from [`Self::pending_writes`] are persisted, so
+    /// callers can release the index lock first; a failure to persist is
+    /// logged, and the results are then lost on restart.
+    pub fn replace_root(&mut self, record: ScanRecord, results: ScanResults) {
+        let root = IndexedRoot {
+            record,
+            ids: IndexedRoot::file_ids(&results.files),
+            files: results.files,
+            duplicate_groups: results.duplicate_groups,
+            large_files: results.large_files,
+            directories: results.directories,
+            sizes: SubtreeSizes::default(),
+            logged: 0,
+        };
+
+        if let Some(dir) = &self.dir {
+            self.writes.push(DiskWrite::Rewrite {
+                root_dir: dir.join(Self::root_key(&root.record.root)),
+                root: Box::new(StoredRoot::from(&root)),
+            });
+        }
+        self.roots.insert(root.record.root.clone(), root);
+    }
+
+    /// Apply changes seen after the scan of `root`, queueing a write if anything changed.
+    ///
+    /// Returns `None` if `root` is not indexed. The changes are logged, or the
+    /// whole root written out once the log is long enough, when the queued
+    /// writes from [`Self::pending_writes`] are persisted, so callers can
+    /// release the index lock first. A failure to persist is logged and
+    /// leaves the in-memory index updated.
+    pub fn update_root(&mut self, root: &str, changes: Vec<FileChange>) -> Option<IndexChanges> {
+        let indexed = self.roots.get_mut(root)?;
+        let count = changes.len();
+        let logged = self.dir.as_ref().map(|_| changes.clone());
+        let result = indexed.apply_changes(changes);
+
+        if let (Some(dir), Some(changes), false) = (&self.dir, logged, result.is_empty()) {
+            let root_dir = dir.join(Self::root_key(root));
+            indexed.logged += count;
+            let write = if indexed.logged > MAX_LOGGED_CHANGES {
+                indexed.logged = 0;
+                DiskWrite::Rewrite {
+                    root_dir,
+                    root: Box::new(StoredRoot::from(&*indexed)),
+                }
+            } else {
+                DiskWrite::Append { root_dir, changes }
+            };
+            self.writes.push(write);
+        }
+        Some(result)
+    }
+
+    /// Writes queued by [`Self::replace_root`] and [`Self::update_root`], to
+    /// persist once the index lock is released
+    pub fn pending_writes(&self) -> PendingWrites {
+        PendingWrites(self.writes.clone())
+    }
+
+    /// Everything indexed for exactly `root`
+    pub fn get(&self, root: &str) -> Option<&IndexedRoot> {
+        self.roots.get(root)
+    }
+
+    /// The indexed root that `path` lies in, the innermost one if roots are nested
+    pub fn root_containing(&self, path: &str) -> Option<&IndexedRoot> {
+        self.roots
+            .values()
+            .filter(|root| Path::new(path).starts_with(&root.record.root))
+            .max_by_key(|root| root.record.root.len())
+    }
+
+    /// The indexed file with the given stable file id
+    pub fn file_by_id(&self, id: &str) -> Option<FileInfo> {
+        self.roots.values().find_map(|root| {
+            let entry = *root.ids.get(id)?;
+            root.files.get(entry).map(|file| file.to_file_info())
+        })
+    }
+
+    /// Iterate over all indexed roots
+    pub fn roots(&self) -> impl Iterator<Item = &IndexedRoot> {
+        self.roots.values()
+    }
+
+    /// Iterate over indexed roots, keeping only those under one of `paths` (all if `None`)
+    pub fn roots_under<'a>(
+        &'a self,
+        paths: Option<&'a [String]>,
+    ) -> impl Iterator<Item = &'a IndexedRoot> + 'a {
+        self.roots.values().filter(move |root| match paths {
+            Some(paths) => paths
+                .iter()
+                .any(|p| Path::new(&root.record.root).starts_with(p)),
+            None => true,
+        })
+    }
+
+    /// Iterate over every indexed file
+    pub fn files(&self) -> impl Iterator<Item = FileInfo> + '_ {
+        self.roots.values().flat_map(|root| root.files.iter())
+    }
+
+    /// Most recent completion time of any scan whose root lies under `path`
+    pub fn last_scan_under(&self, path: &str) -> Option<DateTime<Utc>> {
+        self.roots
+            .values()
+            .filter(|root| Path::new(&root.record.root).starts_with(path))
+            .map(|root| root.record.completed_at)
+            .max()
+    }
+
+    /// Most recent completion time of any indexed scan
+    pub fn last_scan(&self) -> Option<DateTime<Utc>> {
+        self.roots
+            .values()
+            .map(|root| root.record.completed_at)
+            .max()
+    }
+
+    fn root_key(root: &str) -> String {
+        format!("{:x}", md5::compute(root))
+    }
+
+    /// Generation currently stored for a root, `None` until its first write finishes
+    fn current_generation(root_dir: &Path) -> Result<Option<u64>> {
+        match fs::read_to_string(root_dir.join(CURRENT_FILE)) {
+            Ok(current) => Ok(Some(current.trim().parse()?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Load the generation in use for a root, `None` if it has none
+    fn load_root(root_dir: &Path) -> Result<Option<IndexedRoot>> {
+        let Some(generation) = Self::current_generation(root_dir)? else {
+            return Ok(None);
+        };
+        let data_dir = root_dir.join(generation.to_string());
+
+        let record: ScanRecord =
+            serde_json::from_reader(BufReader::new(fs::File::open(data_dir.join(META_FILE))?))?;
+
+        let mut files = FileTable::new();
+        let reader = BufReader::new(fs::File::open(data_dir.join(FILES_FILE))?);
+        for line in reader.lines() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            files.push(&serde_json::from_str(&line)?);
+        }
+
+        let mut root = IndexedRoot {
+            record,
+            ids: IndexedRoot::file_ids(&files),
+            files,
+            duplicate_groups: Self::load_optional(&data_dir.join(DUPLICATES_FILE))?,
+            large_files: Self::load_optional(&data_dir.join(LARGE_FILES_FILE))?,
+            directories: Self::load_optional(&data_dir.join(DIRECTORIES_FILE))?,
+            sizes: SubtreeSizes::default(),
+            logged: 0,
+        };
+        root.logged = Self::replay_changes(&data_dir.join(CHANGES_FILE), &mut root)?;
+        Ok(Some(root))
+    }
+
+    /// Apply the change batches logged for a root, returning how many changes
+    /// the log holds. A batch cut short by a crash ends the log, which then
+    /// counts as full so the next change writes the root out again.
+    fn replay_changes(log: &Path, root: &mut IndexedRoot) -> Result<usize> {
+        let log = match fs::File::open(log) {
+            Ok(log) => log,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut replayed = 0;
+        for line in BufReader::new(log).lines() {
+            let Ok(changes) = serde_json::from_str::<Vec<FileChange>>(&line?) else {
+                return Ok(MAX_LOGGED_CHANGES);
+            };
+            replayed += changes.len();
+            root.apply_changes(changes);
+        }
+        Ok(replayed)
+    }
+
+    /// Append a batch of changes to the log of the generation in use
+    fn append_changes(root_dir: &Path, changes: &[FileChange]) -> Result<()> {
+        let generation = Self::current_generation(root_dir)?
+            .ok_or_else(|| anyhow::anyhow!("No stored generation in {}", root_dir.display()))?;
+        let mut line = serde_json::to_vec(changes)?;
+        line.push(b'\n');
+
+        let mut log = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(root_dir.join(generation.to_string()).join(CHANGES_FILE))?;
+        log.write_all(&line)?;
+        log.sync_data()?;
+        Ok(())
+    }
+
+    fn load_optional<T: serde::de::DeserializeOwned + Default>(path: &Path) -> Result<T> {
+        if path.exists() {
+            Ok(serde_json::from_reader(BufReader::new(fs::File::open(
+                path,
+            )?))?)
+        } else {
+            Ok(T::default())
+        }
+    }
+
+    fn write_root(root_dir: &Path, root: &StoredRoot) -> Result<()> {
+        fs::create_dir_all(root_dir)?;
+
+        // Write the next generation in full, next to the current one
+        let generation = Self::current_generation(root_dir)?.map_or(1, |g| g + 1);
+        let data_dir = root_dir.join(generation.to_string());
+        if data_dir.exists() {
+            fs::remove_dir_all(&data_dir)?; // Left behind by a write that never finished
+        }
+        fs::create_dir(&data_dir)?;
+
+        write_synced(&data_dir.join(FILES_FILE), |writer| {
+            for file in &root.files {
+                serde_json::to_writer(&mut *writer, &file)?;
+                writer.write_all(b"\n")?;
+            }
+            Ok(())
+        })?;
+        write_synced(&data_dir.join(DUPLICATES_FILE), |writer| {
+            Ok(serde_json::to_writer(writer, &root.duplicate_groups)?)
+        })?;
+        write_synced(&data_dir.join(LARGE_FILES_FILE), |writer| {
+            Ok(serde_json::to_writer(writer, &root.large_files)?)
+        })?;
+        write_synced(&data_dir.join(DIRECTORIES_FILE), |writer| {
+            Ok(serde_json::to_writer(writer, &root.directories)?)
+        })?;
+        write_synced(&data_dir.join(META_FILE), |writer| {
+            Ok(serde_json::to_writer_pretty(writer, &root.record)?)
+        })?;
+
+        // Then switch to it with a single rename
+        let current = root_dir.join(format!("{}.tmp", CURRENT_FILE));
+        write_synced(&current, |writer| {
+            Ok(writer.write_all(generation.to_string().as_bytes())?)
+        })?;
+        fs::rename(&current, root_dir.join(CURRENT_FILE))?;
+
+        // Older generations are no longer read
+        for entry in fs::read_dir(root_dir)?.flatten() {
+            let name = entry.file_name();
+            if name == CURRENT_FILE || name == data_dir.file_name().unwrap_or_default() {
+                continue;
+            }
+            let removed = match entry.file_type() {
+                Ok(kind) if kind.is_dir() => fs::remove_dir_all(entry.path()),
+                _ => fs::remove_file(entry.path()),
+            };
+            if let Err(e) = removed {
+                tracing::warn!("Failed to remove {}: {}", entry.path().display(), e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Write `path` through a buffer and sync it to disk before returning
+pub fn write_synced(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<fs::File>) -> Result<()>,
+) -> Result<()> {
+    let mut writer = BufWriter::new(fs::File::create(path)?);
+    write(&mut writer)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    Ok(())
+}
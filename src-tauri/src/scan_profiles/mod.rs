@@ -0,0 +1,259 @@
+use crate::disk_analyzer::exclusion::ExclusionRules;
+use crate::disk_analyzer::{DuplicateStrategy, ScanConfig, ScanType};
+use crate::json_file;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Name of the profile file inside the data directory
+pub const PROFILES_FILE: &str = "scan_profiles.json";
+
+pub const HOME_QUICK: &str = "home_quick";
+pub const MEDIA_DEEP: &str = "media_deep";
+pub const DEVELOPER_CACHES: &str = "developer_caches";
+
+const KB: u64 = 1024;
+const MB: u64 = 1024 * KB;
+
+/// A named set of roots and scan settings that can be run again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanProfile {
+    #[serde(default)]
+    pub id: String, // Assigned when a new profile is first saved
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub builtin: bool, // Shipped preset; copy it to change it
+    pub roots: Vec<String>,
+    pub scan_type: ScanType,
+    pub config: ScanConfig, // Exclusions, size window, depth, hashing and thresholds
+}
+
+/// System locations not worth scanning on this platform, anchored to the
+/// scan root so they only apply when a whole volume is scanned
+pub fn default_exclusions() -> Vec<String> {
+    #[cfg(target_os = "windows")]
+    let patterns = [
+        "/System Volume Information",
+        "/$Recycle.Bin",
+        "/Windows",
+        "/Program Files",
+        "/Program Files (x86)",
+        "/ProgramData",
+        "AppData",
+    ]
+    .as_slice();
+
+    #[cfg(target_os = "macos")]
+    let patterns = [
+        "/System",
+        "/private/var/vm",
+        "/Volumes",
+        "/dev",
+        ".Spotlight-V100",
+        ".fseventsd",
+        ".Trashes",
+    ]
+    .as_slice();
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let patterns = [
+        "/proc",
+        "/sys",
+        "/dev",
+        "/run",
+        "/snap",
+        "/var/lib/docker",
+        "lost+found",
+    ]
+    .as_slice();
+
+    patterns.iter().map(|p| p.to_string()).collect()
+}
+
+/// Settings of a scan started without a profile
+pub fn default_config() -> ScanConfig {
+    let mut exclude_patterns = default_exclusions();
+    exclude_patterns.extend(["node_modules".to_string(), ".git".to_string()]);
+
+    ScanConfig {
+        exclude_patterns,
+        include_hidden: false,
+        follow_symlinks: false,
+        max_depth: None,
+        min_file_size: None,
+        max_file_size: None,
+        calculate_hashes: true,
+        quick_hash_threshold: MB,
+        duplicate_strategy: DuplicateStrategy::SmartDetection,
+        large_file_threshold: 100 * MB,
+        same_filesystem: false,
+        use_ignore_files: false,
+    }
+}
+
+/// Home directory of the current user
+fn home_directory() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let home = std::env::var_os("USERPROFILE");
+    #[cfg(not(target_os = "windows"))]
+    let home = std::env::var_os("HOME");
+    home.map(PathBuf::from)
+}
+
+/// Presets shipped with the app, with roots under the current user's home
+pub fn builtin_profiles() -> Vec<ScanProfile> {
+    let home = home_directory();
+    let home_root: Vec<String> = home
+        .iter()
+        .map(|home| home.to_string_lossy().to_string())
+        .collect();
+    let under_home = |dirs: &[&str]| -> Vec<String> {
+        home.iter()
+            .flat_map(|home| dirs.iter().map(move |dir| home.join(dir)))
+            .map(|dir| dir.to_string_lossy().to_string())
+            .collect()
+    };
+    #[cfg(target_os = "macos")]
+    let media = ["Pictures", "Movies", "Music"];
+    #[cfg(not(target_os = "macos"))]
+    let media = ["Pictures", "Videos", "Music"];
+
+    let mut home_quick = default_config();
+    home_quick.exclude_patterns.push(".cache".to_string());
+    home_quick.calculate_hashes = false;
+    home_quick.duplicate_strategy = DuplicateStrategy::NameAndSize;
+    home_quick.same_filesystem = true;
+
+    let mut media_deep = default_config();
+    media_deep.min_file_size = Some(100 * KB); // Thumbnails and sidecar files
+    media_deep.duplicate_strategy = DuplicateStrategy::HashOnly;
+    media_deep.large_file_threshold = 500 * MB;
+
+    // Caches are what this looks for, so only system locations are excluded
+    let mut developer_caches = default_config();
+    developer_caches.exclude_patterns = default_exclusions();
+    developer_caches.include_hidden = true;
+    developer_caches.calculate_hashes = false;
+    developer_caches.same_filesystem = true;
+
+    vec![
+        ScanProfile {
+            id: HOME_QUICK.to_string(),
+            name: "Home quick".to_string(),
+            description: "Sizes of everything in your home folder, without hashing".to_string(),
+            builtin: true,
+            roots: home_root.clone(),
+            scan_type: ScanType::Quick,
+            config: home_quick,
+        },
+        ScanProfile {
+            id: MEDIA_DEEP.to_string(),
+            name: "Media deep".to_string(),
+            description: "Pictures, videos and music, hashed to find exact duplicates".to_string(),
+            builtin: true,
+            roots: under_home(&media),
+            scan_type: ScanType::Deep,
+            config: media_deep,
+        },
+        ScanProfile {
+            id: DEVELOPER_CACHES.to_string(),
+            name: "Developer caches".to_string(),
+            description: "Your home folder including node_modules, build output and hidden caches"
+                .to_string(),
+            builtin: true,
+            roots: home_root,
+            scan_type: ScanType::Quick,
+            config: developer_caches,
+        },
+    ]
+}
+
+/// User scan profiles, stored as one JSON file next to the scan index.
+///
+/// The built-in presets are listed with them but never stored, so they
+/// follow the platform defaults of the running version.
+#[derive(Debug, Default)]
+pub struct ProfileStore {
+    path: Option<PathBuf>,
+    profiles: Vec<ScanProfile>,
+}
+
+impl ProfileStore {
+    /// Open the profiles stored in `path`; a missing file holds no profiles
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let profiles = json_file::read_list(&path)?;
+
+        Ok(Self {
+            path: Some(path),
+            profiles,
+        })
+    }
+
+    /// The built-in presets followed by the user's profiles, sorted by name
+    pub fn list(&self) -> Vec<ScanProfile> {
+        let mut profiles = self.profiles.clone();
+        profiles.sort_by_key(|profile| profile.name.to_lowercase());
+        builtin_profiles().into_iter().chain(profiles).collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<ScanProfile> {
+        builtin_profiles()
+            .into_iter()
+            .chain(self.profiles.iter().cloned())
+            .find(|profile| profile.id == id)
+    }
+
+    /// Create or update a profile, returning it as stored
+    pub fn save(&mut self, mut profile: ScanProfile) -> Result<ScanProfile> {
+        if builtin_profiles().iter().any(|p| p.id == profile.id) {
+            anyhow::bail!("Built-in profile '{}' cannot be changed", profile.name);
+        }
+        profile.name = profile.name.trim().to_string();
+        if profile.name.is_empty() {
+            anyhow::bail!("Profile name cannot be empty");
+        }
+        if profile.roots.is_empty() {
+            anyhow::bail!("Profile '{}' has no roots to scan", profile.name);
+        }
+        for root in &profile.roots {
+            ExclusionRules::new(Path::new(root), &profile.config)?;
+        }
+
+        profile.builtin = false;
+        if profile.id.is_empty() {
+            profile.id = uuid::Uuid::new_v4().to_string();
+        }
+        match self.profiles.iter_mut().find(|p| p.id == profile.id) {
+            Some(existing) => *existing = profile.clone(),
+            None => self.profiles.push(profile.clone()),
+        }
+
+        self.write()?;
+        Ok(profile)
+    }
+
+    /// Delete a profile, returning whether it existed
+    pub fn delete(&mut self, id: &str) -> Result<bool> {
+        if builtin_profiles().iter().any(|p| p.id == id) {
+            anyhow::bail!("Built-in profiles cannot be deleted");
+        }
+        let count = self.profiles.len();
+        self.profiles.retain(|profile| profile.id != id);
+        if self.profiles.len() == count {
+            return Ok(false);
+        }
+
+        self.write()?;
+        Ok(true)
+    }
+
+    fn write(&self) -> Result<()> {
+        match &self.path {
+            Some(path) => json_file::write_list(path, &self.profiles),
+            None => Ok(()),
+        }
+    }
+}
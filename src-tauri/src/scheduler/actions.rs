@@ -0,0 +1,167 @@
+use super::{CleanupPolicy, JobAction};
+use crate::app_state::AppState;
+use crate::disk_analyzer::{ScanConfig, ScanType};
+use crate::file_system::path_encoding;
+use crate::scan_index::FileChange;
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Run a job's action to completion, returning a summary of what it did
+pub async fn run(app_state: &Arc<AppState>, action: &JobAction) -> Result<String> {
+    match action {
+        JobAction::ScanProfile { profile_id } => scan_profile(app_state, profile_id).await,
+        JobAction::RefreshDuplicates { roots } => refresh_duplicates(app_state, roots).await,
+        JobAction::Cleanup(policy) => cleanup(app_state, policy).await,
+    }
+}
+
+/// Scan one root and wait for its results to reach the index
+async fn scan(
+    app_state: &Arc<AppState>,
+    root: &str,
+    scan_type: ScanType,
+    config: ScanConfig,
+) -> Result<()> {
+    let analyzer = app_state.analyzer().await;
+    let session_id = analyzer
+        .create_scan_session(root.to_string(), scan_type, config)
+        .await?;
+    analyzer.start_scan_session(&session_id).await
+}
+
+async fn scan_profile(app_state: &Arc<AppState>, profile_id: &str) -> Result<String> {
+    let profile = app_state
+        .storage
+        .read()
+        .await
+        .profiles
+        .get(profile_id)
+        .ok_or_else(|| anyhow!("Scan profile not found: {}", profile_id))?;
+
+    for root in &profile.roots {
+        scan(
+            app_state,
+            root,
+            profile.scan_type.clone(),
+            profile.config.clone(),
+        )
+        .await
+        .map_err(|e| anyhow!("Scan of {} failed: {}", root, e))?;
+    }
+    Ok(format!(
+        "Scanned {} root(s) of {}",
+        profile.roots.len(),
+        profile.name
+    ))
+}
+
+/// Rescan indexed roots incrementally with hashing on, so their duplicate
+/// groups are rebuilt from current contents
+async fn refresh_duplicates(app_state: &Arc<AppState>, roots: &[String]) -> Result<String> {
+    let records: Vec<_> = {
+        let storage = app_state.storage.read().await;
+        let paths = (!roots.is_empty()).then_some(roots);
+        storage
+            .index
+            .roots_under(paths)
+            .map(|root| root.record.clone())
+            .collect()
+    };
+    if records.is_empty() {
+        anyhow::bail!("No scanned roots to refresh");
+    }
+
+    for record in &records {
+        let config = ScanConfig {
+            calculate_hashes: true,
+            ..record.config.clone()
+        };
+        scan(app_state, &record.root, ScanType::Incremental, config)
+            .await
+            .map_err(|e| anyhow!("Scan of {} failed: {}", record.root, e))?;
+    }
+
+    let groups: usize = {
+        let storage = app_state.storage.read().await;
+        records
+            .iter()
+            .filter_map(|record| storage.index.get(&record.root))
+            .map(|root| root.duplicate_groups.len())
+            .sum()
+    };
+    Ok(format!(
+        "{} duplicate group(s) in {} root(s)",
+        groups,
+        records.len()
+    ))
+}
+
+/// Delete the indexed files the policy matches, and drop them from the index.
+///
+/// Files changed since they were scanned are left alone.
+async fn cleanup(app_state: &Arc<AppState>, policy: &CleanupPolicy) -> Result<String> {
+    let rules = policy.rules()?;
+    let cutoff = Utc::now() - Duration::days(policy.older_than_days as i64);
+
+    let files: Vec<_> = app_state
+        .storage
+        .read()
+        .await
+        .index
+        .files()
+        .filter(|file| !file.is_directory && file.modified < cutoff)
+        .filter(|file| {
+            let path = path_encoding::decode_path(&file.path);
+            path.starts_with(&policy.root) && rules.is_excluded_with_parents(&path, false)
+        })
+        .collect();
+
+    let mut deleted: Vec<String> = Vec::new();
+    let mut freed = 0u64;
+    let mut failed = 0usize;
+    for file in &files {
+        match crate::file_system::delete_indexed_file(file, policy.move_to_trash).await {
+            Ok(size) => {
+                deleted.push(file.path.clone());
+                freed += size;
+            }
+            Err(e) => {
+                tracing::warn!("Cleanup left {} in place: {}", file.path, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if !deleted.is_empty() {
+        let mut storage = app_state.storage.write().await;
+        let mut by_root: HashMap<String, Vec<FileChange>> = HashMap::new();
+        for path in deleted.iter() {
+            if let Some(root) = storage.index.root_containing(path) {
+                by_root
+                    .entry(root.record.root.clone())
+                    .or_default()
+                    .push(FileChange::Remove(path.clone()));
+            }
+        }
+        for (root, changes) in by_root {
+            storage.index.update_root(&root, changes);
+        }
+        let writes = storage.index.pending_writes();
+        drop(storage);
+        writes.persist().await;
+    }
+
+    let mut message = format!(
+        "Deleted {} file(s) under {}, {} bytes freed",
+        deleted.len(),
+        Path::new(&policy.root).display(),
+        freed
+    );
+    if failed > 0 {
+        message.push_str(&format!(", {} skipped", failed));
+    }
+    Ok(message)
+}
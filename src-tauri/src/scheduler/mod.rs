@@ -0,0 +1,550 @@
+use crate::app_config::SchedulerConfig;
+use crate::app_state::AppState;
+use crate::commands::home_commands::{log_activity, ActivityMetadata, ActivityType};
+use crate::disk_analyzer::exclusion::ExclusionRules;
+use crate::json_file;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+
+pub mod actions;
+
+/// Name of the schedule file inside the data directory
+pub const SCHEDULES_FILE: &str = "schedules.json";
+
+/// Shortest interval a job can repeat at
+pub const MIN_INTERVAL_SECS: u64 = 60;
+
+/// When a job runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobSchedule {
+    Interval { every_secs: u64 },
+    Cron { expression: String }, // minute hour day month weekday, in local time
+}
+
+impl JobSchedule {
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Self::Interval { every_secs } if *every_secs < MIN_INTERVAL_SECS => Err(anyhow!(
+                "Jobs cannot repeat more often than every {} seconds",
+                MIN_INTERVAL_SECS
+            )),
+            Self::Interval { .. } => Ok(()),
+            Self::Cron { expression } => parse_cron(expression).map(|_| ()),
+        }
+    }
+
+    /// First time after `after` the job is due, before jitter
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Interval { every_secs } => Some(after + Duration::seconds(*every_secs as i64)),
+            Self::Cron { expression } => parse_cron(expression)
+                .ok()?
+                .after(&after.with_timezone(&Local))
+                .next()
+                .map(|time| time.with_timezone(&Utc)),
+        }
+    }
+}
+
+/// Parse a five field cron expression. Weekdays are best given by name,
+/// as the cron crate numbers them from 1 (Sunday).
+fn parse_cron(expression: &str) -> Result<cron::Schedule> {
+    if expression.split_whitespace().count() != 5 {
+        anyhow::bail!(
+            "Cron expression '{}' needs five fields: minute hour day month weekday",
+            expression
+        );
+    }
+    cron::Schedule::from_str(&format!("0 {}", expression))
+        .map_err(|e| anyhow!("Invalid cron expression '{}': {}", expression, e))
+}
+
+/// What a job does when it runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobAction {
+    ScanProfile {
+        profile_id: String,
+    },
+    RefreshDuplicates {
+        #[serde(default)]
+        roots: Vec<String>, // Every indexed root when empty
+    },
+    Cleanup(CleanupPolicy),
+}
+
+impl JobAction {
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Self::ScanProfile { profile_id } if profile_id.is_empty() => {
+                anyhow::bail!("No scan profile given")
+            }
+            Self::Cleanup(policy) => policy.rules().map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Indexed files under a root to delete once they are old enough
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanupPolicy {
+    pub root: String,
+    pub patterns: Vec<String>, // Gitignore-style patterns of the files to delete
+    pub older_than_days: u32,  // Only files not modified for this many days
+    #[serde(default)]
+    pub move_to_trash: bool,
+}
+
+impl CleanupPolicy {
+    /// Matcher for the policy's patterns, anchored at its root
+    pub fn rules(&self) -> Result<ExclusionRules> {
+        if self.root.is_empty() {
+            anyhow::bail!("Cleanup policy has no root");
+        }
+        if self.patterns.is_empty() {
+            anyhow::bail!("Cleanup policy for {} has no file patterns", self.root);
+        }
+        let config = crate::disk_analyzer::ScanConfig {
+            exclude_patterns: self.patterns.clone(),
+            ..crate::scan_profiles::default_config()
+        };
+        ExclusionRules::new(Path::new(&self.root), &config)
+    }
+}
+
+/// What to do with runs that came due while the app was closed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRunPolicy {
+    Skip,
+    #[default]
+    RunOnce, // However many were missed
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobTrigger {
+    Scheduled,
+    Missed,
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobRunStatus {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+/// One run of a job, kept in its history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRun {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub trigger: JobTrigger,
+    pub status: JobRunStatus,
+    pub message: String,
+}
+
+/// A recurring job and its recent runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    #[serde(default)]
+    pub id: String, // Assigned when the job is created
+    pub name: String,
+    pub schedule: JobSchedule,
+    pub action: JobAction,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub missed_runs: MissedRunPolicy,
+    #[serde(default)]
+    pub jitter_secs: u64, // Up to this much random delay on every run
+    #[serde(default)]
+    pub next_run: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub history: Vec<JobRun>, // Most recent first
+    #[serde(default, skip_deserializing)]
+    pub running: bool, // Only set in listings
+}
+
+impl ScheduledJob {
+    /// Next due time after `after`, with a random delay of up to `jitter_secs`
+    fn plan_next(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let jitter = match self.jitter_secs {
+            0 => 0,
+            max => fastrand::u64(0..=max),
+        };
+        self.schedule
+            .next_after(after)
+            .map(|next| next + Duration::seconds(jitter as i64))
+    }
+}
+
+/// Scheduled jobs, stored as one JSON file next to the scan index
+#[derive(Debug, Default)]
+pub struct JobStore {
+    path: Option<PathBuf>,
+    jobs: Vec<ScheduledJob>,
+}
+
+impl JobStore {
+    /// Open the jobs stored in `path`; a missing file holds no jobs
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let jobs = json_file::read_list(&path)?;
+
+        Ok(Self {
+            path: Some(path),
+            jobs,
+        })
+    }
+
+    /// Jobs in the order they were created
+    pub fn list(&self) -> Vec<ScheduledJob> {
+        self.jobs.clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<ScheduledJob> {
+        self.jobs.iter().find(|job| job.id == id).cloned()
+    }
+
+    /// Add a job, first due one period after `now`
+    pub fn create(&mut self, mut job: ScheduledJob, now: DateTime<Utc>) -> Result<ScheduledJob> {
+        job.name = job.name.trim().to_string();
+        if job.name.is_empty() {
+            anyhow::bail!("Job name cannot be empty");
+        }
+        job.schedule.validate()?;
+        job.action.validate()?;
+
+        job.id = uuid::Uuid::new_v4().to_string();
+        job.history.clear();
+        job.running = false;
+        job.next_run = job.plan_next(now);
+        self.jobs.push(job.clone());
+
+        self.write()?;
+        Ok(job)
+    }
+
+    /// Pause or resume a job; a resumed job is next due one period after `now`
+    pub fn set_paused(
+        &mut self,
+        id: &str,
+        paused: bool,
+        now: DateTime<Utc>,
+    ) -> Result<ScheduledJob> {
+        let job = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or_else(|| anyhow!("Scheduled job not found: {}", id))?;
+        if job.paused && !paused {
+            job.next_run = job.plan_next(now);
+        }
+        job.paused = paused;
+        let job = job.clone();
+
+        self.write()?;
+        Ok(job)
+    }
+
+    /// Delete a job, returning whether it existed
+    pub fn delete(&mut self, id: &str) -> Result<bool> {
+        let count = self.jobs.len();
+        self.jobs.retain(|job| job.id != id);
+        if self.jobs.len() == count {
+            return Ok(false);
+        }
+
+        self.write()?;
+        Ok(true)
+    }
+
+    /// Add a run to the front of a job's history, keeping at most `limit` runs
+    pub fn record_run(&mut self, id: &str, run: JobRun, limit: usize) -> Result<()> {
+        // The job may have been deleted while it ran
+        let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) else {
+            return Ok(());
+        };
+        job.history.insert(0, run);
+        job.history.truncate(limit.max(1));
+        self.write()
+    }
+
+    /// Take the jobs due at `now` and plan their next run.
+    ///
+    /// A run more than `grace` late was missed while the app was closed, and
+    /// runs or is skipped by the job's missed run policy. Jobs in `running`
+    /// skip this run. Skipped runs are recorded in the history and returned
+    /// with the jobs to start.
+    pub fn take_due(
+        &mut self,
+        now: DateTime<Utc>,
+        grace: Duration,
+        running: &HashSet<String>,
+        history_limit: usize,
+    ) -> Result<DueJobs> {
+        let mut due = DueJobs::default();
+
+        for job in self.jobs.iter_mut().filter(|job| !job.paused) {
+            let Some(next_run) = job.next_run.filter(|next_run| *next_run <= now) else {
+                continue;
+            };
+            let missed = now - next_run > grace;
+            job.next_run = job.plan_next(now);
+
+            let skipped = match (running.contains(&job.id), missed, job.missed_runs) {
+                (true, _, _) => Some("Still running from the previous run".to_string()),
+                (false, true, MissedRunPolicy::Skip) => Some(format!(
+                    "Run due at {} was missed",
+                    next_run.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+                )),
+                _ => None,
+            };
+            match skipped {
+                Some(message) => {
+                    let run = JobRun {
+                        started_at: now,
+                        finished_at: now,
+                        trigger: match missed {
+                            true => JobTrigger::Missed,
+                            false => JobTrigger::Scheduled,
+                        },
+                        status: JobRunStatus::Skipped,
+                        message,
+                    };
+                    job.history.insert(0, run.clone());
+                    job.history.truncate(history_limit.max(1));
+                    due.skipped.push((job.clone(), run));
+                }
+                None if missed => due.run.push((job.clone(), JobTrigger::Missed)),
+                None => due.run.push((job.clone(), JobTrigger::Scheduled)),
+            }
+        }
+
+        if !due.run.is_empty() || !due.skipped.is_empty() {
+            self.write()?;
+        }
+        Ok(due)
+    }
+
+    fn write(&self) -> Result<()> {
+        match &self.path {
+            Some(path) => json_file::write_list(path, &self.jobs),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Jobs that came due in one check
+#[derive(Debug, Default)]
+pub struct DueJobs {
+    pub run: Vec<(ScheduledJob, JobTrigger)>,
+    pub skipped: Vec<(ScheduledJob, JobRun)>,
+}
+
+/// Runs due jobs while the app is open.
+///
+/// Jobs run as their own tasks, at most `max_concurrent_jobs` at a time and
+/// never two runs of the same job at once. The scheduler takes the app state
+/// on every call rather than holding it, as the state owns the scheduler.
+#[derive(Clone)]
+pub struct Scheduler {
+    config: SchedulerConfig,
+    permits: Arc<Semaphore>,
+    running: Arc<Mutex<HashSet<String>>>, // Ids of jobs started and not yet finished
+}
+
+/// A job marked as running. Dropping it marks the job idle again, so a run
+/// that panics or is aborted cannot leave its job stuck
+struct Claim {
+    running: Arc<Mutex<HashSet<String>>>,
+    id: String,
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        running.remove(&self.id);
+    }
+}
+
+impl Scheduler {
+    pub fn new(config: SchedulerConfig) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(config.max_concurrent_jobs.max(1))),
+            config,
+            running: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    pub fn is_running(&self, id: &str) -> bool {
+        self.running.lock().unwrap().contains(id)
+    }
+
+    /// Check for due jobs every `check_interval_secs`, for as long as the app runs
+    pub async fn run(&self, app_state: Arc<AppState>) {
+        let period = std::time::Duration::from_secs(self.config.check_interval_secs.max(1));
+        let mut ticker = tokio::time::interval(period);
+        loop {
+            ticker.tick().await;
+            self.run_due(&app_state, Utc::now()).await;
+        }
+    }
+
+    /// Start the jobs due at `now`, returning their tasks
+    pub async fn run_due(
+        &self,
+        app_state: &Arc<AppState>,
+        now: DateTime<Utc>,
+    ) -> Vec<JoinHandle<JobRun>> {
+        // Checks can run late by up to one interval without a run counting as missed
+        let grace = Duration::seconds(2 * self.config.check_interval_secs.max(1) as i64);
+        let running = self.running.lock().unwrap().clone();
+        let due = app_state.storage.write().await.schedules.take_due(
+            now,
+            grace,
+            &running,
+            self.config.history_limit,
+        );
+        let due = match due {
+            Ok(due) => due,
+            Err(e) => {
+                tracing::error!("Failed to update scheduled jobs: {}", e);
+                return Vec::new();
+            }
+        };
+
+        for (job, run) in &due.skipped {
+            log_run(app_state, job, run).await;
+        }
+        due.run
+            .into_iter()
+            .filter_map(|(job, trigger)| {
+                let claim = self.claim(&job.id)?;
+                Some(self.spawn(app_state.clone(), job, trigger, claim))
+            })
+            .collect()
+    }
+
+    /// Start a job right away, outside its schedule
+    pub async fn run_now(&self, app_state: &Arc<AppState>, id: &str) -> Result<JoinHandle<JobRun>> {
+        let job = app_state
+            .storage
+            .read()
+            .await
+            .schedules
+            .get(id)
+            .ok_or_else(|| anyhow!("Scheduled job not found: {}", id))?;
+        let Some(claim) = self.claim(id) else {
+            anyhow::bail!("Job '{}' is already running", job.name);
+        };
+        Ok(self.spawn(app_state.clone(), job, JobTrigger::Manual, claim))
+    }
+
+    /// Mark a job as running, unless it already is
+    fn claim(&self, id: &str) -> Option<Claim> {
+        self.running
+            .lock()
+            .unwrap()
+            .insert(id.to_string())
+            .then(|| Claim {
+                running: self.running.clone(),
+                id: id.to_string(),
+            })
+    }
+
+    fn spawn(
+        &self,
+        app_state: Arc<AppState>,
+        job: ScheduledJob,
+        trigger: JobTrigger,
+        claim: Claim,
+    ) -> JoinHandle<JobRun> {
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            let _claim = claim;
+            scheduler.execute(&app_state, &job, trigger).await
+        })
+    }
+
+    async fn execute(
+        &self,
+        app_state: &Arc<AppState>,
+        job: &ScheduledJob,
+        trigger: JobTrigger,
+    ) -> JobRun {
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("semaphore is never closed");
+
+        let started_at = Utc::now();
+        let result = actions::run(app_state, &job.action).await;
+        let (status, message) = match result {
+            Ok(message) => (JobRunStatus::Succeeded, message),
+            Err(e) => (JobRunStatus::Failed, e.to_string()),
+        };
+        let run = JobRun {
+            started_at,
+            finished_at: Utc::now(),
+            trigger,
+            status,
+            message,
+        };
+
+        if let Err(e) = app_state.storage.write().await.schedules.record_run(
+            &job.id,
+            run.clone(),
+            self.config.history_limit,
+        ) {
+            tracing::error!("Failed to record run of job {}: {}", job.name, e);
+        }
+        log_run(app_state, job, &run).await;
+        let _ = app_state
+            .websocket_manager
+            .broadcast_message(
+                "scheduled_job_finished".to_string(),
+                serde_json::json!({ "job_id": job.id, "run": run }),
+            )
+            .await;
+        run
+    }
+}
+
+/// Add a run to the activity log
+async fn log_run(app_state: &Arc<AppState>, job: &ScheduledJob, run: &JobRun) {
+    let status = match run.status {
+        JobRunStatus::Succeeded => "completed",
+        JobRunStatus::Failed => "error",
+        JobRunStatus::Skipped => "skipped",
+    };
+    let duration = (run.finished_at - run.started_at).num_seconds().max(0) as u32;
+
+    log_activity(
+        app_state,
+        format!("Tarea programada: {}", job.name),
+        run.message.clone(),
+        ActivityType::ScheduledJobRun,
+        status.to_string(),
+        Some(ActivityMetadata {
+            size: None,
+            count: None,
+            duration: Some(duration),
+            error: (run.status == JobRunStatus::Failed).then(|| run.message.clone()),
+        }),
+    )
+    .await;
+}
@@ -0,0 +1,222 @@
+#[cfg(test)]
+mod tests {
+    use crate::app_state::SimpleStorage;
+    use crate::disk_analyzer::checkpoint::{CheckpointStore, Checkpointer, ScanCheckpoint};
+    use crate::disk_analyzer::{DiskAnalyzer, ScanType};
+    use crate::file_system::{get_file_info, FileInfo};
+    use crate::file_table::FileTable;
+    use crate::tests::hashing_config;
+    use crate::websocket::WebSocketManager;
+    use chrono::{DateTime, Utc};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use tokio::sync::RwLock;
+
+    fn test_checkpoint(session_id: &str, root: &str, phase: &str) -> ScanCheckpoint {
+        ScanCheckpoint {
+            session_id: session_id.to_string(),
+            disk_path: root.to_string(),
+            scan_type: ScanType::Deep,
+            config: hashing_config(),
+            created_at: Utc::now(),
+            phase: phase.to_string(),
+            saved_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = CheckpointStore::open(temp_dir.path()).unwrap();
+
+        let file_path = temp_dir.path().join("file.bin");
+        std::fs::write(&file_path, b"content").unwrap();
+        let mut file = get_file_info(&file_path.to_string_lossy()).await.unwrap();
+        file.hash = Some("abc".to_string());
+
+        let mut directories = HashMap::new();
+        directories.insert("/data/sub".to_string(), Utc::now());
+        store
+            .save(
+                &test_checkpoint("session-1", "/data", "deep"),
+                &directories,
+                &FileTable::from_iter([file]),
+            )
+            .unwrap();
+
+        let interrupted = store.load_all();
+        assert_eq!(interrupted.len(), 1);
+        assert_eq!(interrupted[0].checkpoint.phase, "deep");
+        assert_eq!(interrupted[0].directories.len(), 1);
+        let restored = interrupted[0].files.iter().next().unwrap();
+        assert_eq!(restored.hash.as_deref(), Some("abc"));
+
+        store.remove("session-1").unwrap();
+        assert!(store.load_all().is_empty());
+    }
+
+    #[test]
+    fn test_unfinished_saves_keep_the_previous_checkpoint() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = CheckpointStore::open(temp_dir.path()).unwrap();
+        let file = |path: &str| {
+            FileTable::from_iter([FileInfo {
+                path: path.to_string(),
+                name: path.rsplit('/').next().unwrap().to_string(),
+                size: 1,
+                modified: Utc::now(),
+                created: Utc::now(),
+                is_directory: false,
+                extension: None,
+                hash: None,
+                inode: None,
+                device: None,
+                links: None,
+                allocated_size: None,
+                generation: None,
+                display_path: None,
+            }])
+        };
+        store
+            .save(
+                &test_checkpoint("session-1", "/data", "quick"),
+                &HashMap::new(),
+                &file("/data/first.bin"),
+            )
+            .unwrap();
+
+        // A crash while saving leaves a partial segment and manifest behind
+        let session_dir = temp_dir.path().join("session-1");
+        std::fs::write(session_dir.join("segment-2.jsonl"), b"{\"file\":").unwrap();
+        std::fs::write(session_dir.join("checkpoint.json.tmp"), b"{").unwrap();
+        let interrupted = store.load_all();
+        assert_eq!(interrupted.len(), 1);
+        assert_eq!(interrupted[0].files.to_vec()[0].path, "/data/first.bin");
+
+        store
+            .save(
+                &test_checkpoint("session-1", "/data", "deep"),
+                &HashMap::new(),
+                &file("/data/second.bin"),
+            )
+            .unwrap();
+        let interrupted = store.load_all();
+        assert_eq!(interrupted[0].checkpoint.phase, "deep");
+        assert_eq!(interrupted[0].files.to_vec()[0].path, "/data/second.bin");
+        let mut names: Vec<_> = std::fs::read_dir(&session_dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["checkpoint.json", "segment-2.jsonl"]);
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_are_written_in_the_background() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = CheckpointStore::open(temp_dir.path().join("checkpoints")).unwrap();
+        std::fs::write(temp_dir.path().join("a.bin"), b"a").unwrap();
+        let analyzer = DiskAnalyzer::new(Arc::new(WebSocketManager::new()));
+        let session_id = analyzer
+            .create_scan_session(
+                temp_dir.path().to_string_lossy().to_string(),
+                ScanType::Quick,
+                hashing_config(),
+            )
+            .await
+            .unwrap();
+        let session = analyzer.get_scan_session(&session_id).await.unwrap();
+
+        let mut files = FileTable::new();
+        let a = get_file_info(&temp_dir.path().join("a.bin").to_string_lossy())
+            .await
+            .unwrap();
+        let mut checkpointer = Checkpointer::new(store.clone(), &session);
+        checkpointer.file_added(files.push(&a));
+        checkpointer.save("quick", &HashMap::new(), &files);
+
+        // Saving returned right away; the write lands shortly after
+        let mut waited = Duration::ZERO;
+        while store.load_all().is_empty() && waited < Duration::from_secs(5) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            waited += Duration::from_millis(10);
+        }
+        let interrupted = store.load_all();
+        assert_eq!(interrupted.len(), 1);
+        assert_eq!(interrupted[0].files.len(), 1);
+
+        checkpointer.discard().await;
+        assert!(store.load_all().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_session_resumes_from_checkpoint() {
+        let temp_dir = TempDir::new().unwrap();
+        let root_path = temp_dir.path().join("root");
+        std::fs::create_dir_all(root_path.join("done")).unwrap();
+        std::fs::create_dir_all(root_path.join("todo")).unwrap();
+        std::fs::write(root_path.join("done/walked.txt"), b"walked").unwrap();
+        std::fs::write(root_path.join("todo/pending.txt"), b"pending").unwrap();
+        let root = root_path.to_string_lossy().to_string();
+        let path = |name: &str| root_path.join(name).to_string_lossy().to_string();
+
+        // Checkpoint left by a scan that finished walking `done/` before the app quit
+        let mut walked = get_file_info(&path("done/walked.txt")).await.unwrap();
+        walked.hash = Some("blake3:walked".to_string());
+        let mut ghost = walked.clone();
+        ghost.path = path("done/ghost.txt");
+        ghost.name = "ghost.txt".to_string();
+
+        let done_modified: DateTime<Utc> = std::fs::metadata(root_path.join("done"))
+            .unwrap()
+            .modified()
+            .unwrap()
+            .into();
+        let mut directories = HashMap::new();
+        directories.insert(path("done"), done_modified);
+
+        let checkpoints = CheckpointStore::open(temp_dir.path().join("checkpoints")).unwrap();
+        checkpoints
+            .save(
+                &test_checkpoint("interrupted", &root, "quick"),
+                &directories,
+                &FileTable::from_iter([walked, ghost]),
+            )
+            .unwrap();
+
+        let storage = Arc::new(RwLock::new(SimpleStorage {
+            checkpoints: checkpoints.clone(),
+            ..Default::default()
+        }));
+        let analyzer = DiskAnalyzer::with_storage(Arc::new(WebSocketManager::new()), storage);
+
+        let restored = analyzer.restore_interrupted_sessions().await.unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].session_id, "interrupted");
+        assert!(analyzer
+            .restore_interrupted_sessions()
+            .await
+            .unwrap()
+            .is_empty());
+
+        analyzer.start_scan_session("interrupted").await.unwrap();
+        let results = analyzer
+            .get_scan_session("interrupted")
+            .await
+            .and_then(|s| s.results)
+            .expect("resumed scan should complete");
+
+        let find = |name: &str| results.files.iter().find(|f| f.path == path(name));
+        // `done/` was not listed again, so its entries come from the checkpoint
+        assert!(find("done/ghost.txt").is_some());
+        assert_eq!(
+            find("done/walked.txt").and_then(|f| f.hash),
+            Some("blake3:walked".to_string())
+        );
+        assert!(find("todo/pending.txt").is_some());
+
+        assert!(checkpoints.load_all().is_empty());
+    }
+}
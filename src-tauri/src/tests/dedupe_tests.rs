@@ -0,0 +1,224 @@
+#[cfg(test)]
+mod tests {
+    use crate::disk_analyzer::dedupe::{Candidates, DedupeStage};
+    use crate::disk_analyzer::{
+        DiskAnalyzer, DuplicateStrategy, ScanConfig, ScanResults, ScanType,
+    };
+    use crate::file_system::FileInfo;
+    use crate::file_table::FileTable;
+    use crate::hash_cache::HashCache;
+    use crate::tests::hashing_config;
+    use crate::websocket::WebSocketManager;
+    use chrono::Utc;
+    use std::path::Path;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    const MB: usize = 1024 * 1024;
+
+    fn test_file(path: &str, size: u64) -> FileInfo {
+        FileInfo {
+            path: path.to_string(),
+            name: Path::new(path)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string(),
+            size,
+            modified: Utc::now(),
+            created: Utc::now(),
+            is_directory: false,
+            extension: None,
+            hash: None,
+            inode: None,
+            device: None,
+            links: None,
+            allocated_size: None,
+            generation: None,
+            display_path: None,
+        }
+    }
+
+    fn write(root: &Path, name: &str, contents: &[u8]) {
+        let path = root.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    async fn scan(root: &Path, config: ScanConfig) -> ScanResults {
+        let analyzer = DiskAnalyzer::new(Arc::new(WebSocketManager::new()))
+            .with_hash_cache(Arc::new(HashCache::new(0)));
+        let session_id = analyzer
+            .create_scan_session(root.to_string_lossy().to_string(), ScanType::Deep, config)
+            .await
+            .unwrap();
+        analyzer.start_scan_session(&session_id).await.unwrap();
+        analyzer
+            .get_scan_session(&session_id)
+            .await
+            .and_then(|s| s.results)
+            .expect("scan should produce results")
+    }
+
+    fn stages(results: &ScanResults) -> Vec<DedupeStage> {
+        results.dedupe.iter().map(|report| report.stage).collect()
+    }
+
+    #[test]
+    fn test_candidates_narrow_groups_down() {
+        let mut table = FileTable::new();
+        let a = table.push(&test_file("/data/a", 10));
+        let b = table.push(&test_file("/data/b", 10));
+        let c = table.push(&test_file("/data/c", 10));
+        table.push(&test_file("/data/single", 20));
+        table.push(&test_file("/data/empty", 0));
+        table.push(&test_file("/data/also-empty", 0));
+
+        // Singletons and empty files are never candidates
+        let candidates = Candidates::by_size(&table);
+        assert_eq!(candidates.groups().len(), 1);
+        assert_eq!(candidates.groups()[0].key, "10");
+        assert_eq!(candidates.bytes(&table), 30);
+
+        // Refining keeps the group key; a file without a key drops out
+        let refined = candidates.clone().refine(|id| match id {
+            id if id == c => None,
+            _ => Some("x".to_string()),
+        });
+        assert_eq!(refined.groups()[0].key, "10:x");
+        assert_eq!(refined.files().collect::<Vec<_>>(), vec![a, b]);
+
+        // Files left alone in their part are dropped with it
+        let regrouped =
+            candidates.regroup(|id| Some(if id == a { "h1" } else { "h2" }.to_string()));
+        assert_eq!(regrouped.groups().len(), 1);
+        assert_eq!(regrouped.groups()[0].key, "h2");
+        assert_eq!(regrouped.file_count(), 2);
+
+        let report = regrouped.report(DedupeStage::Full, (3, 30), 20, 0);
+        assert_eq!((report.candidates, report.groups), (2, 1));
+        assert_eq!(report.bytes_saved, 10);
+        let groups = regrouped.into_duplicate_groups(&table);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].files.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_deep_scans_read_only_possible_duplicates() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(root, "unique.bin", &vec![1u8; 300_000]);
+        let mut head = vec![1u8; MB];
+        write(root, "head_a.bin", &head);
+        head[0] = 2;
+        write(root, "head_b.bin", &head);
+        write(root, "same_a.bin", &vec![3u8; 2 * MB]);
+        write(root, "same_b.bin", &vec![3u8; 2 * MB]);
+
+        let results = scan(
+            root,
+            ScanConfig {
+                quick_hash_threshold: 0,
+                ..hashing_config()
+            },
+        )
+        .await;
+
+        assert_eq!(
+            stages(&results),
+            vec![DedupeStage::Size, DedupeStage::Blocks, DedupeStage::Full]
+        );
+        let size = &results.dedupe[0];
+        assert_eq!((size.files_in, size.candidates, size.groups), (5, 4, 2));
+        assert_eq!(size.bytes_saved, 300_000);
+        let blocks = &results.dedupe[1];
+        assert_eq!(
+            (blocks.files_in, blocks.candidates, blocks.groups),
+            (4, 2, 1)
+        );
+        assert_eq!(blocks.bytes_read, 4 * 3 * 64 * 1024);
+        assert_eq!(
+            blocks.bytes_saved,
+            6 * MB as u64 - blocks.bytes_read - 4 * MB as u64
+        );
+        let full = &results.dedupe[2];
+        assert_eq!((full.files_in, full.candidates, full.groups), (2, 2, 1));
+        assert_eq!(full.bytes_read, 4 * MB as u64);
+
+        // Only the files that survived every other stage were hashed in full
+        for file in results.files.iter() {
+            assert_eq!(
+                file.hash.is_some(),
+                file.name.starts_with("same"),
+                "{}",
+                file.name
+            );
+        }
+        assert_eq!(results.duplicate_groups.len(), 1);
+        assert_eq!(results.duplicate_groups[0].files.len(), 2);
+        assert!(results.duplicate_groups[0].hash.starts_with("blake3:"));
+    }
+
+    #[tokio::test]
+    async fn test_strategies_pick_their_stages() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(root, "a/x.bin", &vec![5u8; MB]);
+        write(root, "b/x.bin", &vec![5u8; MB]);
+        write(root, "c/y.bin", &vec![5u8; MB]);
+
+        let by_name = scan(
+            root,
+            ScanConfig {
+                duplicate_strategy: DuplicateStrategy::NameSizePartialHash,
+                quick_hash_threshold: 0,
+                ..hashing_config()
+            },
+        )
+        .await;
+        assert_eq!(
+            stages(&by_name),
+            vec![DedupeStage::Size, DedupeStage::Name, DedupeStage::Blocks]
+        );
+        assert_eq!(by_name.duplicate_groups.len(), 1);
+        assert_eq!(by_name.duplicate_groups[0].files.len(), 2);
+        assert!(by_name.duplicate_groups[0]
+            .hash
+            .starts_with("1048576:x.bin:xxh3:"));
+        assert!(by_name.files.iter().all(|f| f.hash.is_none()));
+
+        // Without full hashes, smart detection stops at the sampled blocks
+        let sampled = scan(
+            root,
+            ScanConfig {
+                duplicate_strategy: DuplicateStrategy::SmartDetection,
+                calculate_hashes: false,
+                quick_hash_threshold: 0,
+                ..hashing_config()
+            },
+        )
+        .await;
+        assert_eq!(
+            stages(&sampled),
+            vec![DedupeStage::Size, DedupeStage::Blocks]
+        );
+        assert_eq!(sampled.duplicate_groups[0].files.len(), 3);
+        assert!(sampled.duplicate_groups[0]
+            .hash
+            .starts_with("1048576:xxh3:"));
+
+        // Files under the quick hash threshold go straight to the full hash
+        let unsampled = scan(
+            root,
+            ScanConfig {
+                quick_hash_threshold: 2 * MB as u64,
+                ..hashing_config()
+            },
+        )
+        .await;
+        assert_eq!(unsampled.dedupe[1].stage, DedupeStage::Blocks);
+        assert_eq!(unsampled.dedupe[1].bytes_read, 0);
+        assert_eq!(unsampled.dedupe[2].bytes_read, 3 * MB as u64);
+        assert_eq!(unsampled.duplicate_groups[0].files.len(), 3);
+    }
+}
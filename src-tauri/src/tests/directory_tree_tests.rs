@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod tests {
+    use crate::directory_tree::{build_tree, layout_treemap, TreeOptions};
+    use crate::file_system::{FileInfo, SizeBasis};
+    use crate::file_table::FileTable;
+    use chrono::Utc;
+    use std::path::{Path, PathBuf};
+
+    fn test_file(path: &Path, size: u64) -> FileInfo {
+        FileInfo {
+            path: path.to_string_lossy().to_string(),
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            size,
+            modified: Utc::now(),
+            created: Utc::now(),
+            is_directory: false,
+            extension: None,
+            hash: None,
+            inode: None,
+            device: None,
+            links: None,
+            allocated_size: None,
+            generation: None,
+            display_path: None,
+        }
+    }
+
+    fn test_table(root: &Path) -> FileTable {
+        let mut files = vec![
+            test_file(&root.join("videos/movie.mkv"), 6000),
+            test_file(&root.join("videos/old/clip.mp4"), 2000),
+            test_file(&root.join("docs/report.pdf"), 1000),
+            test_file(&root.join("top.bin"), 500),
+        ];
+        for i in 0..100 {
+            files.push(test_file(&root.join(format!("cache/{}.tmp", i)), 1));
+        }
+        files.push(test_file(&root.join("notes.txt"), 3));
+        files.into_iter().collect()
+    }
+
+    #[test]
+    fn test_tree_sizes_are_recursive() {
+        let root = PathBuf::from("/data");
+        let table = test_table(&root);
+        let options = TreeOptions {
+            min_fraction: 0.0,
+            ..TreeOptions::default()
+        };
+        let sizes = table.subtree_sizes(options.size_basis);
+        let tree = build_tree(&table, &sizes, &root, &options).unwrap();
+
+        assert_eq!(tree.size, 9603);
+        assert_eq!(tree.file_count, 105);
+        let names: Vec<_> = tree.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["videos", "docs", "top.bin", "cache", "notes.txt"]
+        );
+        assert_eq!(tree.children[0].size, 8000);
+        assert_eq!(tree.children[0].file_count, 2);
+        assert_eq!(tree.children[3].file_count, 100);
+
+        // Drilling down starts from any directory inside the scan
+        let old = build_tree(&table, &sizes, &root.join("videos/old"), &options).unwrap();
+        assert_eq!(old.size, 2000);
+        assert_eq!(old.children[0].name, "clip.mp4");
+        assert!(build_tree(&table, &sizes, &root.join("missing"), &options).is_none());
+    }
+
+    #[test]
+    fn test_small_children_are_lumped_together() {
+        let root = PathBuf::from("/data");
+        let table = test_table(&root);
+        let options = TreeOptions {
+            depth: 1,
+            min_fraction: 0.02,
+            ..TreeOptions::default()
+        };
+        let sizes = table.subtree_sizes(options.size_basis);
+        let tree = build_tree(&table, &sizes, &root, &options).unwrap();
+
+        // cache (100 bytes) and notes.txt fall under 2% of the root
+        assert_eq!(tree.children.len(), 4);
+        let other = tree.children.iter().find(|c| c.is_other).unwrap();
+        assert_eq!(other.size, 103);
+        assert_eq!(other.file_count, 101);
+        assert_eq!(tree.children.iter().map(|c| c.size).sum::<u64>(), tree.size);
+
+        // Children below the requested depth are left for a drill-down
+        let videos = &tree.children[0];
+        assert!(videos.children.is_empty());
+        assert!(videos.has_more);
+    }
+
+    #[test]
+    fn test_treemap_fills_the_area() {
+        let root = PathBuf::from("/data");
+        let table = test_table(&root);
+        let sizes = table.subtree_sizes(SizeBasis::Apparent);
+        let tree = build_tree(&table, &sizes, &root, &TreeOptions::default()).unwrap();
+        let rects = layout_treemap(&tree, 800.0, 600.0);
+
+        assert_eq!(rects[0].path, tree.path);
+        assert_eq!((rects[0].width, rects[0].height), (800.0, 600.0));
+
+        let top_level: Vec<_> = rects.iter().filter(|r| r.depth == 1).collect();
+        assert_eq!(top_level.len(), tree.children.len());
+        let area: f64 = top_level.iter().map(|r| r.width * r.height).sum();
+        assert!((area - 800.0 * 600.0).abs() < 1e-6);
+        for rect in &top_level {
+            let expected = rect.size as f64 / tree.size as f64 * 800.0 * 600.0;
+            assert!((rect.width * rect.height - expected).abs() < 1e-6);
+            assert!(rect.x >= 0.0 && rect.x + rect.width <= 800.0 + 1e-6);
+            assert!(rect.y >= 0.0 && rect.y + rect.height <= 600.0 + 1e-6);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+#[cfg(test)]
+mod tests {
+    use crate::commands::duplicate_commands::{delete_duplicates, DeleteRequest};
+    use crate::file_system::{get_file_info, same_contents, FileInfo};
+    use std::path::Path;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    async fn scanned(path: &Path) -> FileInfo {
+        get_file_info(&path.to_string_lossy()).await.unwrap()
+    }
+
+    fn request(id: &str, file: &FileInfo, kept: Option<&FileInfo>) -> DeleteRequest {
+        DeleteRequest {
+            id: id.to_string(),
+            file: Some(file.clone()),
+            kept_id: kept.map(|_| format!("kept-{}", id)),
+            kept: kept.cloned(),
+            group: None,
+        }
+    }
+
+    #[test]
+    fn test_same_contents_compares_every_byte() {
+        let temp_dir = TempDir::new().unwrap();
+        let write = |name: &str, contents: &[u8]| {
+            let path = temp_dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+        let contents = vec![9u8; 200 * 1024]; // Several chunks
+        let a = write("a", &contents);
+        let b = write("b", &contents);
+        let mut last = contents.clone();
+        *last.last_mut().unwrap() = 0;
+        let c = write("c", &last);
+        let d = write("d", &contents[1..]);
+
+        assert!(same_contents(&a, &b).unwrap());
+        assert!(!same_contents(&a, &c).unwrap());
+        assert!(!same_contents(&a, &d).unwrap());
+        assert!(same_contents(&write("empty", b""), &write("also-empty", b"")).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_only_matching_copies_are_deleted() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for dir in ["keep", "copy", "other"] {
+            std::fs::create_dir(root.join(dir)).unwrap();
+        }
+        // Same name and size everywhere, as name and size detection would group them
+        std::fs::write(root.join("keep/photo.jpg"), b"original").unwrap();
+        std::fs::write(root.join("copy/photo.jpg"), b"original").unwrap();
+        std::fs::write(root.join("other/photo.jpg"), b"modified").unwrap();
+        let kept = scanned(&root.join("keep/photo.jpg")).await;
+        let copy = scanned(&root.join("copy/photo.jpg")).await;
+        let other = scanned(&root.join("other/photo.jpg")).await;
+
+        // Another path to the kept copy is the kept copy, whatever its name
+        let alias = scanned(&root.join("copy/../keep/photo.jpg")).await;
+
+        let result = delete_duplicates(
+            vec![
+                request("copy", &copy, Some(&kept)),
+                request("other", &other, Some(&kept)),
+                request("alias", &alias, Some(&kept)),
+            ],
+            false,
+        )
+        .await;
+
+        assert_eq!(result.deleted, vec!["copy"]);
+        assert_eq!(result.space_saved, 8);
+        assert_eq!(result.failed.len(), 2);
+        assert_eq!(result.failed[0].id, "other");
+        assert!(result.failed[0].error.contains("differ from the kept copy"));
+        assert_eq!(result.failed[1].id, "alias");
+        assert!(result.failed[1]
+            .error
+            .contains("Cannot delete the kept copy"));
+        assert!(!root.join("copy/photo.jpg").exists());
+        assert!(root.join("other/photo.jpg").exists());
+        assert!(root.join("keep/photo.jpg").exists());
+    }
+
+    #[tokio::test]
+    async fn test_every_deletion_needs_a_kept_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("a.bin"), b"same").unwrap();
+        std::fs::write(root.join("b.bin"), b"same").unwrap();
+        let hash = "blake3:same".to_string();
+        let a = FileInfo {
+            hash: Some(hash.clone()),
+            ..scanned(&root.join("a.bin")).await
+        };
+        let b = FileInfo {
+            hash: Some(hash.clone()),
+            ..scanned(&root.join("b.bin")).await
+        };
+
+        // Not even for a group confirmed by hash
+        let result = delete_duplicates(
+            vec![DeleteRequest {
+                group: Some(hash.clone()),
+                ..request("b", &b, None)
+            }],
+            false,
+        )
+        .await;
+        assert!(result.deleted.is_empty());
+        assert!(result.failed[0].error.contains("No kept copy"));
+        assert!(root.join("b.bin").exists());
+
+        // A kept copy deleted in the same batch cannot vouch for anything
+        let result = delete_duplicates(
+            vec![
+                DeleteRequest {
+                    kept_id: Some("a".to_string()),
+                    ..request("b", &b, Some(&a))
+                },
+                DeleteRequest {
+                    kept_id: Some("b".to_string()),
+                    ..request("a", &a, Some(&b))
+                },
+            ],
+            false,
+        )
+        .await;
+        assert!(result.deleted.is_empty());
+        assert_eq!(result.failed.len(), 2);
+
+        let result = delete_duplicates(
+            vec![DeleteRequest {
+                group: Some(hash),
+                ..request("b", &b, Some(&a))
+            }],
+            false,
+        )
+        .await;
+        assert_eq!(result.deleted, vec!["b"]);
+        assert!(!root.join("b.bin").exists());
+    }
+
+    #[tokio::test]
+    async fn test_files_modified_since_the_scan_are_checked_again() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("kept.txt"), b"report v1").unwrap();
+        std::fs::write(root.join("rewritten.txt"), b"report v1").unwrap();
+        std::fs::write(root.join("edited.txt"), b"report v1").unwrap();
+        let kept = scanned(&root.join("kept.txt")).await;
+        let rewritten = scanned(&root.join("rewritten.txt")).await;
+        let edited = scanned(&root.join("edited.txt")).await;
+
+        // Both change after the scan; only one still matches the kept copy
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        std::fs::write(root.join("rewritten.txt"), b"report v1").unwrap();
+        std::fs::write(root.join("edited.txt"), b"report v2").unwrap();
+
+        let result = delete_duplicates(
+            vec![
+                request("rewritten", &rewritten, Some(&kept)),
+                request("edited", &edited, Some(&kept)),
+            ],
+            false,
+        )
+        .await;
+
+        assert_eq!(result.deleted, vec!["rewritten"]);
+        assert_eq!(result.failed[0].id, "edited");
+        assert!(root.join("edited.txt").exists());
+
+        // The hashes of a hash group only vouch for files unchanged since the scan
+        let hash = "blake3:report-v1".to_string();
+        let hashed = |file: &FileInfo| FileInfo {
+            hash: Some(hash.clone()),
+            ..file.clone()
+        };
+        let result = delete_duplicates(
+            vec![DeleteRequest {
+                group: Some(hash.clone()),
+                ..request("edited", &hashed(&edited), Some(&hashed(&kept)))
+            }],
+            false,
+        )
+        .await;
+        assert!(result.failed[0].error.contains("differ from the kept copy"));
+        assert!(root.join("edited.txt").exists());
+    }
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use crate::disk_analyzer::eta::EtaEstimator;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_estimate_follows_smoothed_throughput() {
+        let start = Instant::now();
+        let mut eta = EtaEstimator::default();
+        eta.set_total(10_000);
+
+        // No estimate until a full sample interval has passed
+        eta.record(start, 0);
+        assert_eq!(eta.remaining(0), None);
+        eta.record(start + Duration::from_millis(100), 50);
+        assert_eq!(eta.remaining(50), None);
+
+        // 1000 bytes per second with 9000 bytes left
+        eta.record(start + Duration::from_secs(1), 1000);
+        assert_eq!(eta.remaining(1000), Some(Duration::from_secs(9)));
+
+        // A sudden burst only moves the rate part of the way
+        eta.record(start + Duration::from_secs(2), 6000);
+        let rate = 0.3 * 5000.0 + 0.7 * 1000.0;
+        let expected = Duration::from_secs_f64(4000.0 / rate);
+        assert_eq!(eta.remaining(6000), Some(expected));
+
+        // Finding more than expected never reports negative time
+        assert_eq!(eta.remaining(20_000), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_paused_time_is_not_sampled() {
+        let start = Instant::now();
+        let mut eta = EtaEstimator::default();
+        eta.set_total(10_000);
+        eta.record(start, 0);
+        eta.record(start + Duration::from_secs(1), 1000);
+
+        // An hour paused, then work continues at the same pace
+        eta.pause();
+        eta.record(start + Duration::from_secs(3601), 1000);
+        eta.record(start + Duration::from_secs(3602), 2000);
+        assert_eq!(eta.remaining(2000), Some(Duration::from_secs(8)));
+    }
+}
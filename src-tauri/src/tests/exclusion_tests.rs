@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod tests {
+    use crate::disk_analyzer::exclusion::{ExclusionRules, IgnoreFiles};
+    use crate::disk_analyzer::ScanConfig;
+    use crate::tests::{scanned_paths, test_config};
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn excluding(patterns: &[&str]) -> ScanConfig {
+        ScanConfig {
+            exclude_patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            ..test_config()
+        }
+    }
+
+    #[test]
+    fn test_patterns_match_names_not_substrings() {
+        let root = Path::new("/scan");
+        let rules = ExclusionRules::new(
+            root,
+            &excluding(&["/Windows", "**/*.iso", "cache/", "!keep/**", "/scan/tmp"]),
+        )
+        .unwrap();
+        let excluded = |path: &str, is_dir: bool| {
+            rules.is_excluded(&root.join(path), is_dir, &IgnoreFiles::default())
+        };
+
+        assert!(excluded("Windows", true));
+        assert!(!excluded("docs/Windows", true));
+        assert!(!excluded("docs/windows-notes.txt", false));
+        assert!(excluded("images/disk.iso", false));
+        assert!(!excluded("keep/disk.iso", false));
+        assert!(excluded("app/cache", true));
+        assert!(!excluded("app/cache", false));
+        assert!(excluded("tmp", true));
+        assert!(!excluded("other/tmp", true));
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_rejected() {
+        assert!(ExclusionRules::new(Path::new("/scan"), &excluding(&["[z-a]"])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_walk_prunes_excluded_and_ignored_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("project/target/debug")).unwrap();
+        std::fs::create_dir_all(root.join("build")).unwrap();
+        std::fs::write(root.join("project/.gitignore"), "target/\n*.log\n").unwrap();
+        std::fs::write(root.join("project/main.rs"), b"fn main() {}").unwrap();
+        std::fs::write(root.join("project/run.log"), b"log").unwrap();
+        std::fs::write(root.join("project/target/debug/app"), b"bin").unwrap();
+        std::fs::write(root.join("build/out.o"), b"obj").unwrap();
+        std::fs::write(root.join("notes.txt"), b"notes").unwrap();
+
+        let config = excluding(&["/build"]);
+        assert_eq!(
+            scanned_paths(root, config.clone()).await,
+            vec![
+                "notes.txt",
+                "project/main.rs",
+                "project/run.log",
+                "project/target/debug/app"
+            ]
+        );
+
+        let config = ScanConfig {
+            use_ignore_files: true,
+            ..config
+        };
+        assert_eq!(
+            scanned_paths(root, config).await,
+            vec!["notes.txt", "project/main.rs"]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::disk_analyzer::{DiskAnalyzer, ScanType};
+    use crate::error::DiskDominatorError;
+    use crate::file_system::{file_id, get_file_info};
+    use crate::scan_index::{ScanIndex, ScanRecord};
+    use crate::tests::test_config;
+    use crate::websocket::WebSocketManager;
+    use std::path::Path;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    async fn scan_into_index(root: &Path) -> ScanIndex {
+        let analyzer = DiskAnalyzer::new(Arc::new(WebSocketManager::new()));
+        let session_id = analyzer
+            .create_scan_session(
+                root.to_string_lossy().to_string(),
+                ScanType::Quick,
+                test_config(),
+            )
+            .await
+            .unwrap();
+        analyzer.start_scan_session(&session_id).await.unwrap();
+        let session = analyzer.get_scan_session(&session_id).await.unwrap();
+
+        let mut index = ScanIndex::default();
+        index.replace_root(ScanRecord::from_session(&session), session.results.unwrap());
+        index
+    }
+
+    #[tokio::test]
+    async fn test_ids_survive_renames_and_resolve_from_the_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir(root.join("a")).unwrap();
+        std::fs::write(root.join("a/report.pdf"), b"report").unwrap();
+        std::fs::write(root.join("a/notes.txt"), b"notes").unwrap();
+        std::fs::hard_link(root.join("a/notes.txt"), root.join("notes.txt")).unwrap();
+
+        let index = scan_into_index(root).await;
+        let report = get_file_info(&root.join("a/report.pdf").to_string_lossy())
+            .await
+            .unwrap();
+        let id = report.file_id();
+        assert_eq!(index.file_by_id(&id).unwrap().path, report.path);
+        assert!(index.file_by_id("missing").is_none());
+
+        // Hard links share an inode but not an id
+        let link = get_file_info(&root.join("notes.txt").to_string_lossy())
+            .await
+            .unwrap();
+        let original = get_file_info(&root.join("a/notes.txt").to_string_lossy())
+            .await
+            .unwrap();
+        assert_ne!(link.file_id(), original.file_id());
+
+        // Moving the file keeps its identity
+        std::fs::rename(root.join("a/report.pdf"), root.join("report.pdf")).unwrap();
+        let moved = get_file_info(&root.join("report.pdf").to_string_lossy())
+            .await
+            .unwrap();
+        assert_eq!(moved.file_id(), id);
+    }
+
+    #[tokio::test]
+    async fn test_changed_files_are_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("kept.bin"), b"data").unwrap();
+        std::fs::write(root.join("edited.bin"), b"data").unwrap();
+        std::fs::write(root.join("replaced.bin"), b"data").unwrap();
+
+        let index = scan_into_index(root).await;
+        let scanned = |name: &str| {
+            let path = root.join(name).to_string_lossy().to_string();
+            index.files().find(|f| f.path == path).unwrap()
+        };
+
+        let kept = scanned("kept.bin");
+        assert_eq!(
+            file_id::resolve_unchanged(&kept).await.unwrap(),
+            root.join("kept.bin")
+        );
+
+        std::fs::write(root.join("edited.bin"), b"more data").unwrap();
+        let edited = file_id::resolve_unchanged(&scanned("edited.bin")).await;
+        assert!(matches!(edited, Err(DiskDominatorError::FileChanged(_))));
+
+        // Same size and modification time, but a different file
+        let replaced = scanned("replaced.bin");
+        std::fs::remove_file(root.join("replaced.bin")).unwrap();
+        // Takes the freed inode if it is reused right away
+        std::fs::write(root.join("other.bin"), b"data").unwrap();
+        std::fs::write(root.join("replaced.bin"), b"data").unwrap();
+        let file = std::fs::File::options()
+            .write(true)
+            .open(root.join("replaced.bin"))
+            .unwrap();
+        file.set_modified(std::time::SystemTime::from(replaced.modified))
+            .unwrap();
+        let result = file_id::resolve_unchanged(&replaced).await;
+        assert!(matches!(result, Err(DiskDominatorError::FileChanged(_))));
+
+        std::fs::remove_file(root.join("kept.bin")).unwrap();
+        let gone = file_id::resolve_unchanged(&kept).await;
+        assert!(matches!(gone, Err(DiskDominatorError::PathNotFound(_))));
+    }
+}
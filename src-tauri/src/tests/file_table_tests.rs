@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+    use crate::app_config::PerformanceConfig;
+    use crate::disk_analyzer::{DiskAnalyzer, ScanSessionStatus, ScanType};
+    use crate::file_system::FileInfo;
+    use crate::file_table::{FileTable, ENTRY_BYTES};
+    use crate::tests::test_config;
+    use crate::websocket::WebSocketManager;
+    use chrono::{DateTime, Utc};
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    fn test_file(path: &Path, size: u64) -> FileInfo {
+        let modified = DateTime::<Utc>::from_timestamp(1_700_000_000, 123_456_789).unwrap();
+        FileInfo {
+            path: path.to_string_lossy().to_string(),
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            size,
+            modified,
+            created: modified,
+            is_directory: false,
+            extension: path.extension().map(|e| e.to_string_lossy().to_string()),
+            hash: None,
+            inode: Some(size + 1),
+            device: Some(1),
+            links: Some(1),
+            allocated_size: Some(4096),
+            generation: None,
+            display_path: None,
+        }
+    }
+
+    fn assert_same(a: &FileInfo, b: &FileInfo) {
+        assert_eq!(a.path, b.path);
+        assert_eq!(a.name, b.name);
+        assert_eq!(a.size, b.size);
+        assert_eq!(a.modified, b.modified);
+        assert_eq!(a.created, b.created);
+        assert_eq!(a.is_directory, b.is_directory);
+        assert_eq!(a.extension, b.extension);
+        assert_eq!(a.hash, b.hash);
+        assert_eq!(a.inode, b.inode);
+        assert_eq!(a.generation, b.generation);
+    }
+
+    #[test]
+    fn test_roundtrip_and_lookup() {
+        assert_eq!(ENTRY_BYTES, 64);
+
+        let root = PathBuf::from("/data");
+        let files = vec![
+            test_file(&root.join("a/report.pdf"), 10),
+            test_file(&root.join("b/report.pdf"), 20),
+            test_file(&root.join("a/notes"), 30),
+            test_file(&root.join("top.txt"), 40),
+        ];
+        let mut table: FileTable = files.iter().cloned().collect();
+        assert_eq!(table.len(), 4);
+
+        for (stored, original) in table.iter().zip(&files) {
+            assert_same(&stored, original);
+        }
+
+        let found = table.find(&root.join("b/report.pdf")).unwrap();
+        assert_eq!(found.size(), 20);
+        assert!(table.find(&root.join("b/missing.pdf")).is_none());
+        assert!(table.find(&root.join("c/report.pdf")).is_none());
+
+        let mut in_a: Vec<_> = table
+            .children_of(&root.join("a"))
+            .map(|f| f.name().to_string())
+            .collect();
+        in_a.sort();
+        assert_eq!(in_a, vec!["notes", "report.pdf"]);
+
+        let id = found.id();
+        table.set_hash(id, "cafe");
+        assert_eq!(table.get(id).unwrap().hash(), Some("cafe"));
+
+        // Serializes as a plain list of files
+        let json = serde_json::to_string(&table).unwrap();
+        let plain: Vec<FileInfo> = serde_json::from_str(&json).unwrap();
+        assert_eq!(plain.len(), 4);
+        let restored: FileTable = serde_json::from_str(&json).unwrap();
+        for (a, b) in restored.iter().zip(table.iter()) {
+            assert_same(&a, &b);
+        }
+    }
+
+    #[test]
+    fn test_shared_names_are_stored_once() {
+        let root = PathBuf::from("/data");
+        let mut table = FileTable::new();
+        for i in 0..1000 {
+            table.push(&test_file(&root.join(format!("dir{}/index.html", i)), 1));
+        }
+        let per_file = table.memory_usage() / table.len();
+        assert!(per_file < 200, "{} bytes per file", per_file);
+    }
+
+    #[tokio::test]
+    async fn test_scan_stops_at_memory_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("file.txt"), b"data").unwrap();
+
+        let analyzer = DiskAnalyzer::new(Arc::new(WebSocketManager::new()))
+            .with_performance_config(PerformanceConfig { max_memory_mb: 0 });
+        let session_id = analyzer
+            .create_scan_session(
+                temp_dir.path().to_string_lossy().to_string(),
+                ScanType::Quick,
+                test_config(),
+            )
+            .await
+            .unwrap();
+
+        let error = analyzer.start_scan_session(&session_id).await.unwrap_err();
+        assert!(error.to_string().contains("memory limit"));
+        let session = analyzer.get_scan_session(&session_id).await.unwrap();
+        assert!(matches!(session.status, ScanSessionStatus::Error(_)));
+    }
+}
@@ -0,0 +1,59 @@
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::disk_analyzer::{DiskAnalyzer, ScanType};
+    use crate::tests::hashing_config;
+    use crate::websocket::WebSocketManager;
+    use std::path::Path;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_hard_links_are_counted_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let size = 2 * 1024 * 1024;
+        std::fs::create_dir(root.join("a")).unwrap();
+        std::fs::create_dir(root.join("b")).unwrap();
+        std::fs::write(root.join("a/data.bin"), vec![7u8; size]).unwrap();
+        std::fs::hard_link(root.join("a/data.bin"), root.join("b/link.bin")).unwrap();
+        std::fs::write(root.join("b/copy.bin"), vec![7u8; size]).unwrap();
+
+        let analyzer = DiskAnalyzer::new(Arc::new(WebSocketManager::new()));
+        let session_id = analyzer
+            .create_scan_session(
+                root.to_string_lossy().to_string(),
+                ScanType::Deep,
+                hashing_config(),
+            )
+            .await
+            .unwrap();
+        analyzer.start_scan_session(&session_id).await.unwrap();
+        let results = analyzer
+            .get_scan_session(&session_id)
+            .await
+            .and_then(|s| s.results)
+            .expect("scan should produce results");
+
+        // Three paths, but only two copies of the data on disk
+        assert_eq!(results.total_files, 3);
+        assert_eq!(results.total_size, 2 * size as u64);
+
+        assert_eq!(results.duplicate_groups.len(), 1);
+        let group = &results.duplicate_groups[0];
+        assert_eq!(group.files.len(), 3);
+        assert_eq!(group.total_size, 2 * size as u64);
+        assert_eq!(group.potential_savings, size as u64);
+        assert_eq!(group.hard_linked.len(), 1);
+
+        // Removing one link of the data frees nothing
+        let linked = Path::new(&group.hard_linked[0]);
+        let metadata = std::fs::metadata(linked).unwrap();
+        assert_eq!(crate::file_system::reclaimable_size(linked, &metadata), 0);
+        let copy = root.join("b/copy.bin");
+        let metadata = std::fs::metadata(&copy).unwrap();
+        assert_eq!(
+            crate::file_system::reclaimable_size(&copy, &metadata),
+            size as u64
+        );
+    }
+}
@@ -0,0 +1,208 @@
+#[cfg(test)]
+mod tests {
+    use crate::disk_analyzer::{DiskAnalyzer, ScanType};
+    use crate::hash_cache::{HashCache, HashCounters, HashKind};
+    use crate::hashing::{hash_file_sync, HashAlgorithm};
+    use crate::tests::hashing_config;
+    use crate::websocket::WebSocketManager;
+    use std::cell::Cell;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    /// Hash `path` through the cache, counting how often it is really hashed
+    fn hash(cache: &HashCache, path: &Path, computed: &Cell<u32>) -> String {
+        cache
+            .hash_sync(
+                path,
+                HashKind::Full,
+                HashAlgorithm::Blake3,
+                &HashCounters::default(),
+                || {
+                    computed.set(computed.get() + 1);
+                    hash_file_sync(path, HashAlgorithm::Blake3, |_| {})
+                },
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_unchanged_files_are_hashed_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("a.bin");
+        std::fs::write(&path, b"contents").unwrap();
+        let cache = HashCache::new(100);
+        let computed = Cell::new(0);
+        let counters = HashCounters::default();
+
+        let first = hash(&cache, &path, &computed);
+        assert_eq!(hash(&cache, &path, &computed), first);
+        assert_eq!(computed.get(), 1);
+
+        // Kinds are cached apart, and so are algorithms
+        let partial = |algorithm: HashAlgorithm| {
+            cache
+                .hash_sync(&path, HashKind::Partial, algorithm, &counters, || {
+                    Ok(format!("{}:p", algorithm.name()))
+                })
+                .unwrap()
+        };
+        assert_eq!(partial(HashAlgorithm::Xxh3), "xxh3:p");
+        assert_eq!(partial(HashAlgorithm::Sha256), "sha256:p");
+        assert_eq!(counters.snapshot().misses, 2);
+
+        // A rename keeps the inode, and with it the cached hash
+        let renamed = temp_dir.path().join("b.bin");
+        std::fs::rename(&path, &renamed).unwrap();
+        assert_eq!(hash(&cache, &renamed, &computed), first);
+        assert_eq!(computed.get(), 1);
+
+        let status = cache.status();
+        assert_eq!(status.entries, 1);
+        assert_eq!(status.stats.hits, 2);
+        assert_eq!(status.stats.misses, 3);
+        assert_eq!(status.stats.hit_rate, 0.4);
+    }
+
+    #[test]
+    fn test_changed_files_are_hashed_again() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("a.bin");
+        std::fs::write(&path, b"before").unwrap();
+        let cache = HashCache::new(100);
+        let computed = Cell::new(0);
+
+        let before = hash(&cache, &path, &computed);
+        std::fs::write(&path, b"after!").unwrap();
+        let later = SystemTime::now() + Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        let after = hash(&cache, &path, &computed);
+        assert_ne!(after, before);
+        assert_eq!(computed.get(), 2);
+
+        // A file changed while it is hashed is not cached
+        let path = temp_dir.path().join("b.bin");
+        std::fs::write(&path, b"before").unwrap();
+        let changing = cache
+            .hash_sync(
+                &path,
+                HashKind::Full,
+                HashAlgorithm::Blake3,
+                &HashCounters::default(),
+                || {
+                    std::fs::write(&path, b"changed while hashing").unwrap();
+                    Ok("blake3:stale".to_string())
+                },
+            )
+            .unwrap();
+        assert_eq!(changing, "blake3:stale");
+        assert_ne!(hash(&cache, &path, &computed), "blake3:stale");
+        assert_eq!(computed.get(), 3);
+    }
+
+    #[test]
+    fn test_least_recently_used_hashes_are_evicted() {
+        let temp_dir = TempDir::new().unwrap();
+        let paths: Vec<_> = (0..11)
+            .map(|i| {
+                let path = temp_dir.path().join(format!("{}.bin", i));
+                std::fs::write(&path, i.to_string()).unwrap();
+                path
+            })
+            .collect();
+        let cache = HashCache::new(10);
+        let computed = Cell::new(0);
+
+        for path in &paths[..10] {
+            hash(&cache, path, &computed);
+        }
+        hash(&cache, &paths[0], &computed); // Used again, so kept
+        hash(&cache, &paths[10], &computed);
+        assert_eq!(cache.status().entries, 9);
+
+        computed.set(0);
+        hash(&cache, &paths[0], &computed);
+        hash(&cache, &paths[10], &computed);
+        assert_eq!(computed.get(), 0);
+        hash(&cache, &paths[1], &computed);
+        assert_eq!(computed.get(), 1);
+
+        // A limit of 0 turns the cache off
+        let disabled = HashCache::new(0);
+        hash(&disabled, &paths[0], &computed);
+        hash(&disabled, &paths[0], &computed);
+        assert_eq!(computed.get(), 3);
+        assert_eq!(disabled.status().entries, 0);
+    }
+
+    #[test]
+    fn test_cache_persists_across_reopens() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("hash_cache.jsonl");
+        let path = temp_dir.path().join("a.bin");
+        std::fs::write(&path, b"contents").unwrap();
+        let computed = Cell::new(0);
+
+        let cache = HashCache::open(&cache_path, 100).unwrap();
+        let first = hash(&cache, &path, &computed);
+        cache.flush().unwrap();
+
+        let mut lines = std::fs::read_to_string(&cache_path).unwrap();
+        lines.push_str("not json\n");
+        std::fs::write(&cache_path, lines).unwrap();
+
+        let reopened = HashCache::open(&cache_path, 100).unwrap();
+        assert_eq!(reopened.status().entries, 1);
+        assert_eq!(hash(&reopened, &path, &computed), first);
+        assert_eq!(computed.get(), 1);
+
+        reopened.clear();
+        reopened.flush().unwrap();
+        assert_eq!(
+            HashCache::open(&cache_path, 100).unwrap().status().entries,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deep_scans_report_cache_hits() {
+        let temp_dir = TempDir::new().unwrap();
+        let contents = vec![7u8; 2 * 1024 * 1024]; // Over the quick hash threshold, so sampled first
+        for dir in ["a", "b"] {
+            std::fs::create_dir(temp_dir.path().join(dir)).unwrap();
+            std::fs::write(temp_dir.path().join(dir).join("big.bin"), &contents).unwrap();
+        }
+        let root = temp_dir.path().to_string_lossy().to_string();
+        let analyzer = DiskAnalyzer::new(Arc::new(WebSocketManager::new()))
+            .with_hash_cache(Arc::new(HashCache::new(100)));
+        let config = hashing_config();
+
+        let mut stats = Vec::new();
+        for _ in 0..2 {
+            let session_id = analyzer
+                .create_scan_session(root.clone(), ScanType::Deep, config.clone())
+                .await
+                .unwrap();
+            analyzer.start_scan_session(&session_id).await.unwrap();
+            let results = analyzer
+                .get_scan_session(&session_id)
+                .await
+                .unwrap()
+                .results
+                .unwrap();
+            assert_eq!(results.duplicate_groups.len(), 1);
+            stats.push(results.hash_cache);
+        }
+
+        // Block and full hashes of both files
+        assert_eq!((stats[0].hits, stats[0].misses), (0, 4));
+        assert_eq!((stats[1].hits, stats[1].misses), (4, 0));
+        assert_eq!(stats[1].hit_rate, 1.0);
+    }
+}
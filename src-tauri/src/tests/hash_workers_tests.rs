@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod tests {
+    use crate::disk_analyzer::dedupe::DedupeStage;
+    use crate::disk_analyzer::hash_workers;
+    use crate::disk_analyzer::{DiskAnalyzer, ScanConfig, ScanType};
+    use crate::hash_cache::HashCache;
+    use crate::tests::hashing_config;
+    use crate::websocket::WebSocketManager;
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn pool(threads: usize) -> rayon::ThreadPool {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_slow_consumers_receive_every_outcome() {
+        let mut rx = hash_workers::spawn(pool(4), (0..250).collect(), 4, |i: &u32| {
+            Some(match i % 7 {
+                0 => Err(anyhow::anyhow!("unreadable")),
+                _ => Ok(format!("hash:{}", i)),
+            })
+        });
+
+        let mut hashed = HashSet::new();
+        let mut failed = HashSet::new();
+        while let Some((i, outcome)) = rx.recv().await {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            match outcome {
+                Ok(hash) => {
+                    assert_eq!(hash, format!("hash:{}", i));
+                    hashed.insert(i);
+                }
+                Err(_) => {
+                    failed.insert(i);
+                }
+            }
+        }
+
+        assert_eq!(hashed.len() + failed.len(), 250);
+        assert!(failed.iter().all(|i| i % 7 == 0));
+        assert_eq!(failed.len(), 36);
+    }
+
+    #[tokio::test]
+    async fn test_dropping_the_receiver_stops_the_workers() {
+        let started = Arc::new(AtomicUsize::new(0));
+        let worker_started = started.clone();
+        let mut rx = hash_workers::spawn(pool(2), (0..1000).collect(), 1, move |i: &u32| {
+            worker_started.fetch_add(1, Ordering::SeqCst);
+            Some(Ok(i.to_string()))
+        });
+        for _ in 0..5 {
+            rx.recv().await.unwrap().1.unwrap();
+        }
+        drop(rx);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Received, buffered, and at most two per worker around the drop
+        assert!(started.load(Ordering::SeqCst) <= 5 + 1 + 2 * 2);
+    }
+
+    #[tokio::test]
+    async fn test_deep_scans_hash_more_files_than_the_channel_holds() {
+        let temp_dir = TempDir::new().unwrap();
+        let files = 3 * hash_workers::RESULT_CAPACITY;
+        for i in 0..files {
+            std::fs::write(temp_dir.path().join(format!("{}.bin", i)), b"same").unwrap();
+        }
+
+        let analyzer = DiskAnalyzer::new(Arc::new(WebSocketManager::new()))
+            .with_hash_cache(Arc::new(HashCache::new(0)));
+        let session_id = analyzer
+            .create_scan_session(
+                temp_dir.path().to_string_lossy().to_string(),
+                ScanType::Deep,
+                ScanConfig {
+                    quick_hash_threshold: 0,
+                    ..hashing_config()
+                },
+            )
+            .await
+            .unwrap();
+        analyzer.start_scan_session(&session_id).await.unwrap();
+        let session = analyzer.get_scan_session(&session_id).await.unwrap();
+        let results = session.results.unwrap();
+
+        assert!(results.files.iter().all(|f| f.hash.is_some()));
+        assert_eq!(results.duplicate_groups.len(), 1);
+        assert_eq!(results.duplicate_groups[0].files.len(), files);
+        let full = results.dedupe.last().unwrap();
+        assert_eq!(full.stage, DedupeStage::Full);
+        assert_eq!(full.candidates, files as u64);
+        assert_eq!(session.progress.deep_scan.processed_files, files as u64);
+    }
+}
@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+    use crate::app_config::HashingConfig;
+    use crate::app_state::SimpleStorage;
+    use crate::disk_analyzer::{DiskAnalyzer, ScanType};
+    use crate::hash_cache::HashCache;
+    use crate::hashing::{hash_file_sync, hash_partial_sync, sampled_bytes, HashAlgorithm};
+    use crate::tests::hashing_config;
+    use crate::websocket::WebSocketManager;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+    use tokio::sync::RwLock;
+
+    fn digest(algorithm: HashAlgorithm, data: &[u8]) -> String {
+        let mut hasher = algorithm.hasher();
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    #[test]
+    fn test_hashes_are_tagged_with_their_algorithm() {
+        assert_eq!(
+            digest(HashAlgorithm::Sha256, b"abc"),
+            "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            digest(HashAlgorithm::Blake3, b"abc"),
+            "blake3:6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+        assert_eq!(
+            digest(HashAlgorithm::Xxh3, b"abc"),
+            format!("xxh3:{:032x}", xxhash_rust::xxh3::xxh3_128(b"abc"))
+        );
+
+        let hash = digest(HashAlgorithm::Xxh3, b"abc");
+        assert_eq!(HashAlgorithm::of(&hash), Some(HashAlgorithm::Xxh3));
+        assert!(!HashAlgorithm::Blake3.tagged(&hash));
+        // Untagged SHA-256 hashes of earlier versions belong to no algorithm
+        assert_eq!(
+            HashAlgorithm::of("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_big_files_hash_the_same_on_several_threads() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("big.bin");
+        let contents: Vec<u8> = (0..9 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &contents).unwrap();
+        let expected = format!("blake3:{}", blake3::hash(&contents).to_hex());
+
+        // One update large enough to be split across threads
+        assert_eq!(digest(HashAlgorithm::Blake3, &contents), expected);
+
+        let mut read = 0;
+        let hash = hash_file_sync(&path, HashAlgorithm::Blake3, |bytes| read += bytes).unwrap();
+        assert_eq!(hash, expected);
+        assert_eq!(read, contents.len());
+
+        // The prefilter reads the first, middle and last blocks
+        let block = 64 * 1024;
+        let middle = contents.len() / 2 - block / 2;
+        let mut blocks = contents[..block].to_vec();
+        blocks.extend_from_slice(&contents[middle..middle + block]);
+        blocks.extend_from_slice(&contents[contents.len() - block..]);
+        assert_eq!(
+            hash_partial_sync(&path, HashAlgorithm::Xxh3).unwrap(),
+            digest(HashAlgorithm::Xxh3, &blocks)
+        );
+        assert_eq!(sampled_bytes(contents.len() as u64), 3 * block as u64);
+
+        // Small files are hashed whole
+        let small = temp_dir.path().join("small.bin");
+        std::fs::write(&small, &contents[..100_000]).unwrap();
+        assert_eq!(
+            hash_partial_sync(&small, HashAlgorithm::Xxh3).unwrap(),
+            digest(HashAlgorithm::Xxh3, &contents[..100_000])
+        );
+        assert_eq!(sampled_bytes(100_000), 100_000);
+    }
+
+    #[tokio::test]
+    async fn test_hashes_of_another_algorithm_are_recomputed() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_string_lossy().to_string();
+        for name in ["big.bin", "copy.bin"] {
+            std::fs::write(temp_dir.path().join(name), vec![7u8; 2 * 1024 * 1024]).unwrap();
+        }
+        let storage = Arc::new(RwLock::new(SimpleStorage::default()));
+        let cache = Arc::new(HashCache::new(100));
+        let analyzer = |algorithm| {
+            DiskAnalyzer::with_storage(Arc::new(WebSocketManager::new()), storage.clone())
+                .with_hash_cache(cache.clone())
+                .with_hashing_config(HashingConfig {
+                    algorithm,
+                    ..HashingConfig::default()
+                })
+        };
+
+        let mut hashes = Vec::new();
+        for (algorithm, scan_type) in [
+            (HashAlgorithm::Sha256, ScanType::Deep),
+            (HashAlgorithm::Blake3, ScanType::Incremental),
+        ] {
+            let analyzer = analyzer(algorithm);
+            let session_id = analyzer
+                .create_scan_session(root.clone(), scan_type, hashing_config())
+                .await
+                .unwrap();
+            analyzer.start_scan_session(&session_id).await.unwrap();
+            let results = analyzer
+                .get_scan_session(&session_id)
+                .await
+                .and_then(|s| s.results)
+                .unwrap();
+            let hash = results.files.iter().find_map(|f| f.hash).unwrap();
+            assert!(algorithm.tagged(&hash), "{}", hash);
+            hashes.push((hash, results.hash_cache));
+        }
+
+        // Only the block hashes were reused; neither the previous scan nor
+        // the cache offered the SHA-256 hashes
+        assert_eq!((hashes[1].1.hits, hashes[1].1.misses), (2, 2));
+        assert_eq!(cache.status().entries, 2);
+    }
+}
@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod tests {
+    use crate::app_state::SimpleStorage;
+    use crate::disk_analyzer::{DiskAnalyzer, ScanType};
+    use crate::tests::hashing_config;
+    use crate::websocket::WebSocketManager;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use tokio::sync::RwLock;
+
+    async fn run_scan(
+        analyzer: &DiskAnalyzer,
+        root: &str,
+        scan_type: ScanType,
+    ) -> crate::disk_analyzer::ScanResults {
+        let session_id = analyzer
+            .create_scan_session(root.to_string(), scan_type, hashing_config())
+            .await
+            .unwrap();
+        analyzer.start_scan_session(&session_id).await.unwrap();
+        analyzer
+            .get_scan_session(&session_id)
+            .await
+            .and_then(|s| s.results)
+            .expect("scan should produce results")
+    }
+
+    #[tokio::test]
+    async fn test_incremental_scan_reports_changes_and_reuses_hashes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root_path = temp_dir.path();
+        std::fs::create_dir(root_path.join("keep")).unwrap();
+        std::fs::create_dir(root_path.join("gone")).unwrap();
+        // Duplicated, so the deep scan hashes it
+        for name in ["keep/big.bin", "keep/copy.bin"] {
+            std::fs::write(root_path.join(name), vec![7u8; 2 * 1024 * 1024]).unwrap();
+        }
+        std::fs::write(root_path.join("gone/old.txt"), b"old").unwrap();
+        std::fs::write(root_path.join("edit.txt"), b"before").unwrap();
+
+        let storage = Arc::new(RwLock::new(SimpleStorage::default()));
+        let analyzer =
+            DiskAnalyzer::with_storage(Arc::new(WebSocketManager::new()), storage.clone());
+        let root = root_path.to_string_lossy().to_string();
+
+        let first = run_scan(&analyzer, &root, ScanType::Deep).await;
+        assert_eq!(first.total_files, 4);
+        assert!(first.changes.is_none());
+        let big_path = root_path.join("keep/big.bin").to_string_lossy().to_string();
+        let big_hash = first
+            .files
+            .iter()
+            .find(|f| f.path == big_path)
+            .and_then(|f| f.hash.clone())
+            .expect("duplicated file should be hashed");
+
+        // Let directory modification times move past the first scan
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        std::fs::remove_file(root_path.join("gone/old.txt")).unwrap();
+        std::fs::write(root_path.join("edit.txt"), b"after the edit").unwrap();
+        std::fs::write(root_path.join("new.txt"), b"new").unwrap();
+
+        let second = run_scan(&analyzer, &root, ScanType::Incremental).await;
+        let changes = second
+            .changes
+            .expect("incremental scan should report changes");
+        let path = |name: &str| root_path.join(name).to_string_lossy().to_string();
+        assert_eq!(changes.added, vec![path("new.txt")]);
+        assert_eq!(changes.removed, vec![path("gone/old.txt")]);
+        assert_eq!(changes.modified, vec![path("edit.txt")]);
+
+        assert_eq!(second.total_files, 4);
+        let big = second
+            .files
+            .iter()
+            .find(|f| f.path == big_path)
+            .expect("unchanged file should be carried forward");
+        assert_eq!(big.hash.as_deref(), Some(big_hash.as_str()));
+
+        // The index now holds the incremental result
+        let storage = storage.read().await;
+        let indexed = storage.index.get(&root).expect("root should be indexed");
+        assert_eq!(indexed.files.len(), 4);
+        assert!(indexed.directories.contains_key(&path("keep")));
+    }
+}
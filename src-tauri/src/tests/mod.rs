@@ -1,7 +1,119 @@
 // Test modules
 #[cfg(test)]
+pub mod checkpoint_tests;
+#[cfg(test)]
+pub mod dedupe_tests;
+#[cfg(test)]
+pub mod directory_tree_tests;
+#[cfg(test)]
+pub mod duplicate_delete_tests;
+#[cfg(test)]
+pub mod eta_tests;
+#[cfg(test)]
+pub mod exclusion_tests;
+#[cfg(test)]
+pub mod file_id_tests;
+#[cfg(test)]
+pub mod file_table_tests;
+#[cfg(test)]
+pub mod hash_cache_tests;
+#[cfg(test)]
+pub mod hash_workers_tests;
+#[cfg(test)]
+pub mod hard_link_tests;
+#[cfg(test)]
+pub mod hashing_tests;
+#[cfg(test)]
+pub mod incremental_scan_tests;
+#[cfg(test)]
+pub mod parallel_walker_tests;
+#[cfg(test)]
+pub mod path_encoding_tests;
+#[cfg(test)]
+pub mod scan_control_tests;
+#[cfg(test)]
+pub mod scan_errors_tests;
+#[cfg(test)]
+pub mod scan_index_tests;
+#[cfg(test)]
+pub mod scan_limits_tests;
+#[cfg(test)]
+pub mod scan_profile_tests;
+#[cfg(test)]
+pub mod scheduler_tests;
+#[cfg(test)]
+pub mod size_basis_tests;
+#[cfg(test)]
+pub mod throttle_tests;
+#[cfg(test)]
 pub mod simple_test;
+#[cfg(test)]
+pub mod watcher_tests;
 // #[cfg(test)]
 // pub mod home_commands_tests;
 // #[cfg(test)]
 // pub mod disk_analyzer_tests;
+
+#[cfg(test)]
+use crate::disk_analyzer::{DiskAnalyzer, DuplicateStrategy, ScanConfig, ScanType};
+
+/// Settings shared by the tests: every file, no limits and no content hashing
+#[cfg(test)]
+pub fn test_config() -> ScanConfig {
+    ScanConfig {
+        exclude_patterns: vec![],
+        include_hidden: false,
+        follow_symlinks: false,
+        max_depth: None,
+        min_file_size: None,
+        max_file_size: None,
+        calculate_hashes: false,
+        quick_hash_threshold: 1024 * 1024,
+        duplicate_strategy: DuplicateStrategy::NameAndSize,
+        large_file_threshold: 100 * 1024 * 1024,
+        same_filesystem: false,
+        use_ignore_files: false,
+    }
+}
+
+/// [`test_config`] with duplicates confirmed by full content hashes
+#[cfg(test)]
+pub fn hashing_config() -> ScanConfig {
+    ScanConfig {
+        calculate_hashes: true,
+        duplicate_strategy: DuplicateStrategy::HashOnly,
+        ..test_config()
+    }
+}
+
+/// Scan `root` and return the found paths relative to it, sorted
+#[cfg(test)]
+pub async fn scanned_paths(root: &std::path::Path, config: ScanConfig) -> Vec<String> {
+    let analyzer = DiskAnalyzer::new(std::sync::Arc::new(
+        crate::websocket::WebSocketManager::new(),
+    ));
+    let session_id = analyzer
+        .create_scan_session(root.to_string_lossy().to_string(), ScanType::Quick, config)
+        .await
+        .unwrap();
+    analyzer.start_scan_session(&session_id).await.unwrap();
+
+    let results = analyzer
+        .get_scan_session(&session_id)
+        .await
+        .and_then(|s| s.results)
+        .expect("scan should produce results");
+    let mut paths: Vec<String> = results
+        .files
+        .iter()
+        .map(|f| {
+            std::path::Path::new(&f.path)
+                .strip_prefix(root)
+                .unwrap()
+                .to_string_lossy()
+                .replace('\\', "/")
+        })
+        .collect();
+    paths.sort();
+    paths
+}
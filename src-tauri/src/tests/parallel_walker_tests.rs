@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use crate::app_config::{AppConfig, ScannerConfig};
+    use crate::disk_analyzer::{DiskAnalyzer, ScanResults, ScanType};
+    use crate::tests::test_config;
+    use crate::websocket::WebSocketManager;
+    use std::path::Path;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    async fn scan_with_threads(root: &Path, max_threads: usize) -> ScanResults {
+        let analyzer = DiskAnalyzer::new(Arc::new(WebSocketManager::new()))
+            .with_scanner_config(ScannerConfig { max_threads });
+        let session_id = analyzer
+            .create_scan_session(
+                root.to_string_lossy().to_string(),
+                ScanType::Quick,
+                test_config(),
+            )
+            .await
+            .unwrap();
+        analyzer.start_scan_session(&session_id).await.unwrap();
+        analyzer
+            .get_scan_session(&session_id)
+            .await
+            .and_then(|s| s.results)
+            .expect("scan should produce results")
+    }
+
+    #[tokio::test]
+    async fn test_parallel_walk_matches_single_thread() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for a in 0..5 {
+            for b in 0..4 {
+                let dir = root.join(format!("dir{}/sub{}", a, b));
+                std::fs::create_dir_all(&dir).unwrap();
+                for f in 0..30 {
+                    std::fs::write(dir.join(format!("file{}.txt", f)), vec![0u8; f]).unwrap();
+                }
+            }
+        }
+
+        let single = scan_with_threads(root, 1).await;
+        let parallel = scan_with_threads(root, 8).await;
+
+        assert_eq!(single.total_files, 600);
+        assert_eq!(parallel.total_files, 600);
+        assert_eq!(single.total_size, parallel.total_size);
+
+        let mut single_paths: Vec<_> = single.files.iter().map(|f| f.path.clone()).collect();
+        let mut parallel_paths: Vec<_> = parallel.files.iter().map(|f| f.path.clone()).collect();
+        single_paths.sort();
+        parallel_paths.sort();
+        assert_eq!(single_paths, parallel_paths);
+
+        // Root, 5 directories and 20 subdirectories, all walked completely
+        assert_eq!(parallel.directories.len(), 26);
+        assert!(parallel
+            .directories
+            .contains_key(&root.to_string_lossy().to_string()));
+    }
+
+    #[test]
+    fn test_scanner_config_is_read_from_config_file() {
+        let example = Path::new(env!("CARGO_MANIFEST_DIR")).join("../docs/config.example.toml");
+        assert_eq!(AppConfig::load_from(&example).scanner.max_threads, 4);
+
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        assert_eq!(
+            AppConfig::load_from(&config_path).scanner.max_threads,
+            ScannerConfig::default().max_threads
+        );
+
+        std::fs::write(&config_path, "[scanner]\nmax_threads = 12\n").unwrap();
+        assert_eq!(AppConfig::load_from(&config_path).scanner.max_threads, 12);
+    }
+}
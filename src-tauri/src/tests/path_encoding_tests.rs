@@ -0,0 +1,86 @@
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::disk_analyzer::{DiskAnalyzer, ScanType};
+    use crate::file_system::path_encoding::{
+        decode_path, display_path, encode_path, is_encoded, with_suffix,
+    };
+    use crate::tests::hashing_config;
+    use crate::websocket::WebSocketManager;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_paths_round_trip() {
+        let plain = Path::new("/data/café/report.pdf");
+        assert_eq!(encode_path(plain), "/data/café/report.pdf");
+        assert!(!is_encoded(&encode_path(plain)));
+
+        let raw = Path::new(OsStr::from_bytes(b"/data/caf\xe9/r\xffport.pdf"));
+        let encoded = encode_path(raw);
+        assert!(is_encoded(&encoded));
+        assert!(encoded.starts_with("/data/"));
+        assert_eq!(decode_path(&encoded), raw);
+        assert_eq!(display_path(&encoded), "/data/caf\\xE9/r\\xFFport.pdf");
+
+        // Parents and suffixes work on the exact path
+        let parent = Path::new(&encoded).parent().unwrap();
+        assert_eq!(
+            decode_path(&parent.to_string_lossy()),
+            Path::new(OsStr::from_bytes(b"/data/caf\xe9"))
+        );
+        assert_eq!(
+            with_suffix(raw, ".trash").as_os_str().as_bytes(),
+            b"/data/caf\xe9/r\xffport.pdf.trash"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_operations_resolve_the_exact_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let exact = root.join(OsStr::from_bytes(b"caf\xe9.txt"));
+        let lookalike = root.join("caf\u{FFFD}.txt"); // What a lossy copy of the name would open
+                                                      // Equal sizes and sampled blocks, so both are hashed in full
+        let size = 2 * 1024 * 1024;
+        let mut other = vec![1u8; size];
+        other[size / 4] = 2;
+        std::fs::write(&exact, vec![1u8; size]).unwrap();
+        std::fs::write(&lookalike, &other).unwrap();
+
+        let analyzer = DiskAnalyzer::new(Arc::new(WebSocketManager::new()));
+        let session_id = analyzer
+            .create_scan_session(
+                root.to_string_lossy().to_string(),
+                ScanType::Deep,
+                hashing_config(),
+            )
+            .await
+            .unwrap();
+        analyzer.start_scan_session(&session_id).await.unwrap();
+        let results = analyzer
+            .get_scan_session(&session_id)
+            .await
+            .and_then(|s| s.results)
+            .expect("scan should produce results");
+
+        let file = results
+            .files
+            .iter()
+            .find(|f| f.display_path.is_some())
+            .unwrap();
+        assert_eq!(file.name, "caf\\xE9.txt");
+        assert_eq!(
+            file.display_path.as_deref(),
+            Some(display_path(&file.path).as_ref())
+        );
+        assert!(file.hash.is_some()); // Hashing opened it too
+        assert!(results.duplicate_groups.is_empty());
+
+        crate::file_system::delete_file(&file.path).await.unwrap();
+        assert!(!exact.exists());
+        assert_eq!(std::fs::read(&lookalike).unwrap(), other);
+    }
+}
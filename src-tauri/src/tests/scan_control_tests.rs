@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use crate::disk_analyzer::control::ScanControl;
+    use crate::disk_analyzer::{DiskAnalyzer, ScanSessionStatus, ScanType};
+    use crate::tests::test_config;
+    use crate::websocket::WebSocketManager;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_child_follows_parent() {
+        let parent = Arc::new(ScanControl::default());
+        let child = parent.child();
+
+        parent.pause();
+        assert!(child.is_paused());
+        parent.resume();
+        assert!(!child.is_paused());
+
+        child.cancel();
+        assert!(!parent.is_cancelled());
+        parent.cancel();
+        assert!(parent.child().is_cancelled());
+
+        child.add_file(10);
+        assert_eq!(child.files_processed(), 1);
+        assert_eq!(parent.files_processed(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_controlled_independently() {
+        let first_dir = TempDir::new().unwrap();
+        let second_dir = TempDir::new().unwrap();
+        std::fs::write(first_dir.path().join("a.txt"), b"a").unwrap();
+        for name in ["b.txt", "c.txt"] {
+            std::fs::write(second_dir.path().join(name), b"bc").unwrap();
+        }
+
+        let analyzer = DiskAnalyzer::new(Arc::new(WebSocketManager::new()));
+        let first = analyzer
+            .create_scan_session(
+                first_dir.path().to_string_lossy().to_string(),
+                ScanType::Quick,
+                test_config(),
+            )
+            .await
+            .unwrap();
+        let second = analyzer
+            .create_scan_session(
+                second_dir.path().to_string_lossy().to_string(),
+                ScanType::Quick,
+                test_config(),
+            )
+            .await
+            .unwrap();
+
+        // The first scan waits while paused; the second must not be held up by it
+        analyzer.pause_scan_session(&first).await.unwrap();
+        let first_task = {
+            let analyzer = analyzer.clone();
+            let first = first.clone();
+            tokio::spawn(async move { analyzer.start_scan_session(&first).await })
+        };
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            analyzer.start_scan_session(&second),
+        )
+        .await
+        .expect("second scan should not wait for the paused one")
+        .unwrap();
+
+        let second_session = analyzer.get_scan_session(&second).await.unwrap();
+        assert!(matches!(
+            second_session.status,
+            ScanSessionStatus::Completed
+        ));
+        assert_eq!(second_session.progress.quick_scan.processed_files, 2);
+        assert!(matches!(
+            analyzer.get_scan_session(&first).await.unwrap().status,
+            ScanSessionStatus::Paused
+        ));
+
+        // Cancelling the paused scan ends it without results
+        analyzer.cancel_scan_session(&first).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(10), first_task)
+            .await
+            .expect("cancelled scan should stop")
+            .unwrap()
+            .unwrap();
+        let first_session = analyzer.get_scan_session(&first).await.unwrap();
+        assert!(matches!(first_session.status, ScanSessionStatus::Cancelled));
+        assert!(first_session.results.is_none());
+    }
+}
@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod tests {
+    use crate::disk_analyzer::scan_errors::{
+        ScanError, ScanErrorAction, ScanErrorKind, ScanErrors, MAX_RECORDED_ERRORS,
+    };
+    use crate::disk_analyzer::{DiskAnalyzer, ScanConfig, ScanType};
+    use crate::error::DiskDominatorError;
+    use crate::tests::test_config;
+    use crate::websocket::WebSocketManager;
+    use std::io::{Error, ErrorKind};
+    use std::path::Path;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    fn io_error(path: &str, kind: ErrorKind) -> ScanError {
+        let path = Path::new(path);
+        ScanError::new(
+            path,
+            &DiskDominatorError::from_io(path, Error::new(kind, "test")),
+        )
+    }
+
+    #[test]
+    fn test_errors_are_classified_and_capped() {
+        assert_eq!(
+            io_error("/a", ErrorKind::PermissionDenied).kind,
+            ScanErrorKind::PermissionDenied
+        );
+        assert_eq!(
+            io_error("/a", ErrorKind::NotFound).kind,
+            ScanErrorKind::PathVanished
+        );
+        assert_eq!(
+            io_error("/a", ErrorKind::InvalidFilename).kind,
+            ScanErrorKind::PathTooLong
+        );
+        let other = io_error("/a", ErrorKind::Other);
+        assert_eq!(other.kind, ScanErrorKind::Io);
+        assert!(other.message.contains("/a"));
+
+        let mut errors = ScanErrors::default();
+        for i in 0..MAX_RECORDED_ERRORS + 10 {
+            errors.record(io_error(
+                &format!("/denied/{}", i),
+                ErrorKind::PermissionDenied,
+            ));
+        }
+        errors.record(io_error("/gone", ErrorKind::NotFound));
+
+        let report = errors.report();
+        assert_eq!(report.total, MAX_RECORDED_ERRORS as u64 + 11);
+        assert!(report.truncated);
+        assert_eq!(report.errors.len(), MAX_RECORDED_ERRORS);
+        assert_eq!(report.summaries.len(), 2);
+
+        let denied = &report.summaries[0];
+        assert_eq!(denied.kind, ScanErrorKind::PermissionDenied);
+        assert_eq!(denied.count, MAX_RECORDED_ERRORS as u64 + 10);
+        assert_eq!(
+            denied.suggested_action,
+            Some(ScanErrorAction::RetryElevated)
+        );
+        assert_eq!(denied.example_paths.len(), 5);
+
+        // Counted even though it came after the cap
+        let gone = &report.summaries[1];
+        assert_eq!(gone.count, 1);
+        assert!(gone.example_paths.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_walk_reports_errors_per_path() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("ok.txt"), b"data").unwrap();
+        std::os::unix::fs::symlink(root.join("missing"), root.join("broken")).unwrap();
+        // Names that are not valid Unicode are stored exactly, not reported
+        std::fs::write(root.join(OsStr::from_bytes(b"caf\xe9.txt")), b"data").unwrap();
+
+        let analyzer = DiskAnalyzer::new(Arc::new(WebSocketManager::new()));
+        let session_id = analyzer
+            .create_scan_session(
+                root.to_string_lossy().to_string(),
+                ScanType::Quick,
+                ScanConfig {
+                    follow_symlinks: true,
+                    ..test_config()
+                },
+            )
+            .await
+            .unwrap();
+        analyzer.start_scan_session(&session_id).await.unwrap();
+
+        let report = analyzer.get_scan_errors(&session_id).await.unwrap();
+        assert_eq!(report.total, 1);
+        assert!(!report.truncated);
+
+        let broken = &report.errors[0];
+        assert_eq!(broken.kind, ScanErrorKind::BrokenSymlink);
+        assert_eq!(Path::new(&broken.path), root.join("broken"));
+        assert_eq!(
+            report.summaries[0].suggested_action,
+            Some(ScanErrorAction::ExcludePath)
+        );
+
+        // The progress still lists the messages
+        let session = analyzer.get_scan_session(&session_id).await.unwrap();
+        assert_eq!(session.progress.quick_scan.errors.len(), 1);
+    }
+}
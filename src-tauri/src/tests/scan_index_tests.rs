@@ -0,0 +1,361 @@
+#[cfg(test)]
+mod tests {
+    use crate::app_state::SimpleStorage;
+    use crate::disk_analyzer::{DiskAnalyzer, DuplicateGroup, ScanResults, ScanType};
+    use crate::file_system::{FileInfo, SizeBasis};
+    use crate::scan_index::{FileChange, ScanIndex, ScanRecord};
+    use crate::tests::test_config;
+    use crate::websocket::WebSocketManager;
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+    use tokio::sync::RwLock;
+
+    fn test_record(root: &str, session_id: &str, files: &[FileInfo]) -> ScanRecord {
+        ScanRecord {
+            root: root.to_string(),
+            session_id: session_id.to_string(),
+            scan_type: ScanType::Quick,
+            started_at: Utc::now(),
+            completed_at: Utc::now(),
+            config: test_config(),
+            total_files: files.len() as u64,
+            total_size: files.iter().map(|f| f.size).sum(),
+            total_allocated_size: 0,
+        }
+    }
+
+    fn test_results(files: Vec<FileInfo>) -> ScanResults {
+        ScanResults {
+            total_files: files.len() as u64,
+            total_size: files.iter().map(|f| f.size).sum(),
+            total_allocated_size: 0,
+            files: files.into_iter().collect(),
+            duplicate_groups: Vec::new(),
+            large_files: Vec::new(),
+            scan_duration: 0,
+            directories: HashMap::new(),
+            changes: None,
+            hash_cache: Default::default(),
+            dedupe: Vec::new(),
+        }
+    }
+
+    fn test_file(path: &str, size: u64) -> FileInfo {
+        FileInfo {
+            path: path.to_string(),
+            name: path.rsplit('/').next().unwrap_or(path).to_string(),
+            size,
+            modified: Utc::now(),
+            created: Utc::now(),
+            is_directory: false,
+            extension: None,
+            hash: None,
+            inode: None,
+            device: None,
+            links: None,
+            allocated_size: None,
+            generation: None,
+            display_path: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_survives_reopen() {
+        let temp_dir = TempDir::new().unwrap();
+
+        {
+            let mut index = ScanIndex::open(temp_dir.path()).unwrap();
+            let files = vec![test_file("/data/a.bin", 10), test_file("/data/b.bin", 20)];
+            index.replace_root(
+                test_record("/data", "session-1", &files),
+                test_results(files),
+            );
+
+            // Nothing reaches the disk until the queued writes are persisted
+            assert_eq!(ScanIndex::open(temp_dir.path()).unwrap().roots().count(), 0);
+            index.pending_writes().persist().await;
+        }
+
+        let index = ScanIndex::open(temp_dir.path()).unwrap();
+        let roots: Vec<_> = index.roots().collect();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].record.session_id, "session-1");
+        assert_eq!(roots[0].record.total_size, 30);
+        assert_eq!(index.files().count(), 2);
+        assert!(index.last_scan_under("/data").is_some());
+        assert!(index.last_scan_under("/other").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unfinished_writes_leave_the_stored_root_intact() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut index = ScanIndex::open(temp_dir.path()).unwrap();
+        let files = vec![test_file("/data/a.bin", 10)];
+        index.replace_root(
+            test_record("/data", "session-1", &files),
+            test_results(files),
+        );
+        index.pending_writes().persist().await;
+
+        // A crash while writing the next generation leaves a partial directory behind
+        let root_dir = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let current = std::fs::read_to_string(root_dir.join("CURRENT")).unwrap();
+        let next = root_dir.join((current.parse::<u64>().unwrap() + 1).to_string());
+        std::fs::create_dir(&next).unwrap();
+        std::fs::write(next.join("files.jsonl"), b"{\"truncated").unwrap();
+        // as does a crash before a new root's first generation is switched in
+        std::fs::create_dir_all(temp_dir.path().join("new-root/1")).unwrap();
+
+        let mut index = ScanIndex::open(temp_dir.path()).unwrap();
+        assert_eq!(index.get("/data").unwrap().record.session_id, "session-1");
+        assert_eq!(index.roots().count(), 1);
+
+        // The next write replaces it and cleans up the generation it supersedes
+        let files = vec![test_file("/data/b.bin", 5)];
+        index.replace_root(
+            test_record("/data", "session-2", &files),
+            test_results(files),
+        );
+        index.pending_writes().persist().await;
+        let index = ScanIndex::open(temp_dir.path()).unwrap();
+        assert_eq!(index.get("/data").unwrap().record.session_id, "session-2");
+        let mut entries: Vec<_> = std::fs::read_dir(&root_dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        entries.sort();
+        assert_eq!(entries.len(), 2, "{:?}", entries);
+        assert!(entries.contains(&"CURRENT".to_string()));
+    }
+
+    #[test]
+    fn test_roots_match_whole_path_components() {
+        let mut index = ScanIndex::default();
+        for root in ["/home/a", "/home/ab"] {
+            let files = vec![test_file(&format!("{}/f.bin", root), 1)];
+            index.replace_root(test_record(root, root, &files), test_results(files));
+        }
+
+        let paths = ["/home/a".to_string()];
+        let roots: Vec<_> = index
+            .roots_under(Some(&paths))
+            .map(|root| root.record.root.as_str())
+            .collect();
+        assert_eq!(roots, vec!["/home/a"]);
+        assert_eq!(index.roots_under(Some(&["/home".to_string()])).count(), 2);
+        assert_eq!(
+            index.last_scan_under("/home/ab"),
+            Some(index.get("/home/ab").unwrap().record.completed_at)
+        );
+        assert!(index.last_scan_under("/home/a/f").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rescan_replaces_stale_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut index = ScanIndex::open(temp_dir.path()).unwrap();
+
+        let files = vec![test_file("/data/old.bin", 10)];
+        index.replace_root(
+            test_record("/data", "session-1", &files),
+            test_results(files),
+        );
+
+        let files = vec![test_file("/data/new.bin", 5)];
+        index.replace_root(
+            test_record("/data", "session-2", &files),
+            test_results(files),
+        );
+
+        let other = vec![test_file("/home/x.bin", 1)];
+        index.replace_root(
+            test_record("/home", "session-3", &other),
+            test_results(other),
+        );
+        index.pending_writes().persist().await;
+
+        let index = ScanIndex::open(temp_dir.path()).unwrap();
+        let paths: Vec<_> = index
+            .roots_under(Some(&["/data".to_string()]))
+            .flat_map(|root| root.files.iter().map(|f| f.path.clone()))
+            .collect();
+        assert_eq!(paths, vec!["/data/new.bin".to_string()]);
+        assert_eq!(index.files().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_changes_update_the_stored_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut index = ScanIndex::open(temp_dir.path()).unwrap();
+
+        let with_inode = |path: &str, size: u64, inode: u64| FileInfo {
+            inode: Some(inode),
+            device: Some(1),
+            hash: Some("same".to_string()),
+            ..test_file(path, size)
+        };
+        let files = vec![
+            with_inode("/data/a.bin", 10, 1),
+            with_inode("/data/b.bin", 10, 2),
+            with_inode("/data/c.bin", 10, 3),
+        ];
+        let mut results = test_results(files.clone());
+        results.duplicate_groups = vec![DuplicateGroup::new("same".to_string(), files.clone())];
+        for dir in ["/data", "/data/keep", "/data/old", "/data/old/deep"] {
+            results.directories.insert(dir.to_string(), Utc::now());
+        }
+        index.replace_root(test_record("/data", "session-1", &files), results);
+
+        let root_size = |index: &ScanIndex| {
+            let root = index.get("/data").unwrap();
+            let entry = root.files.node(std::path::Path::new("/data")).unwrap();
+            root.subtree_sizes(SizeBasis::Apparent)[entry.id() as usize].size
+        };
+        assert_eq!(root_size(&index), 30);
+
+        // a.bin moves into a new directory, c.bin is deleted, d.bin appears
+        let moved = FileInfo {
+            modified: files[0].modified,
+            hash: None,
+            ..with_inode("/data/sub/a.bin", 10, 1)
+        };
+        let changes = index
+            .update_root(
+                "/data",
+                vec![
+                    FileChange::Remove("/data/a.bin".to_string()),
+                    FileChange::Remove("/data/c.bin".to_string()),
+                    FileChange::Remove("/data/old".to_string()),
+                    FileChange::Upsert(Box::new(moved)),
+                    FileChange::Upsert(Box::new(test_file("/data/d.bin", 5))),
+                ],
+            )
+            .unwrap();
+        assert_eq!(changes.renamed.len(), 1);
+        assert_eq!(changes.renamed[0].to, "/data/sub/a.bin");
+        assert_eq!(changes.removed, vec!["/data/c.bin".to_string()]);
+        assert_eq!(changes.added, vec!["/data/d.bin".to_string()]);
+        assert!(index.update_root("/other", Vec::new()).is_none());
+        assert_eq!(root_size(&index), 25);
+        // Removing a directory forgets everything walked below it
+        let mut walked: Vec<_> = index.get("/data").unwrap().directories.keys().collect();
+        walked.sort();
+        assert_eq!(walked, vec!["/data/keep"]);
+        index.pending_writes().persist().await;
+
+        // Persisted with updated totals, duplicates following the rename
+        let index = ScanIndex::open(temp_dir.path()).unwrap();
+        let root = index.get("/data").unwrap();
+        assert_eq!(root.record.total_files, 3);
+        assert_eq!(root.record.total_size, 25);
+        let group = &root.duplicate_groups[0];
+        let mut paths: Vec<_> = group.files.iter().map(|f| f.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["/data/b.bin", "/data/sub/a.bin"]);
+        assert_eq!(group.potential_savings, 10);
+        let renamed = root
+            .files
+            .find(std::path::Path::new("/data/sub/a.bin"))
+            .unwrap();
+        assert_eq!(renamed.hash(), Some("same"));
+        assert!(root
+            .files
+            .find(std::path::Path::new("/data/c.bin"))
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_changes_are_logged_until_the_root_is_rewritten() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut index = ScanIndex::open(temp_dir.path()).unwrap();
+        let files = vec![test_file("/data/a.bin", 10)];
+        index.replace_root(
+            test_record("/data", "session-1", &files),
+            test_results(files),
+        );
+        index.pending_writes().persist().await;
+        let root_dir = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let current = || std::fs::read_to_string(root_dir.join("CURRENT")).unwrap();
+        let generation = current();
+
+        // A change is appended to the log of the stored generation
+        let upsert = |path: &str| vec![FileChange::Upsert(Box::new(test_file(path, 5)))];
+        index.update_root("/data", upsert("/data/b.bin")).unwrap();
+        index.pending_writes().persist().await;
+        assert_eq!(current(), generation);
+        let log = root_dir.join(&generation).join("changes.jsonl");
+        assert_eq!(std::fs::read_to_string(&log).unwrap().lines().count(), 1);
+
+        // A batch torn by a crash is dropped, the ones before it are replayed
+        let mut torn = std::fs::OpenOptions::new().append(true).open(&log).unwrap();
+        std::io::Write::write_all(&mut torn, b"[{\"Upsert\":{\"path\":").unwrap();
+        let mut index = ScanIndex::open(temp_dir.path()).unwrap();
+        let stored = |index: &ScanIndex, path: &str| {
+            index
+                .get("/data")
+                .unwrap()
+                .files
+                .find(std::path::Path::new(path))
+                .is_some()
+        };
+        assert!(stored(&index, "/data/b.bin"));
+
+        // and the next change writes the root out in full, with an empty log
+        index.update_root("/data", upsert("/data/c.bin")).unwrap();
+        index.pending_writes().persist().await;
+        assert_ne!(current(), generation);
+        assert!(!root_dir.join(current()).join("changes.jsonl").exists());
+        let index = ScanIndex::open(temp_dir.path()).unwrap();
+        for path in ["/data/a.bin", "/data/b.bin", "/data/c.bin"] {
+            assert!(stored(&index, path), "{}", path);
+        }
+        assert_eq!(index.get("/data").unwrap().record.total_files, 3);
+    }
+
+    #[tokio::test]
+    async fn test_completed_session_is_published() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("one.txt"), b"first").unwrap();
+        std::fs::write(temp_dir.path().join("two.txt"), b"second").unwrap();
+
+        let storage = Arc::new(RwLock::new(SimpleStorage::default()));
+        let websocket_manager = Arc::new(WebSocketManager::new());
+        let mut events = websocket_manager.subscribe();
+        let analyzer = DiskAnalyzer::with_storage(websocket_manager, storage.clone());
+
+        let root = temp_dir.path().to_string_lossy().to_string();
+        let session_id = analyzer
+            .create_scan_session(root.clone(), ScanType::Quick, test_config())
+            .await
+            .unwrap();
+        analyzer.start_scan_session(&session_id).await.unwrap();
+
+        let storage = storage.read().await;
+        let indexed = storage
+            .index
+            .roots()
+            .next()
+            .expect("root should be indexed");
+        assert_eq!(indexed.record.root, root);
+        assert_eq!(indexed.record.session_id, session_id);
+        assert_eq!(indexed.files.len(), 2);
+
+        let mut announced = false;
+        while let Ok(message) = events.try_recv() {
+            announced |= message.message_type == "scan_results_available";
+        }
+        assert!(announced, "results available event should be emitted");
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use crate::disk_analyzer::ScanConfig;
+    use crate::tests::{scanned_paths, test_config};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_depth_and_size_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::write(root.join("top.txt"), vec![0u8; 10]).unwrap();
+        std::fs::write(root.join("a/mid.txt"), vec![0u8; 100]).unwrap();
+        std::fs::write(root.join("a/b/deep.txt"), vec![0u8; 1000]).unwrap();
+
+        let config = ScanConfig {
+            max_depth: Some(1),
+            ..test_config()
+        };
+        assert_eq!(
+            scanned_paths(root, config).await,
+            vec!["a/mid.txt", "top.txt"]
+        );
+
+        let config = ScanConfig {
+            min_file_size: Some(50),
+            max_file_size: Some(500),
+            ..test_config()
+        };
+        assert_eq!(scanned_paths(root, config).await, vec!["a/mid.txt"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlink_loops_are_walked_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir(root.join("dir")).unwrap();
+        std::fs::write(root.join("dir/file.txt"), b"data").unwrap();
+        std::os::unix::fs::symlink(root, root.join("dir/loop")).unwrap();
+
+        // Symlinks are skipped unless asked for
+        assert_eq!(
+            scanned_paths(root, test_config()).await,
+            vec!["dir/file.txt"]
+        );
+
+        let config = ScanConfig {
+            follow_symlinks: true,
+            ..test_config()
+        };
+        assert_eq!(scanned_paths(root, config).await, vec!["dir/file.txt"]);
+    }
+}
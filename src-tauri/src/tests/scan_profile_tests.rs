@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod tests {
+    use crate::disk_analyzer::{DuplicateStrategy, ScanType};
+    use crate::scan_profiles::{
+        builtin_profiles, default_config, default_exclusions, ProfileStore, ScanProfile,
+        HOME_QUICK, MEDIA_DEEP, PROFILES_FILE,
+    };
+    use tempfile::TempDir;
+
+    fn test_profile(name: &str) -> ScanProfile {
+        ScanProfile {
+            id: String::new(),
+            name: name.to_string(),
+            description: String::new(),
+            builtin: false,
+            roots: vec!["/data/photos".to_string()],
+            scan_type: ScanType::Deep,
+            config: default_config(),
+        }
+    }
+
+    #[test]
+    fn test_profiles_are_saved_and_reloaded() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(PROFILES_FILE);
+
+        let (photos_id, archive_id) = {
+            let mut store = ProfileStore::open(&path).unwrap();
+            let mut photos = test_profile("Photos");
+            photos.config.min_file_size = Some(4096);
+            photos.config.duplicate_strategy = DuplicateStrategy::HashOnly;
+            let photos = store.save(photos).unwrap();
+            assert!(!photos.id.is_empty());
+            let archive = store.save(test_profile("archive")).unwrap();
+
+            // Saving with an existing id updates that profile
+            let renamed = ScanProfile {
+                name: "  Photos and videos ".to_string(),
+                ..photos.clone()
+            };
+            store.save(renamed).unwrap();
+            (photos.id, archive.id)
+        };
+
+        let mut store = ProfileStore::open(&path).unwrap();
+        let names: Vec<_> = store.list().into_iter().map(|p| p.name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Home quick",
+                "Media deep",
+                "Developer caches",
+                "archive",
+                "Photos and videos"
+            ]
+        );
+        let photos = store.get(&photos_id).unwrap();
+        assert_eq!(photos.config.min_file_size, Some(4096));
+        assert!(matches!(
+            photos.config.duplicate_strategy,
+            DuplicateStrategy::HashOnly
+        ));
+
+        assert!(store.delete(&archive_id).unwrap());
+        assert!(!store.delete(&archive_id).unwrap());
+        assert!(ProfileStore::open(&path)
+            .unwrap()
+            .get(&archive_id)
+            .is_none());
+    }
+
+    #[test]
+    fn test_invalid_profiles_are_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut store = ProfileStore::open(temp_dir.path().join(PROFILES_FILE)).unwrap();
+
+        // Presets can be copied but not changed
+        let preset = store.get(HOME_QUICK).unwrap();
+        assert!(preset.builtin);
+        assert!(store.save(preset.clone()).is_err());
+        assert!(store.delete(MEDIA_DEEP).is_err());
+        let copy = store
+            .save(ScanProfile {
+                id: String::new(),
+                name: "My home".to_string(),
+                ..preset
+            })
+            .unwrap();
+        assert!(!copy.builtin);
+
+        assert!(store.save(test_profile(" ")).is_err());
+        let mut no_roots = test_profile("Nothing");
+        no_roots.roots.clear();
+        assert!(store.save(no_roots).is_err());
+        let mut bad_pattern = test_profile("Bad pattern");
+        bad_pattern.config.exclude_patterns = vec!["[z-a]".to_string()];
+        assert!(store.save(bad_pattern).is_err());
+        assert_eq!(store.list().len(), builtin_profiles().len() + 1);
+    }
+
+    #[test]
+    fn test_defaults_fit_the_platform() {
+        let exclusions = default_exclusions();
+        #[cfg(target_os = "windows")]
+        assert!(exclusions.contains(&"/Windows".to_string()));
+        #[cfg(target_os = "linux")]
+        {
+            assert!(exclusions.contains(&"/proc".to_string()));
+            assert!(!exclusions.contains(&"/Windows".to_string()));
+        }
+
+        let developer_caches = builtin_profiles().pop().unwrap();
+        assert!(developer_caches.config.include_hidden);
+        assert!(!developer_caches
+            .config
+            .exclude_patterns
+            .contains(&"node_modules".to_string()));
+        assert!(default_config()
+            .exclude_patterns
+            .contains(&"node_modules".to_string()));
+    }
+}
@@ -0,0 +1,298 @@
+#[cfg(test)]
+mod tests {
+    use crate::app_config::AppConfig;
+    use crate::app_state::{AppState, SimpleStorage};
+    use crate::commands::home_commands::ActivityType;
+    use crate::disk_analyzer::ScanType;
+    use crate::scheduler::{
+        CleanupPolicy, JobAction, JobRun, JobRunStatus, JobSchedule, JobStore, JobTrigger,
+        MissedRunPolicy, ScheduledJob, Scheduler,
+    };
+    use crate::tests::test_config;
+    use crate::watcher::WatchManager;
+    use crate::websocket::WebSocketManager;
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+    use tokio::sync::RwLock;
+
+    fn job(name: &str, every_secs: u64) -> ScheduledJob {
+        ScheduledJob {
+            id: String::new(),
+            name: name.to_string(),
+            schedule: JobSchedule::Interval { every_secs },
+            action: JobAction::RefreshDuplicates { roots: vec![] },
+            paused: false,
+            missed_runs: MissedRunPolicy::RunOnce,
+            jitter_secs: 0,
+            next_run: None,
+            history: vec![],
+            running: false,
+        }
+    }
+
+    fn time(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 2, hour, minute, 0).unwrap()
+    }
+
+    /// App state with in-memory storage and no config file
+    fn app_state() -> Arc<AppState> {
+        let config = AppConfig::default();
+        let storage = Arc::new(RwLock::new(SimpleStorage::default()));
+        let websocket_manager = Arc::new(WebSocketManager::new());
+        Arc::new(AppState {
+            watcher: WatchManager::new(
+                config.watcher.clone(),
+                storage.clone(),
+                websocket_manager.clone(),
+            ),
+            scheduler: Scheduler::new(config.scheduler.clone()),
+            config,
+            storage,
+            current_analyzer: Arc::new(RwLock::new(None)),
+            websocket_manager,
+            activity_log: Arc::new(RwLock::new(Default::default())),
+            throttle: Default::default(),
+            hash_cache: Default::default(),
+        })
+    }
+
+    #[test]
+    fn test_next_runs_follow_the_schedule_with_jitter() {
+        let hourly = JobSchedule::Interval { every_secs: 3600 };
+        assert_eq!(hourly.next_after(time(10, 7)), Some(time(11, 7)));
+
+        // Quarter hours fall on the same minutes in every common time zone
+        let quarterly = JobSchedule::Cron {
+            expression: "*/15 * * * *".to_string(),
+        };
+        assert!(quarterly.validate().is_ok());
+        assert_eq!(quarterly.next_after(time(10, 7)), Some(time(10, 15)));
+        assert_eq!(quarterly.next_after(time(10, 15)), Some(time(10, 30)));
+
+        let invalid = [
+            JobSchedule::Interval { every_secs: 5 },
+            JobSchedule::Cron {
+                expression: "61 * * * *".to_string(),
+            },
+            JobSchedule::Cron {
+                expression: "0 0 3 * * *".to_string(),
+            },
+        ];
+        for schedule in invalid {
+            assert!(schedule.validate().is_err(), "{:?}", schedule);
+        }
+
+        let mut store = JobStore::default();
+        for _ in 0..20 {
+            let jittered = store
+                .create(
+                    ScheduledJob {
+                        jitter_secs: 30,
+                        ..job("Jittered", 3600)
+                    },
+                    time(10, 0),
+                )
+                .unwrap();
+            let next_run = jittered.next_run.unwrap();
+            assert!(next_run >= time(11, 0));
+            assert!(next_run <= time(11, 0) + Duration::seconds(30));
+        }
+    }
+
+    #[test]
+    fn test_due_jobs_follow_the_missed_run_policy() {
+        let mut store = JobStore::default();
+        let run_once = store.create(job("Run once", 3600), time(10, 0)).unwrap();
+        let skip = store
+            .create(
+                ScheduledJob {
+                    missed_runs: MissedRunPolicy::Skip,
+                    ..job("Skip", 3600)
+                },
+                time(10, 0),
+            )
+            .unwrap();
+        let paused = store.create(job("Paused", 3600), time(10, 0)).unwrap();
+        store.set_paused(&paused.id, true, time(10, 0)).unwrap();
+        let grace = Duration::seconds(60);
+        let nothing_running = HashSet::new();
+
+        let due = store
+            .take_due(time(10, 30), grace, &nothing_running, 20)
+            .unwrap();
+        assert!(due.run.is_empty() && due.skipped.is_empty());
+
+        // On time, both run as scheduled
+        let due = store
+            .take_due(time(11, 0), grace, &nothing_running, 20)
+            .unwrap();
+        let triggers: Vec<_> = due.run.iter().map(|(j, t)| (j.id.clone(), *t)).collect();
+        assert_eq!(
+            triggers,
+            vec![
+                (run_once.id.clone(), JobTrigger::Scheduled),
+                (skip.id.clone(), JobTrigger::Scheduled)
+            ]
+        );
+        assert_eq!(store.get(&run_once.id).unwrap().next_run, Some(time(12, 0)));
+
+        // Hours later, as after the app was closed
+        let due = store
+            .take_due(time(15, 20), grace, &nothing_running, 20)
+            .unwrap();
+        assert_eq!(due.run.len(), 1);
+        assert_eq!(due.run[0].0.id, run_once.id);
+        assert_eq!(due.run[0].1, JobTrigger::Missed);
+        assert_eq!(due.skipped.len(), 1);
+        let skipped = store.get(&skip.id).unwrap();
+        assert_eq!(skipped.history[0].status, JobRunStatus::Skipped);
+        assert_eq!(skipped.history[0].trigger, JobTrigger::Missed);
+        assert_eq!(skipped.next_run, Some(time(16, 20)));
+
+        // A job still running skips its turn
+        let running = HashSet::from([run_once.id.clone()]);
+        let due = store.take_due(time(16, 20), grace, &running, 20).unwrap();
+        assert_eq!(due.run.len(), 1);
+        assert_eq!(due.run[0].0.id, skip.id);
+        assert_eq!(due.skipped[0].0.id, run_once.id);
+
+        // Resuming starts from now rather than catching up
+        let resumed = store.set_paused(&paused.id, false, time(17, 0)).unwrap();
+        assert_eq!(resumed.next_run, Some(time(18, 0)));
+    }
+
+    #[test]
+    fn test_jobs_persist_with_a_bounded_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("schedules.json");
+
+        let mut store = JobStore::open(&path).unwrap();
+        assert!(store.create(job("  ", 3600), time(10, 0)).is_err());
+        let created = store
+            .create(
+                ScheduledJob {
+                    schedule: JobSchedule::Cron {
+                        expression: "30 3 * * sun".to_string(),
+                    },
+                    ..job("Weekly", 0)
+                },
+                time(10, 0),
+            )
+            .unwrap();
+        for minute in 0..3 {
+            let run = JobRun {
+                started_at: time(11, minute),
+                finished_at: time(11, minute),
+                trigger: JobTrigger::Manual,
+                status: JobRunStatus::Succeeded,
+                message: format!("Run {}", minute),
+            };
+            store.record_run(&created.id, run, 2).unwrap();
+        }
+        store.set_paused(&created.id, true, time(12, 0)).unwrap();
+
+        let reopened = JobStore::open(&path).unwrap();
+        let stored = reopened.get(&created.id).unwrap();
+        assert!(stored.paused);
+        assert_eq!(stored.next_run, created.next_run);
+        let messages: Vec<_> = stored.history.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(messages, ["Run 2", "Run 1"]);
+
+        let mut reopened = reopened;
+        assert!(reopened.delete(&created.id).unwrap());
+        assert!(!reopened.delete(&created.id).unwrap());
+        assert!(JobStore::open(&path).unwrap().list().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_job_deletes_old_matching_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir(root.join("logs")).unwrap();
+        std::fs::write(root.join("logs/old.log"), b"old").unwrap();
+        std::fs::write(root.join("logs/new.log"), b"new").unwrap();
+        std::fs::write(root.join("old.txt"), b"kept").unwrap();
+        let forty_days_ago =
+            std::time::SystemTime::now() - std::time::Duration::from_secs(40 * 86400);
+        for name in ["logs/old.log", "old.txt"] {
+            let file = std::fs::File::options()
+                .write(true)
+                .open(root.join(name))
+                .unwrap();
+            file.set_modified(forty_days_ago).unwrap();
+        }
+
+        let app_state = app_state();
+        let root_str = root.to_string_lossy().to_string();
+        let analyzer = app_state.analyzer().await;
+        let session_id = analyzer
+            .create_scan_session(root_str.clone(), ScanType::Quick, test_config())
+            .await
+            .unwrap();
+        analyzer.start_scan_session(&session_id).await.unwrap();
+
+        let created = app_state
+            .storage
+            .write()
+            .await
+            .schedules
+            .create(
+                ScheduledJob {
+                    action: JobAction::Cleanup(CleanupPolicy {
+                        root: root_str.clone(),
+                        patterns: vec!["*.log".to_string()],
+                        older_than_days: 30,
+                        move_to_trash: false,
+                    }),
+                    ..job("Old logs", 86400)
+                },
+                Utc::now(),
+            )
+            .unwrap();
+
+        let handle = app_state
+            .scheduler
+            .run_now(&app_state, &created.id)
+            .await
+            .unwrap();
+        let run = handle.await.unwrap();
+        assert_eq!(run.status, JobRunStatus::Succeeded, "{}", run.message);
+        assert_eq!(run.trigger, JobTrigger::Manual);
+
+        assert!(!root.join("logs/old.log").exists());
+        assert!(root.join("logs/new.log").exists());
+        assert!(root.join("old.txt").exists());
+        let storage = app_state.storage.read().await;
+        let indexed = storage.index.get(&root_str).unwrap();
+        assert!(!indexed.files.iter().any(|f| f.path.ends_with("old.log")));
+        assert_eq!(indexed.record.total_size, 7);
+        assert_eq!(storage.schedules.get(&created.id).unwrap().history.len(), 1);
+        drop(storage);
+
+        let activities = app_state.activity_log.read().await;
+        assert!(activities
+            .values()
+            .any(|a| matches!(a.activity_type, ActivityType::ScheduledJobRun)
+                && a.status == "completed"));
+        drop(activities);
+
+        // A run that never finishes does not leave the job marked as running
+        let handle = app_state
+            .scheduler
+            .run_now(&app_state, &created.id)
+            .await
+            .unwrap();
+        assert!(app_state.scheduler.is_running(&created.id));
+        handle.abort();
+        assert!(handle.await.unwrap_err().is_cancelled());
+        assert!(!app_state.scheduler.is_running(&created.id));
+
+        assert!(app_state
+            .scheduler
+            .run_now(&app_state, "missing")
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod tests {
+    use crate::disk_analyzer::{DiskAnalyzer, ScanType};
+    use crate::file_system::{get_file_info, SizeBasis};
+    use crate::tests::test_config;
+    use crate::websocket::WebSocketManager;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_unknown_allocation_falls_back_to_apparent_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("file.txt");
+        std::fs::write(&path, b"data").unwrap();
+
+        let mut file = get_file_info(&path.to_string_lossy()).await.unwrap();
+        file.allocated_size = None;
+        assert_eq!(file.size_on(SizeBasis::Allocated), 4);
+        assert_eq!(file.slack(), 0);
+
+        file.allocated_size = Some(4096);
+        assert_eq!(file.size_on(SizeBasis::Apparent), 4);
+        assert_eq!(file.size_on(SizeBasis::Allocated), 4096);
+        assert_eq!(file.slack(), 4092);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_sparse_files_report_allocated_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let sparse = std::fs::File::create(root.join("disk.img")).unwrap();
+        sparse.set_len(64 * 1024 * 1024).unwrap();
+        drop(sparse);
+
+        let analyzer = DiskAnalyzer::new(Arc::new(WebSocketManager::new()));
+        let session_id = analyzer
+            .create_scan_session(
+                root.to_string_lossy().to_string(),
+                ScanType::Quick,
+                test_config(),
+            )
+            .await
+            .unwrap();
+        analyzer.start_scan_session(&session_id).await.unwrap();
+        let results = analyzer
+            .get_scan_session(&session_id)
+            .await
+            .and_then(|s| s.results)
+            .expect("scan should produce results");
+
+        assert_eq!(results.total_size, 64 * 1024 * 1024);
+        assert!(results.total_allocated_size < results.total_size);
+        let image = results.files.iter().next().unwrap();
+        assert_eq!(image.allocated_size, Some(results.total_allocated_size));
+    }
+}
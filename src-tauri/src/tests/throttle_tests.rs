@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod tests {
+    use crate::app_config::ThrottleConfig;
+    use crate::disk_analyzer::control::ScanControl;
+    use crate::disk_analyzer::throttle::{parse_diskstats, Throttle, TokenBucket};
+    use crate::disk_analyzer::{DiskAnalyzer, ScanType};
+    use crate::tests::test_config;
+    use crate::websocket::WebSocketManager;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use tempfile::TempDir;
+
+    fn limits(max_bytes_per_sec: u64, max_files_per_sec: u64) -> ThrottleConfig {
+        ThrottleConfig {
+            max_bytes_per_sec,
+            max_files_per_sec,
+            ..ThrottleConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_token_bucket_allows_a_second_of_burst_then_paces() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::default();
+        assert_eq!(bucket.take(100, 100, start), Duration::ZERO);
+        assert_eq!(bucket.take(50, 100, start), Duration::from_millis(500));
+
+        // Half a second later the debt is paid, and the next 100 owe a second
+        let later = start + Duration::from_millis(500);
+        assert_eq!(bucket.take(100, 100, later), Duration::from_secs(1));
+
+        // Idle time refills at most one second's worth
+        let much_later = later + Duration::from_secs(60);
+        assert_eq!(bucket.take(100, 100, much_later), Duration::ZERO);
+        assert!(bucket.take(1, 100, much_later) > Duration::ZERO);
+
+        assert_eq!(
+            TokenBucket::default().take(u64::MAX, 0, start),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_diskstats_count_whole_disks_once() {
+        let stats = "\
+   7       0 loop0 120 0 4000 10 0 0 0 0 0 20 10 0 0 0 0
+   8       0 sda 1000 10 2000 500 300 20 1000 400 0 800 900 0 0 0 0
+   8       1 sda1 900 10 1900 450 300 20 1000 400 0 700 850 0 0 0 0
+ 259       0 nvme0n1 50 0 100 5 10 0 300 2 0 6 7 0 0 0 0
+ 259       1 nvme0n1p1 50 0 100 5 10 0 300 2 0 6 7 0 0 0 0
+ 253       0 dm-0 700 0 1500 300 300 0 1000 400 0 700 700 0 0 0 0
+  65     160 sdaa 1 0 8 1 1 0 8 1 0 2 2 0 0 0 0
+";
+        // (2000 + 1000) + (100 + 300) + (8 + 8) sectors of 512 bytes
+        assert_eq!(parse_diskstats(stats), 3416 * 512);
+        assert_eq!(parse_diskstats(""), 0);
+    }
+
+    #[tokio::test]
+    async fn test_walker_keeps_to_the_file_rate_and_follows_live_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..60 {
+            std::fs::write(temp_dir.path().join(format!("{}.txt", i)), b"x").unwrap();
+        }
+        let root = temp_dir.path().to_string_lossy().to_string();
+        let throttle = Arc::new(Throttle::new(limits(0, 40)));
+        let analyzer =
+            DiskAnalyzer::new(Arc::new(WebSocketManager::new())).with_throttle(throttle.clone());
+
+        // 40 files fit in the first second's burst, the rest take half a second
+        let started = Instant::now();
+        let session_id = analyzer
+            .create_scan_session(root.clone(), ScanType::Quick, test_config())
+            .await
+            .unwrap();
+        analyzer.start_scan_session(&session_id).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(400));
+        let session = analyzer.get_scan_session(&session_id).await.unwrap();
+        assert_eq!(session.results.unwrap().total_files, 60);
+
+        // At one file a second this scan would take a minute
+        throttle.set_settings(limits(0, 1));
+        let session_id = analyzer
+            .create_scan_session(root, ScanType::Quick, test_config())
+            .await
+            .unwrap();
+        let scan = {
+            let analyzer = analyzer.clone();
+            let session_id = session_id.clone();
+            tokio::spawn(async move { analyzer.start_scan_session(&session_id).await })
+        };
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let lifted = Instant::now();
+        throttle.set_settings(ThrottleConfig::default());
+        tokio::time::timeout(Duration::from_secs(5), scan)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert!(lifted.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_hash_reads_wait_until_cancelled() {
+        let throttle = Throttle::new(limits(1024, 0));
+        let control = Arc::new(ScanControl::default());
+        throttle.wait(0, 1024, &control);
+
+        // A hundred seconds of debt, cut short by cancelling
+        let cancel = {
+            let control = control.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(200));
+                control.cancel();
+            })
+        };
+        let started = Instant::now();
+        throttle.wait(0, 100 * 1024, &control);
+        assert!(started.elapsed() < Duration::from_secs(2));
+        cancel.join().unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_idle_mode_lowers_the_worker_io_priority() {
+        const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+        const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+        let io_class = || {
+            // SAFETY: plain syscall on the calling thread
+            unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, 0) >> 13 }
+        };
+
+        std::thread::spawn(move || {
+            let control = ScanControl::default();
+            let throttle = Throttle::new(ThrottleConfig {
+                idle_mode: true,
+                busy_bytes_per_sec: u64::MAX, // Never backs off
+                ..ThrottleConfig::default()
+            });
+            throttle.wait(1, 0, &control);
+            assert_eq!(io_class(), IOPRIO_CLASS_IDLE);
+            assert!(!throttle.status().backing_off);
+
+            throttle.set_settings(ThrottleConfig::default());
+            throttle.wait(1, 0, &control);
+            assert_ne!(io_class(), IOPRIO_CLASS_IDLE);
+        })
+        .join()
+        .unwrap();
+    }
+}
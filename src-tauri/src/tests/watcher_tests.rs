@@ -0,0 +1,206 @@
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use crate::app_config::WatcherConfig;
+    use crate::app_state::SimpleStorage;
+    use crate::disk_analyzer::{DiskAnalyzer, ScanConfig, ScanType};
+    use crate::tests::test_config;
+    use crate::watcher::{WatchManager, WatchMode};
+    use crate::websocket::WebSocketManager;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use tokio::sync::RwLock;
+
+    struct Harness {
+        storage: Arc<RwLock<SimpleStorage>>,
+        websocket_manager: Arc<WebSocketManager>,
+        analyzer: DiskAnalyzer,
+    }
+
+    async fn scan(root: &Path) -> Harness {
+        let storage = Arc::new(RwLock::new(SimpleStorage::default()));
+        let websocket_manager = Arc::new(WebSocketManager::new());
+        let analyzer = DiskAnalyzer::with_storage(websocket_manager.clone(), storage.clone());
+        let session_id = analyzer
+            .create_scan_session(
+                root.to_string_lossy().to_string(),
+                ScanType::Quick,
+                ScanConfig {
+                    exclude_patterns: vec!["*.tmp".to_string()],
+                    ..test_config()
+                },
+            )
+            .await
+            .unwrap();
+        analyzer.start_scan_session(&session_id).await.unwrap();
+        Harness {
+            storage,
+            websocket_manager,
+            analyzer,
+        }
+    }
+
+    /// Indexed paths under `root`, relative and sorted
+    async fn indexed(storage: &RwLock<SimpleStorage>, root: &Path) -> Vec<String> {
+        let storage = storage.read().await;
+        let indexed = storage.index.get(&root.to_string_lossy()).unwrap();
+        let mut paths: Vec<_> = indexed
+            .files
+            .iter()
+            .filter(|f| !f.is_directory)
+            .map(|f| {
+                Path::new(&f.path)
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    async fn wait_for(storage: &RwLock<SimpleStorage>, root: &Path, expected: &[&str]) {
+        for _ in 0..100 {
+            if indexed(storage, root).await == expected {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(indexed(storage, root).await, expected);
+    }
+
+    #[tokio::test]
+    async fn test_changes_reach_the_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs/a.txt"), b"a").unwrap();
+        std::fs::write(root.join("b.txt"), b"b").unwrap();
+
+        let harness = scan(root).await;
+        let mut messages = harness.websocket_manager.subscribe();
+        let config = WatcherConfig {
+            enabled: true,
+            coalesce_ms: 100,
+            ..WatcherConfig::default()
+        };
+        let watcher = WatchManager::new(
+            config,
+            harness.storage.clone(),
+            harness.websocket_manager.clone(),
+        );
+        let root_str = root.to_string_lossy().to_string();
+        let status = watcher
+            .watch(&root_str, harness.analyzer.clone())
+            .await
+            .unwrap();
+        assert_eq!(status.mode, WatchMode::Live);
+        assert_eq!(status.watched_directories, 2);
+
+        // Created, excluded, written to and in a new directory
+        std::fs::write(root.join("c.txt"), b"c").unwrap();
+        std::fs::write(root.join("scratch.tmp"), b"x").unwrap();
+        std::fs::write(root.join("b.txt"), b"bigger").unwrap();
+        std::fs::create_dir_all(root.join("new/deep")).unwrap();
+        std::fs::write(root.join("new/deep/d.txt"), b"d").unwrap();
+        wait_for(
+            &harness.storage,
+            root,
+            &["b.txt", "c.txt", "docs/a.txt", "new/deep/d.txt"],
+        )
+        .await;
+        let total_size = harness
+            .storage
+            .read()
+            .await
+            .index
+            .get(&root_str)
+            .unwrap()
+            .record
+            .total_size;
+        assert_eq!(total_size, 9);
+
+        // Moved directories keep being watched under their new name
+        std::fs::rename(root.join("docs"), root.join("papers")).unwrap();
+        std::fs::remove_file(root.join("c.txt")).unwrap();
+        wait_for(
+            &harness.storage,
+            root,
+            &["b.txt", "new/deep/d.txt", "papers/a.txt"],
+        )
+        .await;
+        std::fs::write(root.join("papers/e.txt"), b"e").unwrap();
+        wait_for(
+            &harness.storage,
+            root,
+            &["b.txt", "new/deep/d.txt", "papers/a.txt", "papers/e.txt"],
+        )
+        .await;
+
+        let renamed = loop {
+            let message = tokio::time::timeout(Duration::from_secs(5), messages.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            if message.message_type == "index_changed" && message.data["renamed"][0].is_object() {
+                break message;
+            }
+        };
+        assert!(renamed.data["renamed"][0]["to"]
+            .as_str()
+            .unwrap()
+            .ends_with("papers/a.txt"));
+        assert!(watcher.status()[0].last_change.is_some());
+
+        // Nothing is applied once unwatched
+        assert!(watcher.unwatch(&root_str));
+        assert!(watcher.status().is_empty());
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        std::fs::write(root.join("late.txt"), b"late").unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(!indexed(&harness.storage, root)
+            .await
+            .contains(&"late.txt".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_polling_past_the_watch_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("a.txt"), b"a").unwrap();
+
+        let harness = scan(root).await;
+        let config = WatcherConfig {
+            enabled: true,
+            coalesce_ms: 100,
+            max_watches: 0,
+            poll_interval_secs: 1,
+        };
+        let watcher = WatchManager::new(
+            config,
+            harness.storage.clone(),
+            harness.websocket_manager.clone(),
+        );
+        let root_str = root.to_string_lossy().to_string();
+        let status = watcher
+            .watch(&root_str, harness.analyzer.clone())
+            .await
+            .unwrap();
+        assert_eq!(status.mode, WatchMode::Polling);
+        assert_eq!(status.watched_directories, 0);
+
+        // Picked up by the next incremental rescan
+        std::fs::write(root.join("b.txt"), b"b").unwrap();
+        wait_for(&harness.storage, root, &["a.txt", "b.txt"]).await;
+        watcher.unwatch(&root_str);
+
+        let unscanned = TempDir::new().unwrap();
+        let unscanned = unscanned.path().to_string_lossy().to_string();
+        assert!(watcher
+            .watch(&unscanned, harness.analyzer.clone())
+            .await
+            .is_err());
+    }
+}
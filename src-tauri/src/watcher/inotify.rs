@@ -0,0 +1,191 @@
+use super::{WatchBackend, WatchError, WatchEvent};
+use std::collections::HashMap;
+use std::ffi::{CString, OsStr};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Events that can change what a scan of the directory would find
+const WATCH_MASK: u32 = libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_MODIFY
+    | libc::IN_CLOSE_WRITE
+    | libc::IN_ATTRIB
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_DELETE_SELF
+    | libc::IN_MOVE_SELF
+    | libc::IN_ONLYDIR
+    | libc::IN_DONT_FOLLOW
+    | libc::IN_EXCL_UNLINK;
+
+const READ_BUFFER_BYTES: usize = 64 * 1024;
+
+/// Linux inotify instance with one watch per directory
+pub struct Inotify {
+    fd: OwnedFd,
+    paths: HashMap<libc::c_int, PathBuf>, // Watched directory by watch descriptor
+    watches: HashMap<PathBuf, libc::c_int>,
+    buffer: Vec<u8>,
+}
+
+impl Inotify {
+    pub fn new() -> io::Result<Self> {
+        // SAFETY: plain syscall; the descriptor is owned from here on
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+            paths: HashMap::new(),
+            watches: HashMap::new(),
+            buffer: vec![0; READ_BUFFER_BYTES],
+        })
+    }
+
+    /// Stop watching `dir` and the directories below it, which moved away
+    fn forget_below(&mut self, dir: &Path) {
+        let moved: Vec<_> = self
+            .watches
+            .keys()
+            .filter(|path| path.starts_with(dir))
+            .cloned()
+            .collect();
+        for path in moved {
+            if let Some(wd) = self.watches.remove(&path) {
+                self.paths.remove(&wd);
+                // SAFETY: `wd` belongs to this instance
+                unsafe { libc::inotify_rm_watch(self.fd.as_raw_fd(), wd) };
+            }
+        }
+    }
+
+    /// Decode the events in `len` bytes of the read buffer
+    fn parse(&mut self, len: usize, events: &mut Vec<WatchEvent>) {
+        const HEADER: usize = std::mem::size_of::<libc::inotify_event>();
+        let mut offset = 0;
+
+        while offset + HEADER <= len {
+            // SAFETY: the kernel writes whole events, each a header followed by `len` name bytes
+            let event = unsafe {
+                std::ptr::read_unaligned(
+                    self.buffer[offset..].as_ptr() as *const libc::inotify_event
+                )
+            };
+            let name_start = offset + HEADER;
+            offset = name_start + event.len as usize;
+
+            if event.mask & libc::IN_Q_OVERFLOW != 0 {
+                events.push(WatchEvent::Overflow);
+                continue;
+            }
+            if event.mask & libc::IN_IGNORED != 0 {
+                // The directory is gone or no longer watched
+                if let Some(path) = self.paths.remove(&event.wd) {
+                    self.watches.remove(&path);
+                }
+                continue;
+            }
+            let Some(dir) = self.paths.get(&event.wd) else {
+                continue;
+            };
+
+            // The name is padded with NUL bytes
+            let name = &self.buffer[name_start..offset.min(len)];
+            let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+            let path = match name.is_empty() {
+                true => dir.clone(),
+                false => dir.join(OsStr::from_bytes(name)),
+            };
+
+            if event.mask & libc::IN_MOVED_FROM != 0 && event.mask & libc::IN_ISDIR != 0 {
+                self.forget_below(&path);
+            }
+            events.push(WatchEvent::Changed(path));
+        }
+    }
+}
+
+impl WatchBackend for Inotify {
+    fn watch(&mut self, dir: &Path) -> Result<(), WatchError> {
+        if self.watches.contains_key(dir) {
+            return Ok(());
+        }
+
+        let c_path = CString::new(dir.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // SAFETY: `c_path` is a valid NUL-terminated string
+        let wd =
+            unsafe { libc::inotify_add_watch(self.fd.as_raw_fd(), c_path.as_ptr(), WATCH_MASK) };
+        if wd < 0 {
+            let e = io::Error::last_os_error();
+            return Err(match e.raw_os_error() {
+                Some(libc::ENOSPC) => WatchError::LimitReached,
+                _ => WatchError::Io(e),
+            });
+        }
+
+        // The same directory reached through another path keeps one descriptor
+        if let Some(previous) = self.paths.insert(wd, dir.to_path_buf()) {
+            self.watches.remove(&previous);
+        }
+        self.watches.insert(dir.to_path_buf(), wd);
+        Ok(())
+    }
+
+    fn is_watched(&self, dir: &Path) -> bool {
+        self.watches.contains_key(dir)
+    }
+
+    fn watched(&self) -> usize {
+        self.watches.len()
+    }
+
+    fn read_events(&mut self, timeout: Duration) -> io::Result<Vec<WatchEvent>> {
+        let mut poll_fd = libc::pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+        // SAFETY: `poll_fd` outlives the call
+        let ready = unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) };
+        if ready < 0 {
+            let e = io::Error::last_os_error();
+            return match e.kind() {
+                io::ErrorKind::Interrupted => Ok(Vec::new()),
+                _ => Err(e),
+            };
+        }
+
+        let mut events = Vec::new();
+        if ready == 0 {
+            return Ok(events);
+        }
+        loop {
+            // SAFETY: the buffer is valid for writes of its whole length
+            let read = unsafe {
+                libc::read(
+                    self.fd.as_raw_fd(),
+                    self.buffer.as_mut_ptr() as *mut libc::c_void,
+                    self.buffer.len(),
+                )
+            };
+            if read <= 0 {
+                let e = io::Error::last_os_error();
+                match e.kind() {
+                    _ if read == 0 => break,
+                    io::ErrorKind::WouldBlock => break,
+                    io::ErrorKind::Interrupted => continue,
+                    _ => return Err(e),
+                }
+            }
+            self.parse(read as usize, &mut events);
+        }
+        Ok(events)
+    }
+}
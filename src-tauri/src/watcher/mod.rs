@@ -0,0 +1,600 @@
+use crate::app_config::WatcherConfig;
+use crate::app_state::SimpleStorage;
+use crate::disk_analyzer::exclusion::ExclusionRules;
+use crate::disk_analyzer::{DiskAnalyzer, ScanConfig, ScanType};
+use crate::file_system::path_encoding;
+use crate::scan_index::{FileChange, IndexChanges};
+use crate::websocket::WebSocketManager;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Notify, RwLock};
+
+#[cfg(target_os = "linux")]
+mod inotify;
+
+/// Longest a watcher thread waits for events before checking whether it was stopped
+const READ_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Something happened on the filesystem below a watched directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    Changed(PathBuf), // Created, deleted, moved or written to
+    Overflow,         // Events were dropped; only a rescan can tell what changed
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WatchError {
+    #[error("The system limit on watched directories was reached")]
+    LimitReached,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Platform mechanism notifying changes in a set of watched directories
+pub trait WatchBackend: Send {
+    /// Watch the entries directly inside `dir`
+    fn watch(&mut self, dir: &Path) -> Result<(), WatchError>;
+    fn is_watched(&self, dir: &Path) -> bool;
+    /// Number of directories watched
+    fn watched(&self) -> usize;
+    /// Wait up to `timeout` for events, returning every event available
+    fn read_events(&mut self, timeout: Duration) -> std::io::Result<Vec<WatchEvent>>;
+}
+
+/// Watch backend of this platform, or `None` where roots can only be polled
+pub fn platform_backend() -> Option<Box<dyn WatchBackend>> {
+    #[cfg(target_os = "linux")]
+    {
+        match inotify::Inotify::new() {
+            Ok(backend) => Some(Box::new(backend)),
+            Err(e) => {
+                tracing::warn!("Could not initialize inotify, polling instead: {}", e);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchMode {
+    Live,    // Changes are applied as they happen
+    Polling, // The root is rescanned incrementally at an interval
+}
+
+/// How a scanned root is kept up to date, as returned to the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchStatus {
+    pub root: String,
+    pub mode: WatchMode,
+    pub watched_directories: usize,
+    pub last_change: Option<DateTime<Utc>>, // Last time changes were applied to the index
+}
+
+/// Shared state of one watched root
+struct RootWatch {
+    root: String,
+    mode: Mutex<WatchMode>,
+    watched: AtomicUsize,
+    last_change: Mutex<Option<DateTime<Utc>>>,
+    stop: AtomicBool,
+    stopped: Notify,
+}
+
+impl RootWatch {
+    fn status(&self) -> WatchStatus {
+        WatchStatus {
+            root: self.root.clone(),
+            mode: *self.mode.lock().unwrap(),
+            watched_directories: self.watched.load(Ordering::Relaxed),
+            last_change: *self.last_change.lock().unwrap(),
+        }
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+}
+
+/// What a watcher thread hands to the task updating the index
+enum Update {
+    Changes(Vec<FileChange>),
+    Overflow, // Rescan once, then keep watching
+    Fallback, // The root can no longer be watched; poll it instead
+}
+
+/// Keeps the index of scanned roots fresh while the app runs.
+///
+/// Each watched root gets a thread reading filesystem events, which gathers
+/// the paths touched within the coalescing window, looks at what is there
+/// now and hands the resulting changes to a task applying them to the index
+/// and announcing them as `index_changed` messages. Where the platform has no
+/// watch backend, or a root needs more watches than allowed, the root is
+/// rescanned incrementally at an interval instead.
+#[derive(Clone)]
+pub struct WatchManager {
+    config: WatcherConfig,
+    storage: Arc<RwLock<SimpleStorage>>,
+    websocket_manager: Arc<WebSocketManager>,
+    watches: Arc<Mutex<HashMap<String, Arc<RootWatch>>>>,
+}
+
+impl WatchManager {
+    pub fn new(
+        config: WatcherConfig,
+        storage: Arc<RwLock<SimpleStorage>>,
+        websocket_manager: Arc<WebSocketManager>,
+    ) -> Self {
+        Self {
+            config,
+            storage,
+            websocket_manager,
+            watches: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Start keeping an indexed root up to date; watching it again changes nothing.
+    ///
+    /// Rescans run through `analyzer`, which should store its results in the
+    /// same index. Changes made between the scan and this call are only picked
+    /// up by the next rescan.
+    pub async fn watch(&self, root: &str, analyzer: DiskAnalyzer) -> Result<WatchStatus> {
+        if let Some(watch) = self.watches.lock().unwrap().get(root) {
+            return Ok(watch.status());
+        }
+
+        let (config, mut directories) = {
+            let storage = self.storage.read().await;
+            let indexed = storage
+                .index
+                .get(root)
+                .ok_or_else(|| anyhow::anyhow!("{} has not been scanned", root))?;
+            let directories: Vec<_> = indexed
+                .directories
+                .keys()
+                .map(|dir| path_encoding::decode_path(dir))
+                .collect();
+            (indexed.record.config.clone(), directories)
+        };
+        directories.push(PathBuf::from(root));
+        directories.sort();
+        let rules = ExclusionRules::new(Path::new(root), &config)?;
+
+        let watch = Arc::new(RootWatch {
+            root: root.to_string(),
+            mode: Mutex::new(WatchMode::Live),
+            watched: AtomicUsize::new(0),
+            last_change: Mutex::new(None),
+            stop: AtomicBool::new(false),
+            stopped: Notify::new(),
+        });
+
+        // Watch the scanned directories before returning, so no later change is missed
+        let state = watch.clone();
+        let max_watches = self.config.max_watches;
+        let watcher = tokio::task::spawn_blocking(move || {
+            let mut watcher =
+                RootWatcher::new(platform_backend()?, state, config, rules, max_watches);
+            for dir in &directories {
+                watcher.add_watch(dir).ok()?;
+            }
+            Some(watcher)
+        })
+        .await?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        match watcher {
+            Some(watcher) => {
+                let coalesce = Duration::from_millis(self.config.coalesce_ms);
+                std::thread::Builder::new()
+                    .name("index-watcher".to_string())
+                    .spawn(move || watcher.run(coalesce, tx))?;
+                tracing::info!(
+                    "Watching {} ({} directories)",
+                    root,
+                    watch.watched.load(Ordering::Relaxed)
+                );
+            }
+            None => {
+                *watch.mode.lock().unwrap() = WatchMode::Polling;
+                watch.watched.store(0, Ordering::Relaxed);
+                tx.send(Update::Fallback).ok();
+                tracing::info!(
+                    "Polling {} every {} seconds",
+                    root,
+                    self.config.poll_interval_secs
+                );
+            }
+        }
+
+        // Another call may have started watching the same root meanwhile
+        if let Some(existing) = self.watches.lock().unwrap().get(root) {
+            watch.stop.store(true, Ordering::Relaxed);
+            return Ok(existing.status());
+        }
+        self.watches
+            .lock()
+            .unwrap()
+            .insert(root.to_string(), watch.clone());
+        let status = watch.status();
+        tokio::spawn(self.clone().apply_updates(watch, analyzer, rx));
+        Ok(status)
+    }
+
+    /// Stop keeping a root up to date, returning whether it was watched
+    pub fn unwatch(&self, root: &str) -> bool {
+        let Some(watch) = self.watches.lock().unwrap().remove(root) else {
+            return false;
+        };
+        watch.stop.store(true, Ordering::Relaxed);
+        watch.stopped.notify_one();
+        true
+    }
+
+    /// Status of every watched root
+    pub fn status(&self) -> Vec<WatchStatus> {
+        let mut statuses: Vec<_> = self
+            .watches
+            .lock()
+            .unwrap()
+            .values()
+            .map(|watch| watch.status())
+            .collect();
+        statuses.sort_by(|a, b| a.root.cmp(&b.root));
+        statuses
+    }
+
+    /// Apply what the watcher thread reports until the root is unwatched
+    async fn apply_updates(
+        self,
+        watch: Arc<RootWatch>,
+        analyzer: DiskAnalyzer,
+        mut rx: mpsc::UnboundedReceiver<Update>,
+    ) {
+        while let Some(update) = rx.recv().await {
+            if watch.is_stopped() {
+                return;
+            }
+            match update {
+                Update::Changes(changes) => {
+                    let (changes, writes) = {
+                        let mut storage = self.storage.write().await;
+                        let changes = storage.index.update_root(&watch.root, changes);
+                        (changes, storage.index.pending_writes())
+                    };
+                    if let Some(changes) = changes {
+                        self.announce(&watch, changes).await;
+                    }
+                    writes.persist().await;
+                }
+                Update::Overflow => self.rescan(&watch, &analyzer).await,
+                Update::Fallback => {
+                    *watch.mode.lock().unwrap() = WatchMode::Polling;
+                    watch.watched.store(0, Ordering::Relaxed);
+                    self.poll(&watch, &analyzer).await;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Rescan the root at every interval until it is unwatched
+    async fn poll(&self, watch: &RootWatch, analyzer: &DiskAnalyzer) {
+        let interval = Duration::from_secs(self.config.poll_interval_secs.max(1));
+        while !watch.is_stopped() {
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => self.rescan(watch, analyzer).await,
+                _ = watch.stopped.notified() => return,
+            }
+        }
+    }
+
+    /// Rescan the root incrementally, with the settings of its last scan
+    async fn rescan(&self, watch: &RootWatch, analyzer: &DiskAnalyzer) {
+        let Some(config) = self
+            .storage
+            .read()
+            .await
+            .index
+            .get(&watch.root)
+            .map(|indexed| indexed.record.config.clone())
+        else {
+            return;
+        };
+
+        let rescan = async {
+            let session_id = analyzer
+                .create_scan_session(watch.root.clone(), ScanType::Incremental, config)
+                .await?;
+            analyzer.start_scan_session(&session_id).await?;
+            anyhow::Ok(
+                analyzer
+                    .get_scan_session(&session_id)
+                    .await
+                    .and_then(|session| session.results)
+                    .and_then(|results| results.changes),
+            )
+        };
+        match rescan.await {
+            Ok(Some(changes)) => {
+                let changes = IndexChanges {
+                    added: changes.added,
+                    removed: changes.removed,
+                    modified: changes.modified,
+                    renamed: Vec::new(),
+                };
+                self.announce(watch, changes).await;
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Rescan of {} failed: {}", watch.root, e),
+        }
+    }
+
+    async fn announce(&self, watch: &RootWatch, changes: IndexChanges) {
+        if changes.is_empty() {
+            return;
+        }
+        *watch.last_change.lock().unwrap() = Some(Utc::now());
+
+        let totals = self
+            .storage
+            .read()
+            .await
+            .index
+            .get(&watch.root)
+            .map(|indexed| (indexed.record.total_files, indexed.record.total_size));
+        let (total_files, total_size) = totals.unwrap_or_default();
+        let message = serde_json::json!({
+            "root": watch.root,
+            "added": changes.added,
+            "removed": changes.removed,
+            "modified": changes.modified,
+            "renamed": changes.renamed,
+            "total_files": total_files,
+            "total_size": total_size,
+        });
+        if let Err(e) = self
+            .websocket_manager
+            .broadcast_message("index_changed".to_string(), message)
+            .await
+        {
+            tracing::debug!("No one listening for index changes: {}", e);
+        }
+    }
+}
+
+/// The watch limit was hit; the root has to be polled
+struct LimitReached;
+
+/// Reads the events of one root on its own thread and works out the changes
+struct RootWatcher {
+    backend: Box<dyn WatchBackend>,
+    state: Arc<RootWatch>,
+    config: ScanConfig,
+    rules: ExclusionRules,
+    root_device: Option<u64>,
+    max_watches: usize,
+}
+
+impl RootWatcher {
+    fn new(
+        backend: Box<dyn WatchBackend>,
+        state: Arc<RootWatch>,
+        config: ScanConfig,
+        rules: ExclusionRules,
+        max_watches: usize,
+    ) -> Self {
+        let root = Path::new(&state.root);
+        let root_device = std::fs::metadata(root)
+            .ok()
+            .and_then(|metadata| crate::file_system::identity_of(root, &metadata))
+            .map(|identity| identity.device);
+        Self {
+            backend,
+            state,
+            config,
+            rules,
+            root_device,
+            max_watches,
+        }
+    }
+
+    fn run(mut self, coalesce: Duration, tx: mpsc::UnboundedSender<Update>) {
+        let mut pending = BTreeSet::new();
+        let mut deadline: Option<Instant> = None;
+
+        while !self.state.is_stopped() {
+            let timeout = deadline.map_or(READ_TIMEOUT, |deadline| {
+                deadline
+                    .saturating_duration_since(Instant::now())
+                    .min(READ_TIMEOUT)
+            });
+            let events = match self.backend.read_events(timeout) {
+                Ok(events) => events,
+                Err(e) => {
+                    tracing::error!("Watching {} failed: {}", self.state.root, e);
+                    tx.send(Update::Fallback).ok();
+                    return;
+                }
+            };
+
+            let mut overflow = false;
+            for event in events {
+                match event {
+                    WatchEvent::Changed(path) => {
+                        deadline.get_or_insert_with(|| Instant::now() + coalesce);
+                        pending.insert(path);
+                    }
+                    WatchEvent::Overflow => overflow = true,
+                }
+            }
+
+            let update = if overflow {
+                // Watch whatever appeared meanwhile and let a rescan find the changes
+                tracing::warn!("Lost events under {}, rescanning", self.state.root);
+                pending.clear();
+                deadline = None;
+                let root = PathBuf::from(&self.state.root);
+                match self.watch_tree(&root, None) {
+                    Ok(()) => Update::Overflow,
+                    Err(LimitReached) => Update::Fallback,
+                }
+            } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                deadline = None;
+                match self.changes(std::mem::take(&mut pending)) {
+                    Ok(changes) if changes.is_empty() => continue,
+                    Ok(changes) => Update::Changes(changes),
+                    Err(LimitReached) => Update::Fallback,
+                }
+            } else {
+                continue;
+            };
+
+            let fallback = matches!(update, Update::Fallback);
+            if tx.send(update).is_err() || fallback {
+                return;
+            }
+        }
+    }
+
+    /// What changed at each path touched, looking at what is there now
+    fn changes(&mut self, paths: BTreeSet<PathBuf>) -> Result<Vec<FileChange>, LimitReached> {
+        let mut changes = BTreeMap::new(); // By path, as each path may change once per batch
+        for path in paths {
+            let Some(metadata) = self.metadata(&path) else {
+                changes.insert(
+                    path.clone(),
+                    FileChange::Remove(path_encoding::encode_path(&path)),
+                );
+                continue;
+            };
+
+            if metadata.is_dir() {
+                if !self.backend.is_watched(&path) && self.is_indexed(&path, &metadata) {
+                    self.watch_tree(&path, Some(&mut changes))?;
+                }
+            } else if metadata.is_file() {
+                let change = match self.is_indexed(&path, &metadata) {
+                    true => FileChange::Upsert(Box::new(crate::file_system::file_info(
+                        &path, &metadata,
+                    ))),
+                    false => FileChange::Remove(path_encoding::encode_path(&path)), // No longer within the scan's limits
+                };
+                changes.insert(path, change);
+            }
+        }
+        Ok(changes.into_values().collect())
+    }
+
+    /// Watch `dir` and every directory below it, collecting their files into `changes`
+    fn watch_tree(
+        &mut self,
+        dir: &Path,
+        mut changes: Option<&mut BTreeMap<PathBuf, FileChange>>,
+    ) -> Result<(), LimitReached> {
+        let follow_symlinks = self.config.follow_symlinks;
+        let mut entries = walkdir::WalkDir::new(dir)
+            .follow_links(follow_symlinks)
+            .into_iter();
+
+        while let Some(entry) = entries.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !self.is_indexed(entry.path(), &metadata) {
+                if metadata.is_dir() {
+                    entries.skip_current_dir();
+                }
+                continue;
+            }
+
+            if metadata.is_dir() {
+                self.add_watch(entry.path())?;
+            } else if let (true, Some(changes)) = (metadata.is_file(), changes.as_deref_mut()) {
+                changes.insert(
+                    entry.path().to_path_buf(),
+                    FileChange::Upsert(Box::new(crate::file_system::file_info(
+                        entry.path(),
+                        &metadata,
+                    ))),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn add_watch(&mut self, dir: &Path) -> Result<(), LimitReached> {
+        if self.backend.is_watched(dir) {
+            return Ok(());
+        }
+        if self.backend.watched() >= self.max_watches {
+            tracing::warn!(
+                "{} needs more than {} watches, polling instead",
+                self.state.root,
+                self.max_watches
+            );
+            return Err(LimitReached);
+        }
+
+        match self.backend.watch(dir) {
+            Ok(()) => {}
+            Err(WatchError::LimitReached) => {
+                tracing::warn!(
+                    "System watch limit reached under {}, polling instead",
+                    self.state.root
+                );
+                return Err(LimitReached);
+            }
+            // Most likely removed already; its parent reports that
+            Err(WatchError::Io(e)) => tracing::debug!("Could not watch {}: {}", dir.display(), e),
+        }
+        self.state
+            .watched
+            .store(self.backend.watched(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Metadata of what is at `path` now, following symlinks only if the scan did
+    fn metadata(&self, path: &Path) -> Option<std::fs::Metadata> {
+        let metadata = std::fs::symlink_metadata(path).ok()?;
+        if metadata.file_type().is_symlink() && self.config.follow_symlinks {
+            return std::fs::metadata(path).ok();
+        }
+        Some(metadata)
+    }
+
+    /// Whether the scan would have included what is at `path`
+    fn is_indexed(&self, path: &Path, metadata: &std::fs::Metadata) -> bool {
+        if metadata.file_type().is_symlink() {
+            return false;
+        }
+        let encoded = path_encoding::encode_path(path);
+        if DiskAnalyzer::should_exclude_file(&encoded, metadata.is_dir(), &self.rules, &self.config)
+        {
+            return encoded == self.state.root; // The root itself may be hidden
+        }
+
+        if metadata.is_dir() {
+            !self.config.same_filesystem
+                || crate::file_system::identity_of(path, metadata).map(|identity| identity.device)
+                    == self.root_device
+        } else {
+            DiskAnalyzer::within_size_limits(metadata.len(), &self.config)
+                && DiskAnalyzer::within_depth_limit(&self.state.root, &encoded, &self.config)
+        }
+    }
+}
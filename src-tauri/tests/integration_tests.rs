@@ -34,6 +34,8 @@ mod integration_tests {
             quick_hash_threshold: 1024 * 1024,
             duplicate_strategy: DuplicateStrategy::NameAndSize,
             large_file_threshold: 100 * 1024 * 1024,
+            same_filesystem: false,
+            use_ignore_files: false,
         };
 
         // Create session
@@ -85,6 +87,12 @@ mod integration_tests {
                 is_directory: false,
                 extension: Some("txt".to_string()),
                 hash: None,
+                inode: None,
+                device: None,
+                links: None,
+                allocated_size: None,
+                generation: None,
+                display_path: None,
             },
             FileInfo {
                 path: "/test/file2.txt".to_string(),
@@ -95,6 +103,12 @@ mod integration_tests {
                 is_directory: false,
                 extension: Some("txt".to_string()),
                 hash: None,
+                inode: None,
+                device: None,
+                links: None,
+                allocated_size: None,
+                generation: None,
+                display_path: None,
             },
             FileInfo {
                 path: "/test/unique.txt".to_string(),
@@ -105,6 +119,12 @@ mod integration_tests {
                 is_directory: false,
                 extension: Some("txt".to_string()),
                 hash: None,
+                inode: None,
+                device: None,
+                links: None,
+                allocated_size: None,
+                generation: None,
+                display_path: None,
             },
         ];
 
@@ -135,6 +155,8 @@ mod integration_tests {
             quick_hash_threshold: 1024 * 1024,
             duplicate_strategy: DuplicateStrategy::NameAndSize,
             large_file_threshold: 50 * 1024 * 1024, // 50MB threshold for testing
+            same_filesystem: false,
+            use_ignore_files: false,
         };
 
         let files = vec![
@@ -147,6 +169,12 @@ mod integration_tests {
                 is_directory: false,
                 extension: Some("txt".to_string()),
                 hash: None,
+                inode: None,
+                device: None,
+                links: None,
+                allocated_size: None,
+                generation: None,
+                display_path: None,
             },
             FileInfo {
                 path: "/test/large_video.mp4".to_string(),
@@ -157,6 +185,12 @@ mod integration_tests {
                 is_directory: false,
                 extension: Some("mp4".to_string()),
                 hash: None,
+                inode: None,
+                device: None,
+                links: None,
+                allocated_size: None,
+                generation: None,
+                display_path: None,
             },
             FileInfo {
                 path: "/test/huge_archive.zip".to_string(),
@@ -167,6 +201,12 @@ mod integration_tests {
                 is_directory: false,
                 extension: Some("zip".to_string()),
                 hash: None,
+                inode: None,
+                device: None,
+                links: None,
+                allocated_size: None,
+                generation: None,
+                display_path: None,
             },
         ];
 
@@ -237,6 +277,8 @@ mod integration_tests {
             quick_hash_threshold: 1024 * 1024,
             duplicate_strategy: DuplicateStrategy::SmartDetection,
             large_file_threshold: 100 * 1024 * 1024,
+            same_filesystem: false,
+            use_ignore_files: false,
         };
 
         let files = vec![
@@ -250,6 +292,12 @@ mod integration_tests {
                 is_directory: false,
                 extension: Some("txt".to_string()),
                 hash: None,
+                inode: None,
+                device: None,
+                links: None,
+                allocated_size: None,
+                generation: None,
+                display_path: None,
             },
             FileInfo {
                 path: "/test/small2.txt".to_string(),
@@ -260,6 +308,12 @@ mod integration_tests {
                 is_directory: false,
                 extension: Some("txt".to_string()),
                 hash: None,
+                inode: None,
+                device: None,
+                links: None,
+                allocated_size: None,
+                generation: None,
+                display_path: None,
             },
             // Large files (should use name+size since hashing disabled)
             FileInfo {
@@ -271,6 +325,12 @@ mod integration_tests {
                 is_directory: false,
                 extension: Some("bin".to_string()),
                 hash: None,
+                inode: None,
+                device: None,
+                links: None,
+                allocated_size: None,
+                generation: None,
+                display_path: None,
             },
             FileInfo {
                 path: "/test/large2.bin".to_string(),
@@ -281,6 +341,12 @@ mod integration_tests {
                 is_directory: false,
                 extension: Some("bin".to_string()),
                 hash: None,
+                inode: None,
+                device: None,
+                links: None,
+                allocated_size: None,
+                generation: None,
+                display_path: None,
             },
         ];
 
@@ -1353,7 +1353,7 @@ const DuplicatesView = ({ params }: DuplicatesViewProps = { params: undefined })
                 setIsDeleting(true)
                 try {
                   const filesToDelete = getSelectedFilesForDeletion()
-                  const result = await deleteDuplicatesBatch(filesToDelete)
+                  const result = await deleteDuplicatesBatch(filesToDelete, true)
                   
                   toast({
                     title: "Archivos eliminados",
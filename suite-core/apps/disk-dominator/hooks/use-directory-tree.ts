@@ -0,0 +1,104 @@
+import { useState, useCallback } from 'react';
+import { invoke } from './use-tauri';
+import { SizeBasis } from './use-large-files';
+
+export interface DirectoryNode {
+  path: string;
+  name: string;
+  size: number;
+  file_count: number;
+  is_directory: boolean;
+  is_other: boolean; // Lumps together children too small to show
+  has_more: boolean; // Has children below the requested depth
+  children: DirectoryNode[];
+}
+
+export interface TreemapRect {
+  path: string;
+  name: string;
+  size: number;
+  depth: number;
+  is_directory: boolean;
+  is_other: boolean;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+export interface DirectoryTreeOptions {
+  depth?: number;
+  minFraction?: number;
+  sizeBasis?: SizeBasis;
+}
+
+export const useDirectoryTree = () => {
+  const [tree, setTree] = useState<DirectoryNode | null>(null);
+  const [treemap, setTreemap] = useState<TreemapRect[]>([]);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const run = useCallback(async <T,>(command: string, args: Record<string, unknown>) => {
+    try {
+      setLoading(true);
+      setError(null);
+      return await invoke<T>(command, args);
+    } catch (err) {
+      const errorMsg = err instanceof Error ? err.message : 'Failed to load directory tree';
+      setError(errorMsg);
+      throw new Error(errorMsg);
+    } finally {
+      setLoading(false);
+    }
+  }, []);
+
+  const loadTree = useCallback(async (root: string, options: DirectoryTreeOptions = {}) => {
+    const result = await run<DirectoryNode>('get_directory_tree', {
+      root,
+      depth: options.depth,
+      minFraction: options.minFraction,
+      sizeBasis: options.sizeBasis,
+    });
+    setTree(result);
+    return result;
+  }, [run]);
+
+  const drillDown = useCallback(async (path: string, options: DirectoryTreeOptions = {}) => {
+    const result = await run<DirectoryNode>('get_directory_subtree', {
+      path,
+      depth: options.depth,
+      minFraction: options.minFraction,
+      sizeBasis: options.sizeBasis,
+    });
+    setTree(result);
+    return result;
+  }, [run]);
+
+  const loadTreemap = useCallback(async (
+    path: string,
+    width: number,
+    height: number,
+    options: DirectoryTreeOptions = {}
+  ) => {
+    const result = await run<TreemapRect[]>('get_treemap_layout', {
+      path,
+      width,
+      height,
+      depth: options.depth,
+      minFraction: options.minFraction,
+      sizeBasis: options.sizeBasis,
+    });
+    setTreemap(result);
+    return result;
+  }, [run]);
+
+  return {
+    tree,
+    treemap,
+    loading,
+    error,
+    loadTree,
+    drillDown,
+    loadTreemap,
+  };
+};
@@ -25,6 +25,13 @@ export interface ScanRequest {
   exclude_patterns?: string[];
   include_hidden?: boolean;
   calculate_hashes?: boolean;
+  follow_symlinks?: boolean;
+  same_filesystem?: boolean;
+  max_depth?: number;
+  min_file_size?: number;
+  max_file_size?: number;
+  use_ignore_files?: boolean;
+  profile_id?: string; // Saved profile supplying the settings not given here
 }
 
 export interface ScanResponse {
@@ -42,6 +49,12 @@ export interface FileInfo {
   is_directory: boolean;
   extension?: string;
   hash?: string;
+  inode?: number;
+  device?: number;
+  links?: number; // Hard links to the same data
+  allocated_size?: number; // Size on disk
+  generation?: number; // Tells apart files that reuse an inode
+  display_path?: string; // Readable path, set when `path` is not valid Unicode
 }
 
 export interface DuplicateGroup {
@@ -49,6 +62,7 @@ export interface DuplicateGroup {
   files: FileInfo[];
   total_size: number;
   potential_savings: number;
+  hard_linked: string[]; // Paths sharing data with an earlier member; deleting them frees nothing
 }
 
 export interface ScanResults {
@@ -57,9 +71,35 @@ export interface ScanResults {
   large_files: FileInfo[];
   total_files: number;
   total_size: number;
+  total_allocated_size: number; // Size on disk
   scan_duration: number;
 }
 
+export type ScanErrorKind =
+  | 'permission_denied'
+  | 'path_vanished'
+  | 'broken_symlink'
+  | 'path_too_long'
+  | 'io';
+
+export interface ScanError {
+  kind: ScanErrorKind;
+  path: string;
+  message: string;
+}
+
+export interface ScanErrorReport {
+  total: number;
+  truncated: boolean; // More errors happened than are listed
+  summaries: Array<{
+    kind: ScanErrorKind;
+    count: number;
+    suggested_action: 'retry_elevated' | 'exclude_path' | null;
+    example_paths: string[];
+  }>;
+  errors: ScanError[];
+}
+
 export interface ScanSession {
   id: string;
   disk_path: string;
@@ -123,6 +163,13 @@ export const useDiskScanner = () => {
         exclude_patterns: options?.exclude_patterns,
         include_hidden: options?.include_hidden,
         calculate_hashes: options?.calculate_hashes,
+        follow_symlinks: options?.follow_symlinks,
+        same_filesystem: options?.same_filesystem,
+        max_depth: options?.max_depth,
+        min_file_size: options?.min_file_size,
+        max_file_size: options?.max_file_size,
+        use_ignore_files: options?.use_ignore_files,
+        profile_id: options?.profile_id,
       };
 
       const response = await invoke<ScanResponse>('scan_disk_new', { 
@@ -186,6 +233,11 @@ export const useDiskScanner = () => {
     }
   }, [sessionId]);
 
+  const getScanErrors = useCallback(async () => {
+    if (!sessionId) return null;
+    return invoke<ScanErrorReport>('get_scan_errors', { sessionId });
+  }, [sessionId]);
+
   return {
     scanning,
     sessionId,
@@ -196,5 +248,6 @@ export const useDiskScanner = () => {
     pauseScan,
     resumeScan,
     cancelScan,
+    getScanErrors,
   };
 };
\ No newline at end of file
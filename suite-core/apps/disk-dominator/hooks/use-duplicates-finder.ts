@@ -22,6 +22,7 @@ export interface DuplicateCopy {
   is_original: boolean;
   keep_suggestion: boolean;
   metadata?: FileMetadata;
+  hard_linked?: boolean; // Shares its data with an earlier copy
 }
 
 export interface FileMetadata {
@@ -112,6 +113,7 @@ export const useDuplicatesFinder = () => {
           is_original: idx === 0,
           keep_suggestion: idx === 0,
           metadata: null,
+          hard_linked: group.hard_linked?.includes(file.path) ?? false,
         })) || [],
       }));
       
@@ -132,11 +134,26 @@ export const useDuplicatesFinder = () => {
     }
   }, []);
 
-  const deleteDuplicatesBatch = useCallback(async (fileIds: string[], moveToTrash = true) => {
+  const deleteDuplicatesBatch = useCallback(async (
+    fileIds: string[],
+    moveToTrash = true
+  ) => {
     try {
+      // Each file is compared byte for byte with a copy of its group that stays
+      const deleting = new Set(fileIds);
+      const keep: Record<string, string> = {};
+      for (const group of duplicates) {
+        const kept = group.copies.find(copy => !deleting.has(copy.id));
+        if (!kept) continue;
+        for (const copy of group.copies) {
+          if (deleting.has(copy.id)) keep[copy.id] = kept.id;
+        }
+      }
+
       const result = await invoke<DeleteBatchResult>('delete_duplicates_batch', {
         file_ids: fileIds,
         move_to_trash: moveToTrash,
+        keep,
       });
       
       // Refresh duplicates list
@@ -0,0 +1,49 @@
+import { useState, useCallback } from 'react';
+import { invoke } from './use-tauri';
+
+export interface HashCacheStats {
+  hits: number;
+  misses: number;
+  hit_rate: number; // Share of lookups answered by the cache, 0 to 1
+}
+
+export interface HashCacheStatus {
+  entries: number;
+  max_entries: number; // 0 when the cache is turned off
+  stats: HashCacheStats; // Since the app started
+}
+
+export const useHashCache = () => {
+  const [status, setStatus] = useState<HashCacheStatus | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const run = useCallback(async (command: string) => {
+    try {
+      setLoading(true);
+      setError(null);
+      const result = await invoke<HashCacheStatus>(command);
+      setStatus(result);
+      return result;
+    } catch (err) {
+      const errorMsg = err instanceof Error ? err.message : 'Failed to update hash cache';
+      setError(errorMsg);
+      throw new Error(errorMsg);
+    } finally {
+      setLoading(false);
+    }
+  }, []);
+
+  const loadStatus = useCallback(() => run('get_hash_cache_status'), [run]);
+
+  // The next scans hash every file again
+  const clearCache = useCallback(() => run('clear_hash_cache'), [run]);
+
+  return {
+    status,
+    loading,
+    error,
+    loadStatus,
+    clearCache,
+  };
+};
@@ -0,0 +1,69 @@
+import { useState, useCallback } from 'react';
+import { invoke } from './use-tauri';
+
+export type WatchMode = 'live' | 'polling';
+
+export interface WatchStatus {
+  root: string;
+  mode: WatchMode; // 'polling' when the root is rescanned at an interval instead
+  watched_directories: number;
+  last_change?: string; // Last time changes were applied to the index
+}
+
+// Payload of the `index_changed` WebSocket message
+export interface IndexChangedEvent {
+  root: string;
+  added: string[];
+  removed: string[];
+  modified: string[];
+  renamed: { from: string; to: string }[];
+  total_files: number;
+  total_size: number;
+}
+
+export const useIndexWatcher = () => {
+  const [statuses, setStatuses] = useState<WatchStatus[]>([]);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const run = useCallback(async <T,>(command: string, args: Record<string, unknown> = {}) => {
+    try {
+      setLoading(true);
+      setError(null);
+      return await invoke<T>(command, args);
+    } catch (err) {
+      const errorMsg = err instanceof Error ? err.message : 'Failed to update watched roots';
+      setError(errorMsg);
+      throw new Error(errorMsg);
+    } finally {
+      setLoading(false);
+    }
+  }, []);
+
+  const refreshStatus = useCallback(async () => {
+    const result = await run<WatchStatus[]>('get_watch_status');
+    setStatuses(result);
+    return result;
+  }, [run]);
+
+  const watchRoot = useCallback(async (root: string) => {
+    const status = await run<WatchStatus>('watch_root', { root });
+    setStatuses(prev => [...prev.filter(s => s.root !== root), status]);
+    return status;
+  }, [run]);
+
+  const unwatchRoot = useCallback(async (root: string) => {
+    const wasWatched = await run<boolean>('unwatch_root', { root });
+    setStatuses(prev => prev.filter(s => s.root !== root));
+    return wasWatched;
+  }, [run]);
+
+  return {
+    statuses,
+    loading,
+    error,
+    refreshStatus,
+    watchRoot,
+    unwatchRoot,
+  };
+};
@@ -7,6 +7,7 @@ export interface LargeFileInfo {
   path: string;
   name: string;
   size: number;
+  allocated_size: number; // Size on disk
   file_type: string;
   extension: string;
   created: number;
@@ -25,9 +26,14 @@ export interface LargeFileFilter {
   extensions?: string[];
   sort_by?: string;
   sort_order?: string;
+  size_basis?: SizeBasis;
 }
 
+// Apparent size is the length of the contents; allocated is the space taken on disk
+export type SizeBasis = 'apparent' | 'allocated';
+
 export interface SpaceAnalysis {
+  size_basis: SizeBasis;
   total_size: number;
   file_count: number;
   by_type: Record<string, {
@@ -44,6 +50,11 @@ export interface SpaceAnalysis {
     huge: number;
     gigantic: number;
   };
+  slack: {
+    total: number;
+    small_files: number; // Slack of files under 1MB
+    small_file_count: number;
+  };
 }
 
 export interface CompressionOptions {
@@ -0,0 +1,71 @@
+import { useState, useCallback } from 'react';
+import { invoke } from './use-tauri';
+import { ScanConfig } from './use-scan-sessions';
+
+export interface ScanProfile {
+  id: string; // Empty for a profile that was never saved
+  name: string;
+  description: string;
+  builtin: boolean; // Shipped preset; save a copy with an empty id to change it
+  roots: string[];
+  scan_type: 'Quick' | 'Deep' | 'Custom' | 'Incremental';
+  config: ScanConfig;
+}
+
+export interface ProfileScanResponse {
+  session_id: string;
+  message: string;
+  success: boolean;
+}
+
+export const useScanProfiles = () => {
+  const [profiles, setProfiles] = useState<ScanProfile[]>([]);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const run = useCallback(async <T,>(command: string, args: Record<string, unknown> = {}) => {
+    try {
+      setLoading(true);
+      setError(null);
+      return await invoke<T>(command, args);
+    } catch (err) {
+      const errorMsg = err instanceof Error ? err.message : 'Failed to update scan profiles';
+      setError(errorMsg);
+      throw new Error(errorMsg);
+    } finally {
+      setLoading(false);
+    }
+  }, []);
+
+  const loadProfiles = useCallback(async () => {
+    const result = await run<ScanProfile[]>('list_scan_profiles');
+    setProfiles(result);
+    return result;
+  }, [run]);
+
+  const saveProfile = useCallback(async (profile: ScanProfile) => {
+    const saved = await run<ScanProfile>('save_scan_profile', { profile });
+    setProfiles(await run<ScanProfile[]>('list_scan_profiles'));
+    return saved;
+  }, [run]);
+
+  const deleteProfile = useCallback(async (profileId: string) => {
+    const deleted = await run<boolean>('delete_scan_profile', { profileId });
+    setProfiles(prev => prev.filter(p => p.id !== profileId));
+    return deleted;
+  }, [run]);
+
+  const scanWithProfile = useCallback(async (profileId: string) => {
+    return run<ProfileScanResponse[]>('scan_with_profile', { profileId });
+  }, [run]);
+
+  return {
+    profiles,
+    loading,
+    error,
+    loadProfiles,
+    saveProfile,
+    deleteProfile,
+    scanWithProfile,
+  };
+};
@@ -24,6 +24,10 @@ export interface ScanConfig {
   max_file_size?: number;
   calculate_hashes: boolean;
   quick_hash_threshold: number;
+  duplicate_strategy: 'HashOnly' | 'NameAndSize' | 'NameSizePartialHash' | 'SmartDetection';
+  large_file_threshold: number;
+  same_filesystem?: boolean;
+  use_ignore_files?: boolean;
 }
 
 export type ScanSessionStatus = 
@@ -41,6 +45,18 @@ export interface DualScanProgress {
   current_phase: string;
   estimated_total_time: number;
   elapsed_time: number;
+  dedupe_stage?: DedupeStage | null; // Stage of the duplicate search in the deep phase
+}
+
+export type DedupeStage = 'size' | 'name' | 'blocks' | 'full';
+
+export interface DedupeStageReport {
+  stage: DedupeStage;
+  files_in: number;
+  candidates: number; // Files still possibly duplicated afterwards
+  groups: number;
+  bytes_read: number; // File contents read by this stage, without cache hits
+  bytes_saved: number; // Contents of the files it was given that no stage has to read
 }
 
 export interface ScanProgress {
@@ -58,6 +74,7 @@ export interface ScanResults {
   total_files: number;
   total_size: number;
   scan_duration: number;
+  dedupe?: DedupeStageReport[]; // Stages of the duplicate search, in order
 }
 
 export const useScanSessions = () => {
@@ -0,0 +1,53 @@
+import { useState, useCallback } from 'react';
+import { invoke } from './use-tauri';
+
+export interface ThrottleSettings {
+  max_bytes_per_sec: number; // File contents read for hashing; 0 is unlimited
+  max_files_per_sec: number; // Entries walked plus files hashed; 0 is unlimited
+  idle_mode: boolean; // Back off while other programs use the disks
+  busy_bytes_per_sec: number; // Disk traffic of other programs that counts as busy
+}
+
+export interface ThrottleStatus {
+  settings: ThrottleSettings;
+  backing_off: boolean;
+  io_priority_supported: boolean;
+}
+
+export const useScanThrottle = () => {
+  const [status, setStatus] = useState<ThrottleStatus | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const run = useCallback(async (command: string, args: Record<string, unknown> = {}) => {
+    try {
+      setLoading(true);
+      setError(null);
+      const result = await invoke<ThrottleStatus>(command, args);
+      setStatus(result);
+      return result;
+    } catch (err) {
+      const errorMsg = err instanceof Error ? err.message : 'Failed to update scan throttle';
+      setError(errorMsg);
+      throw new Error(errorMsg);
+    } finally {
+      setLoading(false);
+    }
+  }, []);
+
+  const loadThrottle = useCallback(() => run('get_scan_throttle'), [run]);
+
+  // Running scans follow the new limits right away
+  const setThrottle = useCallback(
+    (settings: ThrottleSettings) => run('set_scan_throttle', { settings }),
+    [run]
+  );
+
+  return {
+    status,
+    loading,
+    error,
+    loadThrottle,
+    setThrottle,
+  };
+};
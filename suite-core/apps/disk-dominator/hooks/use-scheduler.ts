@@ -0,0 +1,100 @@
+import { useState, useCallback } from 'react';
+import { invoke } from './use-tauri';
+
+export type JobSchedule =
+  | { kind: 'interval'; every_secs: number }
+  | { kind: 'cron'; expression: string }; // minute hour day month weekday, local time
+
+export interface CleanupPolicy {
+  root: string;
+  patterns: string[]; // Gitignore-style patterns of the files to delete
+  older_than_days: number;
+  move_to_trash: boolean;
+}
+
+export type JobAction =
+  | { type: 'scan_profile'; profile_id: string }
+  | { type: 'refresh_duplicates'; roots: string[] } // Every scanned root when empty
+  | ({ type: 'cleanup' } & CleanupPolicy);
+
+export interface JobRun {
+  started_at: string;
+  finished_at: string;
+  trigger: 'scheduled' | 'missed' | 'manual';
+  status: 'succeeded' | 'failed' | 'skipped';
+  message: string;
+}
+
+export interface ScheduledJob {
+  id: string; // Empty until the job is created
+  name: string;
+  schedule: JobSchedule;
+  action: JobAction;
+  paused: boolean;
+  missed_runs: 'skip' | 'run_once'; // Runs that came due while the app was closed
+  jitter_secs: number;
+  next_run: string | null;
+  history: JobRun[]; // Most recent first
+  running: boolean;
+}
+
+export const useScheduler = () => {
+  const [jobs, setJobs] = useState<ScheduledJob[]>([]);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const run = useCallback(async <T,>(command: string, args: Record<string, unknown> = {}) => {
+    try {
+      setLoading(true);
+      setError(null);
+      return await invoke<T>(command, args);
+    } catch (err) {
+      const errorMsg = err instanceof Error ? err.message : 'Failed to update scheduled jobs';
+      setError(errorMsg);
+      throw new Error(errorMsg);
+    } finally {
+      setLoading(false);
+    }
+  }, []);
+
+  const loadJobs = useCallback(async () => {
+    const result = await run<ScheduledJob[]>('list_scheduled_jobs');
+    setJobs(result);
+    return result;
+  }, [run]);
+
+  const createJob = useCallback(async (job: ScheduledJob) => {
+    const created = await run<ScheduledJob>('create_scheduled_job', { job });
+    setJobs(prev => [...prev, created]);
+    return created;
+  }, [run]);
+
+  const pauseJob = useCallback(async (jobId: string, paused: boolean) => {
+    const updated = await run<ScheduledJob>('pause_scheduled_job', { jobId, paused });
+    setJobs(prev => prev.map(j => (j.id === jobId ? updated : j)));
+    return updated;
+  }, [run]);
+
+  // The run shows up in the job's history once it finishes
+  const runJobNow = useCallback(async (jobId: string) => {
+    await run<void>('run_scheduled_job_now', { jobId });
+    setJobs(prev => prev.map(j => (j.id === jobId ? { ...j, running: true } : j)));
+  }, [run]);
+
+  const deleteJob = useCallback(async (jobId: string) => {
+    const deleted = await run<boolean>('delete_scheduled_job', { jobId });
+    setJobs(prev => prev.filter(j => j.id !== jobId));
+    return deleted;
+  }, [run]);
+
+  return {
+    jobs,
+    loading,
+    error,
+    loadJobs,
+    createJob,
+    pauseJob,
+    runJobNow,
+    deleteJob,
+  };
+};
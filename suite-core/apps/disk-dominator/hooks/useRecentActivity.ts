@@ -8,7 +8,8 @@ export type ActivityType =
   | 'files_deleted'
   | 'files_moved'
   | 'disk_organized'
-  | 'error_occurred';
+  | 'error_occurred'
+  | 'scheduled_job_run';
 
 export interface ActivityMetadata {
   size?: number;
@@ -137,6 +138,7 @@ export const useRecentActivity = (options: UseRecentActivityOptions = {}) => {
       files_moved: 'move',
       disk_organized: 'folder',
       error_occurred: 'alert-circle',
+      scheduled_job_run: 'clock',
     };
     return iconMap[type] || 'info';
   };
@@ -151,6 +153,7 @@ export const useRecentActivity = (options: UseRecentActivityOptions = {}) => {
       files_moved: 'text-purple-500',
       disk_organized: 'text-orange-500',
       error_occurred: 'text-red-600',
+      scheduled_job_run: 'text-indigo-500',
     };
     return colorMap[type] || 'text-gray-500';
   };
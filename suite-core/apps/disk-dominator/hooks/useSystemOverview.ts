@@ -11,6 +11,7 @@ export interface DiskSummary {
   percentage: number;
   file_system?: string;
   last_scanned?: string;
+  indexed_size?: number; // Size of the scanned files on this disk
 }
 
 export interface SystemOverview {